@@ -2,8 +2,8 @@ use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
 use hue::api::{
-    GroupedLightUpdate, LightUpdate, ResourceLink, RoomUpdate, Scene, SceneUpdate,
-    ZigbeeDeviceDiscoveryUpdate,
+    DeviceUpdate, GroupedLightUpdate, LightUpdate, ResourceLink, Room, RoomUpdate, Scene,
+    SceneUpdate, ZigbeeDeviceDiscoveryUpdate,
 };
 use hue::stream::HueStreamLightsV2;
 
@@ -31,8 +31,14 @@ pub enum BackendRequest {
 
     GroupedLightUpdate(ResourceLink, GroupedLightUpdate),
 
+    /// Room created in the Hue app. The backend owning its devices creates it.
+    RoomCreate(ResourceLink, Room),
     RoomUpdate(ResourceLink, RoomUpdate),
 
+    DeviceUpdate(ResourceLink, DeviceUpdate),
+    /// Install the update announced by a `device_software_update`
+    SoftwareUpdateInstall(ResourceLink),
+
     Delete(ResourceLink),
 
     EntertainmentStart(Uuid),
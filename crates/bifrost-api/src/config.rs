@@ -59,6 +59,8 @@ pub struct HassServer {
     pub url: Url,
     pub token_env: Option<String>,
     pub poll_interval_secs: Option<NonZeroU32>,
+    pub poll_only: Option<bool>,
+    pub streaming_fps: Option<NonZeroU32>,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize, Default, Eq, PartialEq)]
@@ -434,6 +434,28 @@ pub enum LightSignal {
     Alternating,
 }
 
+#[derive(Copy, Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum LightAlertAction {
+    Breathe,
+}
+
+#[derive(Copy, Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct LightAlertUpdate {
+    pub action: LightAlertAction,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct LightSignalingUpdate {
+    pub signal: LightSignal,
+    /// Duration in milliseconds (ignored for `no_signal`)
+    #[serde(default)]
+    pub duration: u32,
+    /// Colors for `on_off_color` (one) and `alternating` (two)
+    #[serde(default, rename = "color", skip_serializing_if = "Vec::is_empty")]
+    pub colors: Vec<ColorUpdate>,
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
 #[serde(rename_all = "snake_case")]
 pub enum LightDynamicsStatus {
@@ -663,6 +685,10 @@ pub struct LightUpdate {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub identify: Option<DeviceIdentifyUpdate>,
     #[serde(skip_serializing_if = "Option::is_none")]
+    pub alert: Option<LightAlertUpdate>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub signaling: Option<LightSignalingUpdate>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub timed_effects: Option<LightTimedEffectsUpdate>,
 }
 
@@ -839,6 +865,13 @@ impl ColorGamut {
             y: 0.027_116,
         },
     };
+
+    /// The sRGB primaries, for lights that are driven with RGB or HS values
+    pub const SRGB: Self = Self {
+        red: XY { x: 0.64, y: 0.33 },
+        green: XY { x: 0.30, y: 0.60 },
+        blue: XY { x: 0.15, y: 0.06 },
+    };
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
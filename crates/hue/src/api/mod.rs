@@ -33,16 +33,19 @@ pub use entertainment_config::{
 pub use grouped_light::{GroupedLight, GroupedLightUpdate};
 pub use light::{
     ColorGamut, ColorTemperature, ColorTemperatureUpdate, ColorUpdate, Delta, Dimming,
-    DimmingUpdate, GamutType, Light, LightAlert, LightColor, LightDynamics, LightDynamicsStatus,
-    LightEffect, LightEffectActionUpdate, LightEffectParameters, LightEffectStatus,
-    LightEffectValues, LightEffects, LightEffectsV2, LightEffectsV2Update, LightFunction,
-    LightGradient, LightGradientMode, LightGradientPoint, LightGradientUpdate, LightMetadata,
-    LightMode, LightPowerup, LightPowerupColor, LightPowerupDimming, LightPowerupOn,
-    LightPowerupPreset, LightProductData, LightSignal, LightSignaling, LightTimedEffect,
-    LightTimedEffects, LightTimedEffectsUpdate, LightUpdate, MirekSchema, On,
+    DimmingUpdate, GamutType, Light, LightAlert, LightAlertAction, LightAlertUpdate, LightColor,
+    LightDynamics, LightDynamicsStatus, LightEffect, LightEffectActionUpdate,
+    LightEffectParameters, LightEffectStatus, LightEffectValues, LightEffects, LightEffectsUpdate,
+    LightEffectsV2, LightEffectsV2Update, LightFunction, LightGradient, LightGradientMode,
+    LightGradientPoint, LightGradientUpdate, LightMetadata, LightMode, LightPowerup,
+    LightPowerupColor, LightPowerupDimming, LightPowerupOn, LightPowerupPreset, LightProductData,
+    LightSignal, LightSignaling, LightSignalingUpdate, LightTimedEffect, LightTimedEffects,
+    LightTimedEffectsUpdate, LightUpdate, MirekSchema, On,
 };
 pub use resource::{RType, ResourceLink, ResourceRecord};
-pub use room::{Room, RoomArchetype, RoomMetadata, RoomMetadataUpdate, RoomUpdate};
+pub use room::{
+    Room, RoomArchetype, RoomMetadata, RoomMetadataUpdate, RoomUpdate, Zone, ZoneUpdate,
+};
 pub use scene::{
     Scene, SceneAction, SceneActionElement, SceneActive, SceneMetadata, SceneRecall, SceneStatus,
     SceneStatusEnum, SceneUpdate,
@@ -50,11 +53,12 @@ pub use scene::{
 use serde::ser::SerializeMap;
 pub use stream::HueStreamKey;
 pub use stubs::{
-    Bridge, BridgeHome, Button, ButtonData, ButtonMetadata, ButtonReport, DevicePower,
-    DeviceSoftwareUpdate, DollarRef, GeofenceClient, Geolocation, GroupedLightLevel, GroupedMotion,
-    Homekit, InternetConnectivity, InternetConnectivityStatus, LightLevel, Matter, Metadata,
-    MetadataUpdate, Motion, PrivateGroup, PublicImage, RelativeRotary, SmartScene, Taurus,
-    Temperature, TimeZone, ZigbeeConnectivity, ZigbeeConnectivityStatus, Zone,
+    BatteryState, Bridge, BridgeHome, Button, ButtonData, ButtonMetadata, ButtonReport, Contact,
+    ContactReport, ContactState, DevicePower, DeviceSoftwareUpdate, DeviceSoftwareUpdateUpdate,
+    DollarRef, GeofenceClient, Geolocation, GroupedLightLevel, GroupedMotion, Homekit,
+    InternetConnectivity, InternetConnectivityStatus, LightLevel, Matter, Metadata, MetadataUpdate,
+    Motion, PowerState, PrivateGroup, PublicImage, RelativeRotary, SmartScene, SoftwareUpdateState,
+    Taurus, Temperature, TimeZone, ZigbeeConnectivity, ZigbeeConnectivityStatus,
 };
 pub use update::Update;
 pub use zigbee_device_discovery::{
@@ -93,6 +97,7 @@ pub enum Resource {
     Bridge(Bridge),
     BridgeHome(BridgeHome),
     Button(Button),
+    Contact(Contact),
     Device(Device),
     DevicePower(DevicePower),
     DeviceSoftwareUpdate(DeviceSoftwareUpdate),
@@ -124,7 +129,6 @@ pub enum Resource {
 
     /* Unmapped variants */
     CameraMotion(Value),
-    Contact(Value),
     MatterFabric(Value),
     ServiceGroup(Value),
     Tamper(Value),
@@ -187,6 +191,7 @@ impl Resource {
             Self::Bridge(obj) => Some(obj.owner),
             Self::BridgeHome(_) => None,
             Self::Button(obj) => Some(obj.owner),
+            Self::Contact(obj) => Some(obj.owner),
             Self::Device(_) => None,
             Self::DevicePower(obj) => Some(obj.owner),
             Self::DeviceSoftwareUpdate(obj) => Some(obj.owner),
@@ -217,7 +222,6 @@ impl Resource {
 
             /* Unmapped variants */
             Self::CameraMotion(_) => None,
-            Self::Contact(_) => None,
             Self::MatterFabric(_) => None,
             Self::ServiceGroup(_) => None,
             Self::Tamper(_) => None,
@@ -233,6 +237,7 @@ impl Resource {
             RType::Bridge => Self::Bridge(from_value(obj)?),
             RType::BridgeHome => Self::BridgeHome(from_value(obj)?),
             RType::Button => Self::Button(from_value(obj)?),
+            RType::Contact => Self::Contact(from_value(obj)?),
             RType::Device => Self::Device(from_value(obj)?),
             RType::DevicePower => Self::DevicePower(from_value(obj)?),
             RType::DeviceSoftwareUpdate => Self::DeviceSoftwareUpdate(from_value(obj)?),
@@ -261,7 +266,6 @@ impl Resource {
             RType::ZigbeeDeviceDiscovery => Self::ZigbeeDeviceDiscovery(from_value(obj)?),
             RType::Zone => Self::Zone(from_value(obj)?),
             RType::CameraMotion => Self::CameraMotion(obj),
-            RType::Contact => Self::Contact(obj),
             RType::MatterFabric => Self::MatterFabric(obj),
             RType::ServiceGroup => Self::ServiceGroup(obj),
             RType::Tamper => Self::Tamper(obj),
@@ -325,6 +329,7 @@ resource_conversion_impl!(BehaviorScript);
 resource_conversion_impl!(Bridge);
 resource_conversion_impl!(BridgeHome);
 resource_conversion_impl!(Button);
+resource_conversion_impl!(Contact);
 resource_conversion_impl!(Device);
 resource_conversion_impl!(DevicePower);
 resource_conversion_impl!(DeviceSoftwareUpdate);
@@ -378,6 +383,11 @@ impl<'a> V1Reply<'a> {
         Self::new(format!("/groups/{id}/{path}"))
     }
 
+    #[must_use]
+    pub fn for_sensor_path(id: u32, path: &str) -> Self {
+        Self::new(format!("/sensors/{id}/{path}"))
+    }
+
     #[must_use]
     pub fn for_group(id: u32) -> Self {
         Self::new(format!("/groups/{id}"))
@@ -1,7 +1,11 @@
 use hex::FromHexError;
+use serde::{Deserialize, Serialize};
 
 use crate::error::{HueError, HueResult};
 
+/// Pre-shared key for entertainment streams. Serialized as a hex string.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(into = "String", try_from = "String")]
 pub struct HueStreamKey {
     key: [u8; Self::BYTE_SIZE],
 }
@@ -56,3 +60,38 @@ impl TryFrom<&str> for HueStreamKey {
         Ok(Self::new(key))
     }
 }
+
+impl TryFrom<String> for HueStreamKey {
+    type Error = HueError;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        Self::try_from(value.as_str())
+    }
+}
+
+impl From<HueStreamKey> for String {
+    fn from(value: HueStreamKey) -> Self {
+        value.to_hex()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use crate::api::HueStreamKey;
+
+    #[test]
+    fn serde_hex() {
+        let key = HueStreamKey::new(*b"0123456789abcdef");
+        let value = serde_json::to_value(key).unwrap();
+
+        assert_eq!(value, json!("30313233343536373839616263646566"));
+        assert_eq!(serde_json::from_value::<HueStreamKey>(value).unwrap(), key);
+    }
+
+    #[test]
+    fn serde_invalid() {
+        assert!(serde_json::from_value::<HueStreamKey>(json!("0123")).is_err());
+    }
+}
@@ -1,7 +1,7 @@
 use std::collections::BTreeSet;
 
 use chrono::{DateTime, Utc};
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize};
 use serde_json::Value;
 
 use crate::api::{DeviceArchetype, LightFunction, ResourceLink, SceneMetadata};
@@ -51,6 +51,52 @@ pub struct ButtonReport {
     pub event: String,
 }
 
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Contact {
+    pub owner: ResourceLink,
+    pub enabled: bool,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub contact_report: Option<ContactReport>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ContactReport {
+    #[serde(with = "date_format::utc_ms")]
+    pub changed: DateTime<Utc>,
+    pub state: ContactState,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ContactState {
+    Contact,
+    NoContact,
+}
+
+impl Contact {
+    #[must_use]
+    pub const fn new(owner: ResourceLink) -> Self {
+        Self {
+            owner,
+            enabled: true,
+            contact_report: None,
+        }
+    }
+
+    /// Report a new state, where `open` means there is no contact
+    pub fn report(&mut self, open: bool) {
+        let state = if open {
+            ContactState::NoContact
+        } else {
+            ContactState::Contact
+        };
+        self.contact_report = Some(ContactReport {
+            changed: Utc::now(),
+            state,
+        });
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct DollarRef {
     #[serde(rename = "$ref", skip_serializing_if = "Option::is_none")]
@@ -60,14 +106,114 @@ pub struct DollarRef {
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct DevicePower {
     pub owner: ResourceLink,
-    pub power_state: Value,
+    pub power_state: PowerState,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq, Eq)]
+pub struct PowerState {
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub battery_state: Option<BatteryState>,
+    #[serde(
+        skip_serializing_if = "Option::is_none",
+        deserialize_with = "battery_level",
+        default
+    )]
+    pub battery_level: Option<u8>,
+}
+
+/// Older versions of Bifrost stored the battery level as a float
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+fn battery_level<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<u8>, D::Error> {
+    let level = Option::<f64>::deserialize(deserializer)?;
+    Ok(level.map(|level| level.clamp(0.0, 100.0).round() as u8))
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum BatteryState {
+    Normal,
+    Low,
+    Critical,
+}
+
+impl BatteryState {
+    #[must_use]
+    pub const fn from_level(level: u8) -> Self {
+        match level {
+            0..=5 => Self::Critical,
+            6..=20 => Self::Low,
+            _ => Self::Normal,
+        }
+    }
+}
+
+impl DevicePower {
+    /// A battery powered device, until it reports its battery level
+    #[must_use]
+    pub fn new(owner: ResourceLink) -> Self {
+        Self {
+            owner,
+            power_state: PowerState::default(),
+        }
+    }
+
+    /// Report a battery level in percent
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    pub fn report(&mut self, level: f64) {
+        let level = level.clamp(0.0, 100.0).round() as u8;
+        self.power_state = PowerState {
+            battery_state: Some(BatteryState::from_level(level)),
+            battery_level: Some(level),
+        };
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct DeviceSoftwareUpdate {
     pub owner: ResourceLink,
-    pub state: Value,
+    pub state: SoftwareUpdateState,
     pub problems: Vec<Value>,
+    /// Progress of an installing update in percent. Not reported by real
+    /// bridges, which only report the state.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub progress: Option<u8>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SoftwareUpdateState {
+    NoUpdate,
+    UpdatePending,
+    ReadyToInstall,
+    Installing,
+}
+
+impl DeviceSoftwareUpdate {
+    #[must_use]
+    pub const fn new(owner: ResourceLink) -> Self {
+        Self {
+            owner,
+            state: SoftwareUpdateState::NoUpdate,
+            problems: vec![],
+            progress: None,
+        }
+    }
+
+    /// Report the update state, with the progress (in percent) of an
+    /// update being installed
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    pub fn report(&mut self, state: SoftwareUpdateState, progress: Option<f64>) {
+        self.state = state;
+        self.progress = progress
+            .filter(|_| state == SoftwareUpdateState::Installing)
+            .map(|pct| pct.clamp(0.0, 100.0).round() as u8);
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct DeviceSoftwareUpdateUpdate {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub install: Option<bool>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -123,6 +269,18 @@ pub struct LightLevel {
     pub owner: ResourceLink,
 }
 
+impl LightLevel {
+    /// Hue reports illuminance as `10000 * log10(lux) + 1`
+    #[must_use]
+    pub fn light_level_from_lux(lux: f64) -> f64 {
+        if lux <= 0.0 {
+            0.0
+        } else {
+            10000.0f64.mul_add(lux.log10(), 1.0).round().max(0.0)
+        }
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Matter {
     pub has_qr_code: bool,
@@ -188,7 +346,7 @@ pub struct InternetConnectivity {
     pub status: InternetConnectivityStatus,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
 #[serde(rename_all = "snake_case")]
 pub enum ZigbeeConnectivityStatus {
     Connected,
@@ -206,14 +364,6 @@ pub struct ZigbeeConnectivity {
     pub status: ZigbeeConnectivityStatus,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
-pub struct Zone {
-    pub metadata: Metadata,
-    pub children: BTreeSet<ResourceLink>,
-    #[serde(default)]
-    pub services: BTreeSet<ResourceLink>,
-}
-
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Temperature {
     pub enabled: bool,
@@ -260,3 +410,43 @@ pub struct MetadataUpdate {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub function: Option<LightFunction>,
 }
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use crate::api::{BatteryState, LightLevel, PowerState};
+
+    #[test]
+    fn battery_state_from_level() {
+        assert_eq!(BatteryState::from_level(0), BatteryState::Critical);
+        assert_eq!(BatteryState::from_level(5), BatteryState::Critical);
+        assert_eq!(BatteryState::from_level(20), BatteryState::Low);
+        assert_eq!(BatteryState::from_level(21), BatteryState::Normal);
+    }
+
+    #[test]
+    fn power_state_float_level() {
+        let state: PowerState =
+            serde_json::from_value(json!({"battery_state": "low", "battery_level": 17.0})).unwrap();
+        assert_eq!(state.battery_level, Some(17));
+        assert_eq!(state.battery_state, Some(BatteryState::Low));
+
+        let state: PowerState = serde_json::from_value(json!({})).unwrap();
+        assert_eq!(state, PowerState::default());
+    }
+
+    #[test]
+    fn light_level_from_lux() {
+        let levels = [
+            (-1.0, 0.0),
+            (0.0, 0.0),
+            (1.0, 1.0),
+            (10.0, 10001.0),
+            (1000.0, 30001.0),
+        ];
+        for (lux, level) in levels {
+            assert!((LightLevel::light_level_from_lux(lux) - level).abs() < 0.5);
+        }
+    }
+}
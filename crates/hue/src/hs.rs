@@ -21,11 +21,111 @@ impl From<RawHS> for HS {
     }
 }
 
+impl HS {
+    /// Convert (unit) RGB to hue/saturation, using the HSV color model.
+    ///
+    /// Returns the color, and its value (brightness) in the 0.0-1.0 range.
+    #[must_use]
+    pub fn from_rgb_unit(r: f64, g: f64, b: f64) -> (Self, f64) {
+        let max = r.max(g).max(b);
+        let min = r.min(g).min(b);
+        let delta = max - min;
+
+        if max <= f64::EPSILON {
+            return (Self { hue: 0.0, sat: 0.0 }, 0.0);
+        }
+
+        let sat = delta / max;
+        let hue = if delta <= f64::EPSILON {
+            0.0
+        } else if (max - r).abs() <= f64::EPSILON {
+            ((g - b) / delta).rem_euclid(6.0)
+        } else if (max - g).abs() <= f64::EPSILON {
+            (b - r) / delta + 2.0
+        } else {
+            (r - g) / delta + 4.0
+        };
+
+        (
+            Self {
+                hue: hue / 6.0,
+                sat,
+            },
+            max,
+        )
+    }
+
+    /// Convert hue/saturation to (unit) RGB, using the HSV color model.
+    #[must_use]
+    pub fn to_rgb_unit(&self, value: f64) -> [f64; 3] {
+        let c = value * self.sat;
+        let h = (self.hue.rem_euclid(1.0)) * 6.0;
+        let x = c * (1.0 - (h % 2.0 - 1.0).abs());
+        let m = value - c;
+
+        if h < 1.0 {
+            [m + c, m + x, m]
+        } else if h < 2.0 {
+            [m + x, m + c, m]
+        } else if h < 3.0 {
+            [m, m + c, m + x]
+        } else if h < 4.0 {
+            [m, m + x, m + c]
+        } else if h < 5.0 {
+            [m + x, m, m + c]
+        } else {
+            [m + c, m, m + x]
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use crate::hs::{HS, RawHS};
     use crate::{compare, compare_float, compare_hs};
 
+    #[test]
+    fn from_rgb_unit_primaries() {
+        let (red, value) = HS::from_rgb_unit(1.0, 0.0, 0.0);
+        compare_hs!(red, HS { hue: 0.0, sat: 1.0 });
+        compare!(value, 1.0);
+
+        let (green, _) = HS::from_rgb_unit(0.0, 1.0, 0.0);
+        compare_hs!(
+            green,
+            HS {
+                hue: 1.0 / 3.0,
+                sat: 1.0
+            }
+        );
+
+        let (blue, _) = HS::from_rgb_unit(0.0, 0.0, 1.0);
+        compare_hs!(
+            blue,
+            HS {
+                hue: 2.0 / 3.0,
+                sat: 1.0
+            }
+        );
+    }
+
+    #[test]
+    fn from_rgb_unit_black() {
+        let (hs, value) = HS::from_rgb_unit(0.0, 0.0, 0.0);
+        compare_hs!(hs, HS { hue: 0.0, sat: 0.0 });
+        compare!(value, 0.0);
+    }
+
+    #[test]
+    fn rgb_unit_roundtrip() {
+        let rgb = [0.2, 0.6, 0.9];
+        let (hs, value) = HS::from_rgb_unit(rgb[0], rgb[1], rgb[2]);
+        let res = hs.to_rgb_unit(value);
+        for (a, b) in res.iter().zip(rgb) {
+            compare!(*a, b);
+        }
+    }
+
     #[test]
     fn from_rawhs_min() {
         compare_hs!(
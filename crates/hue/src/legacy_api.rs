@@ -452,15 +452,33 @@ impl ApiGroup {
         }
     }
 
-    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
     #[must_use]
     pub fn from_lights_and_room(
         glight: &api::GroupedLight,
         lights: Vec<String>,
         room: api::Room,
+    ) -> Self {
+        Self::from_lights_and_group(glight, lights, room.metadata.name, ApiGroupType::Room)
+    }
+
+    #[must_use]
+    pub fn from_lights_and_zone(
+        glight: &api::GroupedLight,
+        lights: Vec<String>,
+        zone: api::Zone,
+    ) -> Self {
+        Self::from_lights_and_group(glight, lights, zone.metadata.name, ApiGroupType::Zone)
+    }
+
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    fn from_lights_and_group(
+        glight: &api::GroupedLight,
+        lights: Vec<String>,
+        name: String,
+        group_type: ApiGroupType,
     ) -> Self {
         Self {
-            name: room.metadata.name,
+            name,
             lights,
             action: ApiGroupAction {
                 on: glight.on.is_some_and(|on| on.on),
@@ -474,7 +492,7 @@ impl ApiGroup {
                 colormode: None,
             },
             class: ApiGroupClass::default(),
-            group_type: ApiGroupType::Room,
+            group_type,
             recycle: false,
             sensors: vec![],
             state: ApiGroupState::default(),
@@ -595,6 +613,12 @@ pub struct ApiLight {
 }
 
 impl ApiLight {
+    #[must_use]
+    pub const fn with_reachable(mut self, reachable: bool) -> Self {
+        self.state.reachable = reachable;
+        self
+    }
+
     fn v1_archetype(archetype: &api::DeviceArchetype) -> String {
         use api::DeviceArchetype;
         match archetype {
@@ -867,6 +891,203 @@ impl ApiSensor {
             capabilities: Value::Null,
         }
     }
+
+    /// Light level below which a v1 light sensor reports `dark`
+    pub const LIGHTLEVEL_THOLD_DARK: u64 = 16000;
+
+    /// Offset above the dark threshold, from where `daylight` is reported
+    pub const LIGHTLEVEL_THOLD_OFFSET: u64 = 7000;
+
+    fn from_device(uuid: &Uuid, dev: &api::Device, sensor_type: &str) -> Self {
+        let product_data = &dev.product_data;
+        Self {
+            sensor_type: sensor_type.to_string(),
+            config: Value::Null,
+            name: dev.metadata.name.clone(),
+            state: Value::Null,
+            manufacturername: product_data.manufacturer_name.clone(),
+            modelid: product_data.model_id.clone(),
+            swversion: product_data.software_version.clone(),
+            swupdate: Some(SwUpdate::default()),
+
+            /* FIXME: Should have form "00:11:22:33:44:55:66:77-02-0406" */
+            uniqueid: Some(uuid.as_simple().to_string()),
+
+            diversityid: None,
+            productname: Some(product_data.product_name.clone()),
+            recycle: Some(false),
+            capabilities: json!({
+                "certified": product_data.certified,
+                "primary": true,
+            }),
+        }
+    }
+
+    /// Convert the timestamp of a v2 sensor report to the v1 `lastupdated`
+    /// format, or "none" if it is missing.
+    fn lastupdated(report: Option<&Value>) -> Value {
+        report
+            .and_then(Value::as_str)
+            .and_then(|ts| DateTime::parse_from_rfc3339(ts).ok())
+            .map_or_else(
+                || json!("none"),
+                |ts| {
+                    json!(
+                        ts.with_timezone(&Utc)
+                            .format("%Y-%m-%dT%H:%M:%S")
+                            .to_string()
+                    )
+                },
+            )
+    }
+
+    fn sensor_config(enabled: bool, reachable: bool) -> Value {
+        json!({
+            "on": enabled,
+            "reachable": reachable,
+            "alert": "none",
+        })
+    }
+
+    #[must_use]
+    pub fn from_dev_and_motion(uuid: &Uuid, dev: &api::Device, motion: &api::Motion) -> Self {
+        let report = &motion.motion;
+        let valid = report["motion_valid"].as_bool().unwrap_or(false);
+
+        Self {
+            config: Self::sensor_config(motion.enabled, valid),
+            state: json!({
+                "presence": report["motion"].as_bool().unwrap_or(false),
+                "lastupdated": Self::lastupdated(
+                    report
+                        .pointer("/motion_report/changed")
+                        .or_else(|| report.get("last_updated"))
+                ),
+            }),
+            ..Self::from_device(uuid, dev, "ZLLPresence")
+        }
+    }
+
+    #[must_use]
+    #[allow(clippy::cast_possible_truncation)]
+    pub fn from_dev_and_temperature(
+        uuid: &Uuid,
+        dev: &api::Device,
+        temp: &api::Temperature,
+    ) -> Self {
+        let report = &temp.temperature;
+        let valid = report["temperature_valid"].as_bool().unwrap_or(false);
+
+        /* v1 reports temperatures in hundredths of a degree celsius */
+        let temperature = report["temperature"]
+            .as_f64()
+            .filter(|_| valid)
+            .map(|t| (t * 100.0).round() as i64);
+
+        Self {
+            config: Self::sensor_config(temp.enabled, valid),
+            state: json!({
+                "temperature": temperature,
+                "lastupdated": Self::lastupdated(report.pointer("/temperature_report/changed")),
+            }),
+            ..Self::from_device(uuid, dev, "ZLLTemperature")
+        }
+    }
+
+    #[must_use]
+    pub fn from_dev_and_light_level(
+        uuid: &Uuid,
+        dev: &api::Device,
+        light_level: &api::LightLevel,
+    ) -> Self {
+        let report = &light_level.light;
+        let valid = report["light_level_valid"].as_bool().unwrap_or(false);
+        let level = report["light_level"].as_u64().filter(|_| valid);
+
+        let mut config = Self::sensor_config(light_level.enabled, valid);
+        config["tholddark"] = json!(Self::LIGHTLEVEL_THOLD_DARK);
+        config["tholdoffset"] = json!(Self::LIGHTLEVEL_THOLD_OFFSET);
+
+        Self {
+            config,
+            state: json!({
+                "lightlevel": level,
+                "dark": level.map(|lvl| lvl < Self::LIGHTLEVEL_THOLD_DARK),
+                "daylight": level
+                    .map(|lvl| lvl >= Self::LIGHTLEVEL_THOLD_DARK + Self::LIGHTLEVEL_THOLD_OFFSET),
+                "lastupdated": Self::lastupdated(report.pointer("/light_level_report/changed")),
+            }),
+            ..Self::from_device(uuid, dev, "ZLLLightLevel")
+        }
+    }
+
+    /// Contact sensors are modelled after the CLIP open/close sensor, since
+    /// there is no zigbee equivalent in the v1 api.
+    #[must_use]
+    pub fn from_dev_and_contact(uuid: &Uuid, dev: &api::Device, contact: &api::Contact) -> Self {
+        let report = contact.contact_report.as_ref();
+
+        Self {
+            config: Self::sensor_config(contact.enabled, report.is_some()),
+            state: json!({
+                "open": report.is_some_and(|rep| rep.state == api::ContactState::NoContact),
+                "lastupdated": report.map_or_else(
+                    || json!("none"),
+                    |rep| json!(rep.changed.format("%Y-%m-%dT%H:%M:%S").to_string()),
+                ),
+            }),
+            ..Self::from_device(uuid, dev, "CLIPOpenClose")
+        }
+    }
+
+    /// v1 button events are encoded as `<button number><event code>`, where
+    /// the button number is the v2 control id.
+    #[must_use]
+    pub fn v1_button_event(control_id: u32, event: &str) -> Option<u32> {
+        let code = match event {
+            "initial_press" => 0,
+            "repeat" | "long_press" => 1,
+            "short_release" => 2,
+            "long_release" => 3,
+            _ => return None,
+        };
+        Some(control_id * 1000 + code)
+    }
+
+    /// A v1 switch covers all buttons of a device, and reports the most
+    /// recent event of any of them.
+    #[must_use]
+    pub fn from_dev_and_buttons(uuid: &Uuid, dev: &api::Device, buttons: &[api::Button]) -> Self {
+        let last = buttons
+            .iter()
+            .filter_map(|btn| Some((btn.metadata.control_id, btn.button.button_report.as_ref()?)))
+            .max_by_key(|(_, report)| report.updated);
+
+        let (buttonevent, lastupdated) = last.map_or_else(
+            || (None, json!("none")),
+            |(id, report)| {
+                (
+                    Self::v1_button_event(id, &report.event),
+                    json!(report.updated.format("%Y-%m-%dT%H:%M:%S").to_string()),
+                )
+            },
+        );
+
+        Self {
+            config: Self::sensor_config(true, true),
+            state: json!({
+                "buttonevent": buttonevent,
+                "lastupdated": lastupdated,
+            }),
+            ..Self::from_device(uuid, dev, "ZLLSwitch")
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ApiSensorConfigUpdate {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub on: Option<bool>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -878,7 +1099,7 @@ pub struct ApiUserConfig {
     pub rules: HashMap<u32, ApiRule>,
     pub scenes: HashMap<String, ApiScene>,
     pub schedules: HashMap<u32, ApiSchedule>,
-    pub sensors: HashMap<u32, ApiSensor>,
+    pub sensors: HashMap<String, ApiSensor>,
 }
 
 impl Default for ApiConfig {
@@ -1035,4 +1256,84 @@ mod tests {
 
         assert_eq!(res, b"\"01:02:03:aa:bb:cc\"");
     }
+
+    #[test]
+    fn zone_group_type() {
+        use std::collections::BTreeSet;
+
+        use crate::api::{GroupedLight, RType, RoomArchetype, RoomMetadata, Zone};
+        use crate::legacy_api::ApiGroup;
+
+        let zone = Zone::new(
+            RoomMetadata::new(RoomArchetype::Downstairs, "Downstairs"),
+            BTreeSet::new(),
+        );
+        let glight = GroupedLight::new(RType::Zone.deterministic("zone"));
+        let group = ApiGroup::from_lights_and_zone(&glight, vec!["1".to_string()], zone);
+
+        let json = serde_json::to_value(group).unwrap();
+        assert_eq!(json["type"], "Zone");
+        assert_eq!(json["name"], "Downstairs");
+        assert_eq!(json["lights"], serde_json::json!(["1"]));
+    }
+
+    #[test]
+    fn sensor_button_event() {
+        use crate::legacy_api::ApiSensor;
+
+        assert_eq!(ApiSensor::v1_button_event(1, "initial_press"), Some(1000));
+        assert_eq!(ApiSensor::v1_button_event(2, "long_press"), Some(2001));
+        assert_eq!(ApiSensor::v1_button_event(3, "short_release"), Some(3002));
+        assert_eq!(ApiSensor::v1_button_event(4, "long_release"), Some(4003));
+        assert_eq!(ApiSensor::v1_button_event(1, "double_short_release"), None);
+    }
+
+    #[test]
+    fn sensor_light_level() {
+        use std::collections::BTreeSet;
+
+        use serde_json::json;
+
+        use crate::api::{Device, DeviceArchetype, DeviceProductData, LightLevel, Metadata, RType};
+        use crate::legacy_api::ApiSensor;
+
+        let dev = Device {
+            product_data: DeviceProductData {
+                model_id: "SML001".to_string(),
+                manufacturer_name: DeviceProductData::SIGNIFY_MANUFACTURER_NAME.to_string(),
+                product_name: "Hue motion sensor".to_string(),
+                product_archetype: DeviceArchetype::UnknownArchetype,
+                certified: true,
+                software_version: "1.0".to_string(),
+                hardware_platform_type: None,
+            },
+            metadata: Metadata::new(DeviceArchetype::UnknownArchetype, "Hallway"),
+            services: BTreeSet::new(),
+            usertest: None,
+            identify: None,
+        };
+        let link = RType::LightLevel.deterministic("hallway");
+        let light_level = LightLevel {
+            enabled: false,
+            light: json!({
+                "light_level": 12000,
+                "light_level_valid": true,
+                "light_level_report": {
+                    "changed": "2025-01-02T03:04:05+00:00",
+                    "light_level": 12000,
+                },
+            }),
+            owner: RType::Device.deterministic("hallway"),
+        };
+
+        let sensor = ApiSensor::from_dev_and_light_level(&link.rid, &dev, &light_level);
+        let json = serde_json::to_value(sensor).unwrap();
+        assert_eq!(json["type"], "ZLLLightLevel");
+        assert_eq!(json["name"], "Hallway");
+        assert_eq!(json["config"]["on"], false);
+        assert_eq!(json["state"]["lightlevel"], 12000);
+        assert_eq!(json["state"]["dark"], true);
+        assert_eq!(json["state"]["daylight"], false);
+        assert_eq!(json["state"]["lastupdated"], "2025-01-02T03:04:05");
+    }
 }
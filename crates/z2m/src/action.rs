@@ -0,0 +1,269 @@
+//! Parsing of the `action` values reported by zigbee2mqtt remotes.
+//!
+//! zigbee2mqtt does not have a common format for actions, but most remotes
+//! report `<button>_<event>`, like `on_press_release` (Hue dimmer switch),
+//! `button_1_hold` (Hue tap dial) or `arrow_left_click` (IKEA remotes).
+//! Remotes with a single button often report the bare event, like `single`.
+
+/// What happened to a button, in zigbee2mqtt terms
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ButtonAction {
+    /// The button went down
+    Press,
+    /// The button is being held down (reported once, or repeatedly)
+    Hold,
+    /// The button was released, after either a press or a hold
+    Release,
+    /// The button was released after a press
+    PressRelease,
+    /// The button was released after a hold
+    HoldRelease,
+    /// A complete press and release, reported as one action
+    Click,
+    /// Two clicks in quick succession
+    Double,
+    /// A plain action, like `on` or `toggle`, that only names the button
+    Activate,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RotaryDirection {
+    Left,
+    Right,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RotarySpeed {
+    Step,
+    Slow,
+    Fast,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action<'a> {
+    /// Activity on a button. The name is empty for single-button remotes
+    /// that only report the event.
+    Button {
+        button: &'a str,
+        action: ButtonAction,
+    },
+    /// A turn of a dial
+    Rotary {
+        direction: RotaryDirection,
+        speed: RotarySpeed,
+    },
+}
+
+/// Known events, longest first, so `on_press_release` is not taken for a
+/// release of the button `on_press`.
+const BUTTON_ACTIONS: [(&str, ButtonAction); 12] = [
+    ("press_release", ButtonAction::PressRelease),
+    ("hold_release", ButtonAction::HoldRelease),
+    ("long_release", ButtonAction::HoldRelease),
+    ("double_press", ButtonAction::Double),
+    ("long_press", ButtonAction::Hold),
+    ("release", ButtonAction::Release),
+    ("double", ButtonAction::Double),
+    ("single", ButtonAction::Click),
+    ("click", ButtonAction::Click),
+    ("press", ButtonAction::Press),
+    ("hold", ButtonAction::Hold),
+    ("long", ButtonAction::Hold),
+];
+
+/// Actions that mirror the zigbee commands a remote sends to its bound
+/// lights. The button activity behind them is reported separately, or not
+/// at all, so they do not name buttons.
+const COMMAND_PREFIXES: [&str; 9] = [
+    "brightness_move",
+    "brightness_step",
+    "brightness_stop",
+    "color_",
+    "hue_",
+    "saturation_",
+    "recall_",
+    "store_",
+    "enhanced_",
+];
+
+/// Multi-clicks that have no Hue equivalent
+const IGNORED_EVENTS: [&str; 3] = ["triple", "quadruple", "many"];
+
+impl<'a> Action<'a> {
+    #[must_use]
+    pub fn parse(value: &'a str) -> Option<Self> {
+        if value.is_empty()
+            || COMMAND_PREFIXES.iter().any(|pf| value.starts_with(pf))
+            || IGNORED_EVENTS.iter().any(|ev| value.ends_with(ev))
+        {
+            return None;
+        }
+
+        if let Some(rotation) = value.strip_prefix("dial_rotate_") {
+            let (direction, speed) = rotation.split_once('_')?;
+            let direction = match direction {
+                "left" => RotaryDirection::Left,
+                "right" => RotaryDirection::Right,
+                _ => return None,
+            };
+            let speed = match speed {
+                "step" => RotarySpeed::Step,
+                "slow" => RotarySpeed::Slow,
+                "fast" => RotarySpeed::Fast,
+                _ => return None,
+            };
+            return Some(Self::Rotary { direction, speed });
+        }
+
+        for (event, action) in BUTTON_ACTIONS {
+            if value == event {
+                return Some(Self::Button { button: "", action });
+            }
+            if let Some(button) = value
+                .strip_suffix(event)
+                .and_then(|rest| rest.strip_suffix('_'))
+            {
+                return Some(Self::Button { button, action });
+            }
+        }
+
+        Some(Self::Button {
+            button: value,
+            action: ButtonAction::Activate,
+        })
+    }
+}
+
+/// The buttons of a remote, in the order of its reported action values.
+///
+/// Remotes that report bare events have a single button, and any plain
+/// actions they report (like `on` and `off` on the Hue smart button) come
+/// from that same button.
+#[must_use]
+pub fn buttons<'a>(values: impl IntoIterator<Item = &'a str>) -> Vec<&'a str> {
+    let actions: Vec<Action> = values.into_iter().filter_map(Action::parse).collect();
+
+    let single = actions
+        .iter()
+        .any(|act| matches!(act, Action::Button { button: "", .. }));
+
+    let mut buttons = vec![];
+    for act in actions {
+        let Action::Button { button, action } = act else {
+            continue;
+        };
+        if single && action == ButtonAction::Activate {
+            continue;
+        }
+        if !buttons.contains(&button) {
+            buttons.push(button);
+        }
+    }
+
+    buttons
+}
+
+/// True, if any of the action values is a dial rotation
+pub fn has_rotary<'a>(values: impl IntoIterator<Item = &'a str>) -> bool {
+    values
+        .into_iter()
+        .any(|value| matches!(Action::parse(value), Some(Action::Rotary { .. })))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::action::{Action, ButtonAction, RotaryDirection, RotarySpeed, buttons, has_rotary};
+
+    #[test]
+    fn parse_button_actions() {
+        assert_eq!(
+            Action::parse("on_press_release"),
+            Some(Action::Button {
+                button: "on",
+                action: ButtonAction::PressRelease
+            })
+        );
+        assert_eq!(
+            Action::parse("button_2_hold"),
+            Some(Action::Button {
+                button: "button_2",
+                action: ButtonAction::Hold
+            })
+        );
+        assert_eq!(
+            Action::parse("arrow_left_click"),
+            Some(Action::Button {
+                button: "arrow_left",
+                action: ButtonAction::Click
+            })
+        );
+        assert_eq!(
+            Action::parse("double"),
+            Some(Action::Button {
+                button: "",
+                action: ButtonAction::Double
+            })
+        );
+        assert_eq!(
+            Action::parse("toggle"),
+            Some(Action::Button {
+                button: "toggle",
+                action: ButtonAction::Activate
+            })
+        );
+        assert_eq!(Action::parse("brightness_move_up"), None);
+        assert_eq!(Action::parse(""), None);
+    }
+
+    #[test]
+    fn parse_rotary_actions() {
+        assert_eq!(
+            Action::parse("dial_rotate_left_fast"),
+            Some(Action::Rotary {
+                direction: RotaryDirection::Left,
+                speed: RotarySpeed::Fast
+            })
+        );
+        assert_eq!(Action::parse("dial_rotate_up_fast"), None);
+    }
+
+    #[test]
+    fn remote_buttons() {
+        let dimmer = [
+            "on_press",
+            "on_press_release",
+            "on_hold",
+            "on_hold_release",
+            "up_press",
+            "up_press_release",
+            "down_press",
+            "down_press_release",
+            "off_press",
+            "off_press_release",
+        ];
+        assert_eq!(buttons(dimmer), ["on", "up", "down", "off"]);
+        assert!(!has_rotary(dimmer));
+
+        let smart_button = ["on", "off", "skip_backward", "press", "hold", "release"];
+        assert_eq!(buttons(smart_button), [""]);
+
+        let ikea = [
+            "toggle",
+            "brightness_up_click",
+            "brightness_up_hold",
+            "brightness_up_release",
+            "arrow_left_click",
+            "brightness_move_up",
+        ];
+        assert_eq!(buttons(ikea), ["toggle", "brightness_up", "arrow_left"]);
+
+        let tap_dial = [
+            "button_1_press",
+            "button_2_press",
+            "dial_rotate_left_step",
+            "brightness_step_up",
+        ];
+        assert_eq!(buttons(tap_dial), ["button_1", "button_2"]);
+        assert!(has_rotary(tap_dial));
+    }
+}
@@ -121,6 +121,11 @@ pub struct DeviceRemove {
     pub id: String,
 }
 
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct DeviceOtaUpdate {
+    pub id: String,
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct DeviceRemoveResponse {
     pub id: String,
@@ -183,13 +188,31 @@ pub struct DeviceRename {
     pub homeassistant_rename: bool,
 }
 
-#[derive(Serialize, Deserialize, Clone, Hash, Debug, Copy)]
+#[derive(Serialize, Deserialize, Clone, Hash, Debug, Copy, PartialEq, Eq)]
 #[serde(rename_all = "snake_case")]
 pub enum Availability {
     Online,
     Offline,
 }
 
+/// Payload of the `<device>/availability` topic. Older versions of
+/// zigbee2mqtt publish the plain state, newer versions an object.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(untagged)]
+pub enum AvailabilityMessage {
+    State { state: Availability },
+    Legacy(Availability),
+}
+
+impl AvailabilityMessage {
+    #[must_use]
+    pub const fn state(&self) -> Availability {
+        match self {
+            Self::State { state } | Self::Legacy(state) => *state,
+        }
+    }
+}
+
 #[derive(Serialize, Deserialize, Clone, Hash)]
 #[serde(transparent)]
 pub struct IeeeAddress(#[serde(deserialize_with = "ieee_address")] u64);
@@ -200,6 +223,19 @@ impl Debug for IeeeAddress {
     }
 }
 
+impl IeeeAddress {
+    /// The address in the format used by Hue, like "00:17:88:01:0b:cd:ef:12"
+    #[must_use]
+    pub fn mac_address(&self) -> String {
+        self.0
+            .to_be_bytes()
+            .iter()
+            .map(|b| format!("{b:02x}"))
+            .collect::<Vec<_>>()
+            .join(":")
+    }
+}
+
 impl Display for IeeeAddress {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "0x{:016x}", self.0)
@@ -490,6 +526,20 @@ impl Device {
         })
     }
 
+    /// True, if zigbee2mqtt can update the firmware of this device
+    #[must_use]
+    pub fn supports_ota(&self) -> bool {
+        self.definition.as_ref().is_some_and(|def| def.supports_ota)
+    }
+
+    /// Find a top-level expose by the property it is reported in
+    #[must_use]
+    pub fn expose_property(&self, property: &str) -> Option<&Expose> {
+        self.exposes()
+            .iter()
+            .find(|exp| exp.base().property.as_deref() == Some(property))
+    }
+
     #[must_use]
     pub fn expose_gradient(&self) -> Option<&ExposeList> {
         self.exposes().iter().find_map(|exp| {
@@ -511,12 +561,12 @@ impl Device {
     }
 
     #[must_use]
-    pub fn expose_action(&self) -> bool {
-        self.exposes().iter().any(|exp| {
-            if let Expose::Enum(ExposeEnum { base, .. }) = exp {
-                base.name.as_deref() == Some("action")
+    pub fn expose_action(&self) -> Option<&ExposeEnum> {
+        self.exposes().iter().find_map(|exp| {
+            if let Expose::Enum(action) = exp {
+                (action.base.name.as_deref() == Some("action")).then_some(action)
             } else {
-                false
+                None
             }
         })
     }
@@ -631,6 +681,13 @@ pub struct ExposeEnum {
     pub values: Vec<Value>,
 }
 
+impl ExposeEnum {
+    /// The values of the enum that are strings
+    pub fn string_values(&self) -> impl Iterator<Item = &str> {
+        self.values.iter().filter_map(Value::as_str)
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ExposeLight {
     #[serde(flatten)]
@@ -728,3 +785,26 @@ pub struct DeviceEndpointClusters {
     pub input: Vec<String>,
     pub output: Vec<String>,
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::api::{Availability, AvailabilityMessage, IeeeAddress};
+
+    #[test]
+    fn availability_message() {
+        let msg: AvailabilityMessage = serde_json::from_str(r#"{"state": "offline"}"#).unwrap();
+        assert_eq!(msg.state(), Availability::Offline);
+
+        let msg: AvailabilityMessage = serde_json::from_str(r#""online""#).unwrap();
+        assert_eq!(msg.state(), Availability::Online);
+
+        assert!(serde_json::from_str::<AvailabilityMessage>(r#""away""#).is_err());
+    }
+
+    #[test]
+    fn ieee_address_mac_address() {
+        let addr: IeeeAddress = serde_json::from_str(r#""0x00178801000bcdef""#).unwrap();
+        assert_eq!(addr.mac_address(), "00:17:88:01:00:0b:cd:ef");
+        assert_eq!(addr.to_string(), "0x00178801000bcdef");
+    }
+}
@@ -1,7 +1,9 @@
 use serde::Serialize;
 use serde_json::Value;
 
-use crate::api::{DeviceRemove, GroupMemberChange, PermitJoin};
+use crate::api::{
+    DeviceOtaUpdate, DeviceRemove, GroupAdd, GroupMemberChange, GroupRemove, PermitJoin,
+};
 use crate::update::DeviceUpdate;
 
 #[derive(Clone, Debug, Serialize)]
@@ -33,6 +35,12 @@ pub enum Z2mRequest<'a> {
         payload: Z2mPayload,
     },
 
+    #[serde(untagged)]
+    GroupAdd(GroupAdd),
+
+    #[serde(untagged)]
+    GroupRemove(GroupRemove),
+
     #[serde(untagged)]
     GroupMemberAdd(GroupMemberChange),
 
@@ -45,6 +53,9 @@ pub enum Z2mRequest<'a> {
     #[serde(untagged)]
     DeviceRemove(DeviceRemove),
 
+    #[serde(untagged)]
+    DeviceOtaUpdate(DeviceOtaUpdate),
+
     #[serde(untagged)]
     Update(&'a DeviceUpdate),
 
@@ -37,17 +37,28 @@ pub struct DeviceUpdate {
     #[serde(skip_serializing_if = "Option::is_none")]
     #[serde(default)]
     pub power_on_behavior: Option<PowerOnBehavior>,
-    #[serde(skip_serializing_if = "HashMap::is_empty")]
-    #[serde(default)]
-    pub update: HashMap<String, Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub update: Option<OtaUpdate>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub update_available: Option<bool>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub battery: Option<Value>,
+    pub battery: Option<f64>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub transition: Option<f64>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub effect: Option<DeviceEffect>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub action: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub occupancy: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub contact: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub temperature: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub illuminance: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub illuminance_lux: Option<f64>,
 
     /* all other fields */
     #[serde(skip_serializing_if = "HashMap::is_empty")]
@@ -192,6 +203,36 @@ pub struct ColorOptions {
     pub execute_if_off: bool,
 }
 
+/// Firmware update state of a device, as reported in its `update` property
+#[allow(clippy::pub_underscore_fields)]
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct OtaUpdate {
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub state: Option<OtaUpdateState>,
+    /// Progress in percent, while updating
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub progress: Option<f64>,
+    /// Seconds until the update is done, while updating
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub remaining: Option<f64>,
+
+    /* all other fields */
+    #[serde(skip_serializing_if = "HashMap::is_empty")]
+    #[serde(default, flatten)]
+    pub __: HashMap<String, Value>,
+}
+
+#[derive(Copy, Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum OtaUpdateState {
+    Idle,
+    Available,
+    Scheduled,
+    Updating,
+    #[serde(other)]
+    Unknown,
+}
+
 #[derive(Copy, Debug, Serialize, Deserialize, Clone)]
 #[serde(deny_unknown_fields)]
 pub struct LevelConfig {
@@ -255,3 +296,30 @@ pub enum DeviceEffect {
     FinishEffect,
     StopEffect,
 }
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use crate::update::{DeviceUpdate, OtaUpdateState};
+
+    #[test]
+    fn ota_update_state() {
+        let upd: DeviceUpdate = serde_json::from_value(json!({
+            "update": {
+                "state": "updating",
+                "progress": 42.5,
+                "remaining": 310,
+                "installed_version": 16_783_874,
+            },
+        }))
+        .unwrap();
+        let ota = upd.update.unwrap();
+        assert_eq!(ota.state, Some(OtaUpdateState::Updating));
+        assert_eq!(ota.progress, Some(42.5));
+
+        let upd: DeviceUpdate =
+            serde_json::from_value(json!({"update": {"state": "something_new"}})).unwrap();
+        assert_eq!(upd.update.unwrap().state, Some(OtaUpdateState::Unknown));
+    }
+}
@@ -5,13 +5,105 @@ use serde_json::{Map, Value, json};
 
 use bifrost_api::backend::BackendRequest;
 use hue::api::{
-    GroupedLight, GroupedLightUpdate, LightUpdate, Motion, Resource, ResourceLink, Room, Scene,
-    SceneActive, SceneStatus, SceneStatusEnum, SceneUpdate,
+    Contact, DeviceUpdate, Entertainment, EntertainmentConfiguration, GroupedLight,
+    GroupedLightUpdate, Light, LightLevel, LightUpdate, Motion, RType, Resource, ResourceLink,
+    Room, RoomUpdate, Scene, SceneActive, SceneStatus, SceneStatusEnum, SceneUpdate, Temperature,
+    Zone,
+};
+use hue::stream::HueStreamLightsV2;
+use uuid::Uuid;
+
+use crate::backend::hass::color;
+use crate::backend::hass::command::HassServiceCall;
+use crate::backend::hass::effects;
+use crate::backend::hass::entertainment::{HassEntStream, HassEntTarget};
+use crate::backend::hass::import;
+use crate::backend::hass::{
+    HassBackend, HassEntityBinding, HassEntityKind, HassServiceKind, mirek_kelvin,
 };
-
-use crate::backend::hass::{HassBackend, HassEntityBinding, HassEntityKind, HassServiceKind};
 use crate::error::ApiResult;
-use crate::model::hass::HassSwitchMode;
+use crate::model::hass::{HassSwitchMode, HassUiConfig};
+use crate::model::state::AuxData;
+use crate::model::throttle::Throttle;
+
+/// The service call that applies a light update to an entity, if any
+fn light_service_call(binding: &HassEntityBinding, upd: &LightUpdate) -> Option<HassServiceCall> {
+    match binding.kind {
+        HassEntityKind::Light => {
+            if upd.on.is_some_and(|on| !on.on) {
+                return Some(HassServiceCall::new("light", "turn_off", Map::new()));
+            }
+
+            let mut data = Map::new();
+
+            if binding.capabilities.supports_brightness {
+                if let Some(dim) = upd.dimming {
+                    let bri_value = (dim.brightness * 255.0 / 100.0).round().clamp(0.0, 255.0);
+                    let bri = format!("{bri_value:.0}")
+                        .parse::<u16>()
+                        .ok()
+                        .map_or(0, |x| x.min(255));
+                    data.insert("brightness".to_string(), json!(bri));
+                }
+            }
+
+            if binding.capabilities.supports_color_temp {
+                if let Some(ct) = upd.color_temperature.and_then(|ct| ct.mirek) {
+                    let mirek = binding.capabilities.clamp_mirek(ct);
+                    if let Some(kelvin) = mirek_kelvin(f64::from(mirek)) {
+                        data.insert("color_temp_kelvin".to_string(), json!(kelvin));
+                    }
+                }
+            }
+
+            if binding.capabilities.supports_color {
+                if let Some(color) = upd.color {
+                    let (key, value) =
+                        color::color_service_data(binding.capabilities.color_mode, color.xy);
+                    data.insert(key.to_string(), value);
+                }
+            }
+
+            if let Some(effect) = effects::requested_effect(upd) {
+                if let Some(name) = binding.effects.hass_effect(effect) {
+                    data.insert("effect".to_string(), json!(name));
+                }
+            }
+
+            if let Some(duration_ms) = upd.dynamics.as_ref().and_then(|d| d.duration) {
+                data.insert(
+                    "transition".to_string(),
+                    Value::from(f64::from(duration_ms) / 1000.0),
+                );
+            }
+
+            (upd.on.is_some_and(|on| on.on) || !data.is_empty())
+                .then(|| HassServiceCall::new("light", "turn_on", data))
+        }
+        HassEntityKind::Switch => upd.on.map(|on| {
+            let service = if on.on { "turn_on" } else { "turn_off" };
+            HassServiceCall::new("switch", service, Map::new())
+        }),
+        HassEntityKind::BinarySensor | HassEntityKind::Sensor | HassEntityKind::Event => None,
+    }
+}
+
+/// Light updates grouped by service call. Lights needing the same call
+/// share it, so they change together.
+#[derive(Default)]
+struct LightBatches(Vec<(HassServiceCall, Vec<HassEntityBinding>)>);
+
+impl LightBatches {
+    fn add(&mut self, binding: HassEntityBinding, upd: &LightUpdate) {
+        let Some(call) = light_service_call(&binding, upd) else {
+            return;
+        };
+        match self.0.iter_mut().find(|(batch, _)| *batch == call) {
+            Some((_, bindings)) => bindings.push(binding),
+            None => self.0.push((call, vec![binding])),
+        }
+    }
+}
 
 impl HassBackend {
     fn lookup_binding_by_light(&self, link: &ResourceLink) -> Option<HassEntityBinding> {
@@ -29,72 +121,19 @@ impl HassBackend {
         self.entity_map.get(entity_id).cloned()
     }
 
-    async fn backend_light_update(
-        &self,
+    pub(super) async fn backend_light_update(
+        &mut self,
         binding: &HassEntityBinding,
         upd: &LightUpdate,
     ) -> ApiResult<()> {
-        match binding.kind {
-            HassEntityKind::Light => {
-                if let Some(on) = upd.on {
-                    if !on.on {
-                        self.client
-                            .call_service("light", "turn_off", &binding.entity_id, Map::new())
-                            .await?;
-                        return Ok(());
-                    }
-                }
-
-                let mut data = Map::new();
-
-                if binding.capabilities.supports_brightness {
-                    if let Some(dim) = upd.dimming {
-                        let bri_value = (dim.brightness * 255.0 / 100.0).round().clamp(0.0, 255.0);
-                        let bri = format!("{bri_value:.0}")
-                            .parse::<u16>()
-                            .ok()
-                            .map_or(0, |x| x.min(255));
-                        data.insert("brightness".to_string(), json!(bri));
-                    }
-                }
-
-                if binding.capabilities.supports_color_temp {
-                    if let Some(ct) = upd.color_temperature.and_then(|ct| ct.mirek) {
-                        data.insert("color_temp".to_string(), json!(ct));
-                    }
-                }
-
-                if binding.capabilities.supports_color {
-                    if let Some(color) = upd.color {
-                        data.insert("xy_color".to_string(), json!([color.xy.x, color.xy.y]));
-                    }
-                }
-
-                if let Some(duration_ms) = upd.dynamics.as_ref().and_then(|d| d.duration) {
-                    data.insert(
-                        "transition".to_string(),
-                        Value::from(f64::from(duration_ms) / 1000.0),
-                    );
-                }
-
-                if upd.on.is_some_and(|on| on.on) || !data.is_empty() {
-                    self.client
-                        .call_service("light", "turn_on", &binding.entity_id, data)
-                        .await?;
-                }
-            }
-            HassEntityKind::Switch => {
-                if let Some(on) = upd.on {
-                    let service = if on.on { "turn_on" } else { "turn_off" };
-                    self.client
-                        .call_service("switch", service, &binding.entity_id, Map::new())
-                        .await?;
-                }
-            }
-            HassEntityKind::BinarySensor => {}
+        if let Some(call) = light_service_call(binding, upd) {
+            // Otherwise the blink would restore the old state over this one
+            self.stop_signal(&binding.entity_id).await;
+            self.call_service(call.domain, call.service, &binding.entity_id, call.data)
+                .await?;
         }
 
-        Ok(())
+        self.light_signal(binding, upd).await
     }
 
     async fn backend_sensor_enabled_update(
@@ -122,17 +161,30 @@ impl HassBackend {
                 }
             }
             HassServiceKind::Contact => {
-                if let Ok(contact_obj) = lock.get_resource(&binding.service_link) {
-                    if let Resource::Contact(mut raw) = contact_obj.obj {
-                        if let Some(map) = raw.as_object_mut() {
-                            map.insert("enabled".to_string(), Value::Bool(enabled));
-                        }
-                        let _ = lock.delete(&binding.service_link);
-                        lock.add(&binding.service_link, Resource::Contact(raw))?;
-                    }
+                if lock.get::<Contact>(&binding.service_link).is_ok() {
+                    lock.update::<Contact>(&binding.service_link.rid, |c| {
+                        c.enabled = enabled;
+                    })?;
+                }
+            }
+            HassServiceKind::Temperature => {
+                if lock.get::<Temperature>(&binding.service_link).is_ok() {
+                    lock.update::<Temperature>(&binding.service_link.rid, |t| {
+                        t.enabled = enabled;
+                    })?;
+                }
+            }
+            HassServiceKind::LightLevel => {
+                if lock.get::<LightLevel>(&binding.service_link).is_ok() {
+                    lock.update::<LightLevel>(&binding.service_link.rid, |ll| {
+                        ll.enabled = enabled;
+                    })?;
                 }
             }
-            HassServiceKind::Light | HassServiceKind::Switch => {}
+            HassServiceKind::Light
+            | HassServiceKind::Switch
+            | HassServiceKind::Button
+            | HassServiceKind::RelativeRotary => {}
         }
         drop(lock);
 
@@ -152,20 +204,27 @@ impl HassBackend {
     }
 
     async fn backend_grouped_light_update(
-        &self,
+        &mut self,
         link: &ResourceLink,
         upd: &GroupedLightUpdate,
     ) -> ApiResult<()> {
-        let room = self.state.lock().await.get::<GroupedLight>(link)?.owner;
-        let children = self
-            .state
-            .lock()
-            .await
-            .get::<Room>(&room)?
-            .children
-            .iter()
-            .copied()
-            .collect::<Vec<_>>();
+        let lock = self.state.lock().await;
+        let owner = lock.get::<GroupedLight>(link)?.owner;
+        // Rooms hold devices, zones hold light services
+        let members = if owner.rtype == RType::Zone {
+            lock.get::<Zone>(&owner)?
+                .children
+                .iter()
+                .filter_map(|light| self.lookup_binding_by_light(light))
+                .collect::<Vec<_>>()
+        } else {
+            lock.get::<Room>(&owner)?
+                .children
+                .iter()
+                .filter_map(|device| self.lookup_binding_by_device(device))
+                .collect::<Vec<_>>()
+        };
+        drop(lock);
 
         let light_upd = LightUpdate {
             on: upd.on,
@@ -176,19 +235,25 @@ impl HassBackend {
             ..LightUpdate::default()
         };
 
-        for child in children {
-            if let Some(binding) = self.lookup_binding_by_device(&child) {
-                let grouped_as_light = match binding.kind {
-                    HassEntityKind::Light => true,
-                    HassEntityKind::Switch => {
-                        binding.switch_mode.unwrap_or(HassSwitchMode::Plug) == HassSwitchMode::Light
-                    }
-                    HassEntityKind::BinarySensor => false,
-                };
-                if grouped_as_light {
-                    self.backend_light_update(&binding, &light_upd).await?;
+        let mut batches = LightBatches::default();
+        for binding in members {
+            let grouped_as_light = match binding.kind {
+                HassEntityKind::Light => true,
+                HassEntityKind::Switch => {
+                    binding.switch_mode.unwrap_or(HassSwitchMode::Plug) == HassSwitchMode::Light
                 }
+                HassEntityKind::BinarySensor | HassEntityKind::Sensor | HassEntityKind::Event => {
+                    false
+                }
+            };
+            if !grouped_as_light {
+                continue;
             }
+            batches.add(binding, &light_upd);
+        }
+
+        for (call, bindings) in batches.0 {
+            self.batch_command(call, &bindings).await;
         }
 
         Ok(())
@@ -200,13 +265,7 @@ impl HassBackend {
         sid: u32,
         scene: &Scene,
     ) -> ApiResult<()> {
-        let mut lock = self.state.lock().await;
-        lock.aux_set(
-            link_scene,
-            crate::model::state::AuxData::new().with_index(sid),
-        );
-        lock.add(link_scene, Resource::Scene(scene.clone()))?;
-
+        let lock = self.state.lock().await;
         let snapshot_entities = lock
             .get::<Room>(&scene.group)
             .map(|room| {
@@ -219,7 +278,9 @@ impl HassBackend {
                             binding.switch_mode.unwrap_or(HassSwitchMode::Plug)
                                 == HassSwitchMode::Light
                         }
-                        HassEntityKind::BinarySensor => false,
+                        HassEntityKind::BinarySensor
+                        | HassEntityKind::Sensor
+                        | HassEntityKind::Event => false,
                     })
                     .map(|binding| binding.entity_id)
                     .collect::<Vec<_>>()
@@ -230,6 +291,7 @@ impl HassBackend {
         let short = link_scene.rid.simple().to_string();
         let scene_id = format!("bifrost_{}", &short[..short.len().min(12)]);
         let ha_entity_id = format!("scene.{scene_id}");
+        let mut aux = AuxData::new().with_index(sid);
 
         if snapshot_entities.is_empty() {
             self.ui_log(format!(
@@ -248,23 +310,38 @@ impl HassBackend {
             ))
             .await;
         } else {
-            self.scene_map.insert(link_scene.rid, ha_entity_id);
+            aux = aux.with_topic(&ha_entity_id);
         }
 
+        let mut lock = self.state.lock().await;
+        lock.aux_set(link_scene, aux);
+        lock.add(link_scene, Resource::Scene(scene.clone()))?;
+        drop(lock);
+
         Ok(())
     }
 
     async fn backend_scene_recall(&mut self, link: &ResourceLink) -> ApiResult<()> {
-        if let Some(ha_scene) = self.scene_map.get(&link.rid) {
-            self.client.turn_on_scene(ha_scene).await?;
-            return Ok(());
-        }
-
-        let scene_actions = {
+        let (ha_scene, scene_actions) = {
             let lock = self.state.lock().await;
-            lock.get::<Scene>(link)?.actions.clone()
+            (
+                self.hass_scene_for(&lock, link),
+                lock.get::<Scene>(link)?.actions.clone(),
+            )
         };
 
+        if let Some(ha_scene) = ha_scene {
+            // Scenes from `scene.create` are gone after Home Assistant
+            // restarts, so fall back to setting each light
+            match self.client.turn_on_scene(&ha_scene).await {
+                Ok(()) => return Ok(()),
+                Err(err) => {
+                    log::debug!("[{}] Recall of {ha_scene} failed: {err}", self.name);
+                }
+            }
+        }
+
+        let mut batches = LightBatches::default();
         for action in scene_actions {
             if let Some(binding) = self.lookup_binding_by_light(&action.target) {
                 if matches!(binding.kind, HassEntityKind::Switch)
@@ -280,10 +357,14 @@ impl HassBackend {
                     dynamics: None,
                     ..LightUpdate::default()
                 };
-                self.backend_light_update(&binding, &upd).await?;
+                batches.add(binding, &upd);
             }
         }
 
+        for (call, bindings) in batches.0 {
+            self.batch_command(call, &bindings).await;
+        }
+
         Ok(())
     }
 
@@ -310,6 +391,10 @@ impl HassBackend {
             })?;
         }
 
+        if let Some(name) = upd.metadata.as_ref().and_then(|md| md.name.as_deref()) {
+            self.backend_scene_rename(link, name).await?;
+        }
+
         if let Some(recall) = &upd.recall {
             if matches!(
                 recall.action,
@@ -323,11 +408,356 @@ impl HassBackend {
         Ok(())
     }
 
+    async fn backend_entertainment_start(&mut self, ent_id: &Uuid) -> ApiResult<()> {
+        log::trace!("[{}] Entertainment start", self.name);
+        let lock = self.state.lock().await;
+
+        let ent: &EntertainmentConfiguration = lock.get_id(*ent_id)?;
+
+        let mut es = HassEntStream::default();
+        let mut bindings: Vec<HassEntityBinding> = vec![];
+
+        for chan in &ent.channels {
+            for member in &chan.members {
+                // Members rendered by other backends are not ours to drive
+                let Some(light_link) = lock
+                    .get::<Entertainment>(&member.service)
+                    .ok()
+                    .and_then(|enttm| enttm.renderer_reference)
+                else {
+                    continue;
+                };
+                let Some(binding) = self.lookup_binding_by_light(&light_link) else {
+                    continue;
+                };
+
+                es.add_channel_member(chan.channel_id, &binding.entity_id);
+                if !bindings.iter().any(|b| b.entity_id == binding.entity_id) {
+                    bindings.push(binding);
+                }
+            }
+        }
+        drop(lock);
+
+        for binding in bindings {
+            let restore = match self.client.get_state(&binding.entity_id).await {
+                Ok(state) => Some(HassEntStream::restore_from_state(&state)),
+                Err(err) => {
+                    log::warn!(
+                        "[{}] Cannot snapshot {} before streaming: {}",
+                        self.name,
+                        binding.entity_id,
+                        err
+                    );
+                    None
+                }
+            };
+
+            es.add_target(HassEntTarget {
+                entity_id: binding.entity_id,
+                color_mode: binding
+                    .capabilities
+                    .supports_color
+                    .then_some(binding.capabilities.color_mode),
+                throttle: Throttle::from_fps(self.fps),
+                restore,
+            });
+        }
+
+        if es.is_empty() {
+            return Ok(());
+        }
+
+        log::info!(
+            "[{}] Starting entertainment mode stream at {} fps",
+            self.name,
+            self.fps
+        );
+        self.entstream = Some(es);
+
+        Ok(())
+    }
+
+    fn backend_entertainment_frame(&mut self, frame: &HueStreamLightsV2) {
+        if let Some(es) = &mut self.entstream {
+            es.send_frame(&self.client, &self.name, frame);
+        }
+    }
+
+    async fn backend_entertainment_stop(&mut self) -> ApiResult<()> {
+        let Some(mut es) = self.entstream.take() else {
+            return Ok(());
+        };
+        es.finish().await;
+
+        log::debug!("[{}] Stopping entertainment mode..", self.name);
+
+        for target in es.targets() {
+            let Some((service, data)) = &target.restore else {
+                continue;
+            };
+            if let Err(err) = self
+                .call_service("light", service, &target.entity_id, data.clone())
+                .await
+            {
+                self.ui_log(format!(
+                    "Failed to restore {} after streaming: {}",
+                    target.entity_id, err
+                ))
+                .await;
+            }
+        }
+
+        let mut lock = self.state.lock().await;
+
+        for id in lock.get_resource_ids_by_type(RType::Light) {
+            let light: &Light = lock.get_id(id)?;
+            if light.is_streaming() {
+                lock.update(&id, Light::stop_streaming)?;
+            }
+        }
+
+        for id in lock.get_resource_ids_by_type(RType::EntertainmentConfiguration) {
+            let ec: &EntertainmentConfiguration = lock.get_id(id)?;
+            if ec.is_streaming() {
+                lock.update(&id, EntertainmentConfiguration::stop_streaming)?;
+            }
+        }
+        drop(lock);
+
+        if std::mem::take(&mut self.resync_after_stream) {
+            self.resync_and_log("Skipped backend requests while streaming")
+                .await;
+        }
+
+        Ok(())
+    }
+
+    /// Install an update through the `update` entity that reported it. A
+    /// refused install is only logged, like failed light commands.
+    async fn backend_software_update_install(&mut self, link: &ResourceLink) {
+        let topic = self
+            .state
+            .lock()
+            .await
+            .aux_get(link)
+            .ok()
+            .and_then(|aux| aux.topic.clone());
+        let Some(entity_id) = topic.filter(|topic| topic.starts_with("update.")) else {
+            return;
+        };
+
+        if let Err(err) = self
+            .call_service("update", "install", &entity_id, Map::new())
+            .await
+        {
+            self.ui_log(format!("Installing update {entity_id} failed: {err}"))
+                .await;
+        }
+    }
+
+    /// Keep a device renamed (or retyped) in the Hue app, by storing the
+    /// change as entity preferences
+    async fn backend_device_update(
+        &self,
+        link: &ResourceLink,
+        upd: &DeviceUpdate,
+    ) -> ApiResult<()> {
+        let Some(md) = &upd.metadata else {
+            return Ok(());
+        };
+        let Some(binding) = self.lookup_binding_by_device(link) else {
+            return Ok(());
+        };
+        let archetype = md
+            .archetype
+            .as_ref()
+            .filter(|_| binding.service_kind == HassServiceKind::Light)
+            .and_then(import::hass_light_archetype);
+
+        let mut ui = self.ui_state.lock().await;
+        if let Some(name) = &md.name {
+            ui.set_entity_alias(&binding.entity_id, Some(name.clone()));
+        }
+        if archetype.is_some() {
+            ui.set_entity_light_archetype(&binding.entity_id, archetype);
+        }
+        // A failed save only loses the change on restart, so keep going
+        if let Err(err) = ui.persist_and_log(&format!(
+            "[{}] Stored Hue app changes to {}",
+            self.name, binding.entity_id
+        )) {
+            log::warn!("[{}] Failed to save UI config: {err}", self.name);
+        }
+        drop(ui);
+
+        if binding.service_kind == HassServiceKind::Light {
+            let mut lock = self.state.lock().await;
+            lock.update::<Light>(&binding.service_link.rid, |light| {
+                if let Some(name) = &md.name {
+                    light.metadata.name.clone_from(name);
+                }
+                if let Some(archetype) = &md.archetype {
+                    light.metadata.archetype = archetype.clone();
+                }
+            })?;
+        }
+
+        Ok(())
+    }
+
+    /// Keep room changes made in the Hue app (name, type and devices) in the
+    /// room config
+    async fn backend_room_update(
+        &mut self,
+        link: &ResourceLink,
+        upd: &RoomUpdate,
+    ) -> ApiResult<()> {
+        let Some(room_id) = self
+            .room_map
+            .values()
+            .find(|room| room.room_link == *link)
+            .map(|room| room.room_id.clone())
+        else {
+            return Ok(());
+        };
+
+        let current = {
+            let lock = self.state.lock().await;
+            lock.get::<Room>(link)?.children.clone()
+        };
+
+        let mut ui = self.ui_state.lock().await;
+        if let Some(md) = &upd.metadata {
+            if let Some(name) = &md.name {
+                ui.rename_room(&room_id, name);
+            }
+            if let Some(archetype) = md.archetype {
+                ui.set_room_archetype(&room_id, archetype);
+            }
+        }
+        if let Some(children) = &upd.children {
+            for binding in self.entity_map.values() {
+                if children.contains(&binding.device_link) {
+                    ui.set_entity_room(&binding.entity_id, Some(room_id.clone()));
+                } else if current.contains(&binding.device_link) {
+                    // Taken out of the room, without (yet) being put elsewhere
+                    ui.set_entity_room(
+                        &binding.entity_id,
+                        Some(HassUiConfig::DEFAULT_ROOM_ID.to_string()),
+                    );
+                }
+            }
+        }
+        ui.persist_and_log(&format!(
+            "[{}] Stored Hue app changes to room {room_id}",
+            self.name
+        ))?;
+        drop(ui);
+
+        self.refresh_rooms_from_ui_config().await
+    }
+
+    /// Create a room made in the Hue app, if it is empty or holds any of our
+    /// devices. Rooms of only other backends' devices are left to them.
+    async fn backend_room_create(&mut self, link: &ResourceLink, room: &Room) -> ApiResult<()> {
+        let members = self
+            .entity_map
+            .values()
+            .filter(|binding| room.children.contains(&binding.device_link))
+            .map(|binding| binding.entity_id.clone())
+            .collect::<Vec<_>>();
+
+        if !room.children.is_empty() && members.is_empty() {
+            return Ok(());
+        }
+
+        let mut ui = self.ui_state.lock().await;
+
+        // The UI config is shared by all Home Assistant backends, so the
+        // first one to get here creates the room for all of them
+        if ui
+            .config
+            .rooms
+            .iter()
+            .any(|known| known.hue_room_id == Some(link.rid))
+        {
+            return Ok(());
+        }
+
+        let Some(created) = ui.add_hue_room(&room.metadata.name, link.rid, room.metadata.archetype)
+        else {
+            drop(ui);
+            let msg = format!(
+                "Cannot create room {:?} from the Hue app",
+                room.metadata.name
+            );
+            log::warn!("[{}] {msg}", self.name);
+            self.ui_log(msg).await;
+            return Ok(());
+        };
+        for entity_id in &members {
+            ui.set_entity_room(entity_id, Some(created.id.clone()));
+        }
+        ui.persist_and_log(&format!(
+            "[{}] Added room {} from the Hue app",
+            self.name, created.name
+        ))?;
+        drop(ui);
+
+        self.refresh_rooms_from_ui_config().await
+    }
+
+    /// Delete a room from the Hue app. Its scenes go with it, and its devices
+    /// fall back to the default room (or their area room).
+    async fn backend_room_delete(&mut self, link: &ResourceLink) -> ApiResult<()> {
+        let Some(room_id) = self
+            .room_map
+            .values()
+            .find(|room| room.room_link == *link)
+            .map(|room| room.room_id.clone())
+        else {
+            return Ok(());
+        };
+
+        if room_id == HassUiConfig::DEFAULT_ROOM_ID {
+            self.ui_log("The default room cannot be deleted").await;
+            return Ok(());
+        }
+
+        let scenes = self.state.lock().await.get_scenes_for_room(&link.rid);
+        for scene in scenes {
+            if let Err(err) = self
+                .backend_scene_delete(&RType::Scene.link_to(scene))
+                .await
+            {
+                self.ui_log(format!(
+                    "Deleting scene {scene} of room {room_id} failed: {err}"
+                ))
+                .await;
+            }
+        }
+
+        let mut ui = self.ui_state.lock().await;
+        ui.remove_room(&room_id);
+        // A failed save only loses the change on restart, so keep going
+        if let Err(err) = ui.persist_and_log(&format!(
+            "[{}] Removed room {room_id} from the Hue app",
+            self.name
+        )) {
+            log::warn!("[{}] Failed to save UI config: {err}", self.name);
+        }
+        drop(ui);
+
+        // The room is no longer configured, so this deletes it
+        self.refresh_rooms_from_ui_config().await
+    }
+
     pub(super) async fn handle_backend_event(&mut self, req: Arc<BackendRequest>) -> ApiResult<()> {
         match &*req {
             BackendRequest::LightUpdate(link, upd) => {
                 if let Some(binding) = self.lookup_binding_by_light(link) {
-                    self.backend_light_update(&binding, upd).await?;
+                    self.light_command(&binding, upd).await;
                 }
             }
             BackendRequest::SensorEnabledUpdate(link, enabled) => {
@@ -376,15 +806,155 @@ impl HassBackend {
             BackendRequest::SceneUpdate(link, upd) => {
                 self.backend_scene_update(link, upd).await?;
             }
+            BackendRequest::EntertainmentStart(ent_id) => {
+                self.backend_entertainment_start(ent_id).await?;
+            }
+            BackendRequest::EntertainmentFrame(frame) => {
+                self.backend_entertainment_frame(frame);
+            }
+            BackendRequest::EntertainmentStop() => {
+                self.backend_entertainment_stop().await?;
+            }
 
-            BackendRequest::RoomUpdate(_, _)
-            | BackendRequest::Delete(_)
-            | BackendRequest::EntertainmentStart(_)
-            | BackendRequest::EntertainmentFrame(_)
-            | BackendRequest::EntertainmentStop()
-            | BackendRequest::ZigbeeDeviceDiscovery(_, _) => {}
+            BackendRequest::Delete(link) => match link.rtype {
+                RType::Scene => self.backend_scene_delete(link).await?,
+                RType::Room => {
+                    if let Err(err) = self.backend_room_delete(link).await {
+                        self.ui_log(format!("Deleting room failed: {err}")).await;
+                    }
+                }
+                _ => {}
+            },
+
+            BackendRequest::RoomCreate(link, room) => {
+                if let Err(err) = self.backend_room_create(link, room).await {
+                    self.ui_log(format!("Creating room failed: {err}")).await;
+                }
+            }
+            BackendRequest::RoomUpdate(link, upd) => {
+                if let Err(err) = self.backend_room_update(link, upd).await {
+                    self.ui_log(format!("Storing room changes failed: {err}"))
+                        .await;
+                }
+            }
+            BackendRequest::DeviceUpdate(link, upd) => {
+                if let Err(err) = self.backend_device_update(link, upd).await {
+                    self.ui_log(format!("Storing device changes failed: {err}"))
+                        .await;
+                }
+            }
+            BackendRequest::SoftwareUpdateInstall(link) => {
+                self.backend_software_update_install(link).await;
+            }
+
+            BackendRequest::ZigbeeDeviceDiscovery(_, _) => {}
         }
 
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use hue::api::{LightUpdate, On, RType};
+    use hue::xy::XY;
+
+    use crate::backend::hass::effects::HassEffectMap;
+    use crate::backend::hass::{
+        HassColorMode, HassEntityBinding, HassEntityKind, HassLightCapabilities, HassServiceKind,
+    };
+
+    use super::{LightBatches, light_service_call};
+
+    fn binding(entity_id: &str, kind: HassEntityKind) -> HassEntityBinding {
+        HassEntityBinding {
+            entity_id: entity_id.to_string(),
+            name: entity_id.to_string(),
+            kind,
+            service_kind: HassServiceKind::Light,
+            service_link: RType::Light.deterministic(entity_id),
+            device_link: RType::Device.deterministic(entity_id),
+            capabilities: HassLightCapabilities {
+                supports_brightness: true,
+                supports_color: true,
+                supports_color_temp: true,
+                color_mode: HassColorMode::Xy,
+                ..HassLightCapabilities::default()
+            },
+            effects: HassEffectMap::default(),
+            switch_mode: None,
+        }
+    }
+
+    #[test]
+    fn light_update_to_service_call() {
+        let light = binding("light.desk", HassEntityKind::Light);
+
+        let off = light_service_call(&light, &LightUpdate::new().with_on(On::new(false))).unwrap();
+        assert_eq!((off.service, off.data.len()), ("turn_off", 0));
+
+        let upd = LightUpdate::new()
+            .with_brightness(Some(50.0))
+            .with_color_temperature(250)
+            .with_color_xy(XY::new(0.3, 0.3));
+        let call = light_service_call(&light, &upd).unwrap();
+        assert_eq!((call.domain, call.service), ("light", "turn_on"));
+        assert_eq!(call.data["brightness"], json!(128));
+        assert_eq!(call.data["color_temp_kelvin"], json!(4000));
+        assert_eq!(call.data["xy_color"], json!([0.3, 0.3]));
+
+        assert!(light_service_call(&light, &LightUpdate::new()).is_none());
+    }
+
+    #[test]
+    fn switch_only_turns_on_and_off() {
+        let switch = binding("switch.fan", HassEntityKind::Switch);
+
+        let on = light_service_call(&switch, &LightUpdate::new().with_on(On::new(true))).unwrap();
+        assert_eq!((on.domain, on.service), ("switch", "turn_on"));
+        assert!(
+            light_service_call(&switch, &LightUpdate::new().with_brightness(Some(10.0))).is_none()
+        );
+
+        let sensor = binding("sensor.temp", HassEntityKind::Sensor);
+        assert!(light_service_call(&sensor, &LightUpdate::new().with_on(On::new(true))).is_none());
+    }
+
+    #[test]
+    fn batches_share_equal_calls() {
+        let upd = LightUpdate::new().with_brightness(Some(100.0));
+        let off = LightUpdate::new().with_on(On::new(false));
+
+        let mut batches = LightBatches::default();
+        batches.add(binding("light.a", HassEntityKind::Light), &upd);
+        batches.add(binding("light.b", HassEntityKind::Light), &upd);
+        batches.add(binding("light.c", HassEntityKind::Light), &off);
+        batches.add(binding("switch.d", HassEntityKind::Switch), &off);
+        batches.add(
+            binding("light.e", HassEntityKind::Light),
+            &LightUpdate::new(),
+        );
+
+        let batches = batches
+            .0
+            .iter()
+            .map(|(call, bindings)| {
+                let ids = bindings
+                    .iter()
+                    .map(|b| b.entity_id.as_str())
+                    .collect::<Vec<_>>();
+                (call.domain, call.service, ids)
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            batches,
+            [
+                ("light", "turn_on", vec!["light.a", "light.b"]),
+                ("light", "turn_off", vec!["light.c"]),
+                ("switch", "turn_off", vec!["switch.d"]),
+            ]
+        );
+    }
+}
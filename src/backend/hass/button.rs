@@ -0,0 +1,475 @@
+//! Remotes and dials, from Home Assistant `event.*` entities.
+//!
+//! ZHA remotes have no such entities, only device triggers (fired as
+//! `zha_event`). Each of their buttons is exposed as a made up
+//! `device_trigger.*` entity instead, which goes through the same import as
+//! event entities, and fires when Home Assistant reports the trigger.
+
+use std::collections::{BTreeMap, HashMap};
+use std::time::Duration as StdDuration;
+
+use chrono::{DateTime, Duration, Utc};
+use serde_json::{Map, Value, json};
+
+use hue::api::{Button, ButtonData, ButtonMetadata, ButtonReport, RelativeRotary, ResourceLink};
+
+use crate::backend::hass::client::{HassClient, HassState};
+use crate::backend::hass::registry::HassRegistry;
+use crate::backend::hass::{HassBackend, HassEntityBinding, HassServiceKind};
+use crate::error::ApiResult;
+use crate::resource::Resources;
+
+/// Hue button events, in the order a real Hue remote reports them
+pub const BUTTON_EVENT_VALUES: [&str; 6] = [
+    "initial_press",
+    "repeat",
+    "short_release",
+    "long_press",
+    "long_release",
+    "double_short_release",
+];
+
+/// Rotary events closer together than this continue a turn ("repeat")
+const ROTARY_REPEAT_WINDOW_MS: i64 = 1000;
+
+/// Domain of the entities made up for device trigger buttons
+pub const DEVICE_TRIGGER_DOMAIN: &str = "device_trigger";
+
+const TRIGGER_SUBSCRIBE_TIMEOUT: StdDuration = StdDuration::from_secs(10);
+
+/// A remote button that Home Assistant only reports through device triggers
+#[derive(Clone, Debug)]
+pub struct HassRemoteButton {
+    pub entity_id: String,
+    pub name: String,
+    /// Trigger configs, each with an `id` of `<entity_id>|<trigger type>`
+    pub triggers: Vec<Value>,
+}
+
+impl HassRemoteButton {
+    fn trigger_types(&self) -> impl Iterator<Item = &str> {
+        self.triggers
+            .iter()
+            .filter_map(|trigger| trigger.get("type").and_then(Value::as_str))
+    }
+
+    fn reports_release(&self) -> bool {
+        self.trigger_types()
+            .any(|t| t == "remote_button_short_release")
+    }
+
+    /// Made up state, as an `event.*` entity would have it
+    #[must_use]
+    pub fn state(&self, trigger_type: Option<&str>) -> HassState {
+        let reports_release = self.reports_release();
+        let event_types = self
+            .trigger_types()
+            .map(|t| trigger_event_type(t, reports_release))
+            .collect::<Vec<_>>();
+
+        let mut attributes = Map::new();
+        attributes.insert("friendly_name".to_string(), json!(self.name));
+        attributes.insert("event_types".to_string(), json!(event_types));
+        if let Some(trigger_type) = trigger_type {
+            attributes.insert(
+                "event_type".to_string(),
+                json!(trigger_event_type(trigger_type, reports_release)),
+            );
+        }
+
+        HassState {
+            entity_id: self.entity_id.clone(),
+            state: "unknown".to_string(),
+            attributes,
+        }
+    }
+}
+
+/// Translate a device trigger type (like `remote_button_short_press`) into
+/// the event type an `event.*` entity would report.
+///
+/// Remotes that report the release separately fire `short_press` for the
+/// press itself.
+#[must_use]
+pub fn trigger_event_type(trigger_type: &str, reports_release: bool) -> &str {
+    let event = trigger_type
+        .strip_prefix("remote_button_")
+        .unwrap_or(trigger_type);
+    if event == "short_press" && reports_release {
+        "initial_press"
+    } else {
+        event
+    }
+}
+
+/// Group the button triggers of a device (as listed by
+/// `device_automation/trigger/list`) into one button per subtype
+#[must_use]
+pub fn remote_buttons(
+    device_id: &str,
+    device_name: &str,
+    triggers: &[Value],
+) -> BTreeMap<String, HassRemoteButton> {
+    let mut buttons = BTreeMap::new();
+
+    for trigger in triggers {
+        let (Some(trigger_type), Some(subtype)) = (
+            trigger.get("type").and_then(Value::as_str),
+            trigger.get("subtype").and_then(Value::as_str),
+        ) else {
+            continue;
+        };
+        if !trigger_type.starts_with("remote_button_") {
+            continue;
+        }
+
+        let entity_id = format!("{DEVICE_TRIGGER_DOMAIN}.{device_id}_{subtype}");
+        let mut config = trigger.clone();
+        if let Some(obj) = config.as_object_mut() {
+            // Only informative, and not accepted when subscribing
+            obj.remove("metadata");
+            obj.insert(
+                "id".to_string(),
+                json!(format!("{entity_id}|{trigger_type}")),
+            );
+        }
+
+        buttons
+            .entry(entity_id.clone())
+            .or_insert_with(|| HassRemoteButton {
+                entity_id,
+                name: format!("{device_name} {}", subtype.replace('_', " ")),
+                triggers: vec![],
+            })
+            .triggers
+            .push(config);
+    }
+
+    buttons
+}
+
+pub fn event_types(state: &HassState) -> Vec<String> {
+    state
+        .attributes
+        .get("event_types")
+        .and_then(Value::as_array)
+        .map(|arr| {
+            arr.iter()
+                .filter_map(Value::as_str)
+                .map(str::to_ascii_lowercase)
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn rotary_direction(event_type: &str) -> Option<&'static str> {
+    match event_type {
+        "clock_wise" | "clockwise" | "rotate_right" | "rotate_cw" => Some("clock_wise"),
+        "counter_clock_wise" | "counterclockwise" | "rotate_left" | "rotate_ccw" => {
+            Some("counter_clock_wise")
+        }
+        _ => None,
+    }
+}
+
+/// Dials are event entities that only ever report rotation
+pub fn is_rotary(event_types: &[String]) -> bool {
+    !event_types.is_empty() && event_types.iter().all(|t| rotary_direction(t).is_some())
+}
+
+/// Translate a Home Assistant event type into the Hue button events it implies.
+///
+/// Integrations that report the press separately (like the Hue integration)
+/// get a 1:1 mapping. For integrations that only report "single" clicks, the
+/// `initial_press` is synthesized, since Hue clients expect it first.
+pub fn button_events(event_type: &str, reports_press: bool) -> &'static [&'static str] {
+    match event_type {
+        "initial_press" | "press" | "pressed" | "down" => &["initial_press"],
+        "repeat" | "hold_repeat" => &["repeat"],
+        "long_press" | "hold" | "long" => &["long_press"],
+        "long_release" | "hold_release" | "release_after_hold" => &["long_release"],
+        "double_short_release" | "double" | "double_press" | "double_click" => {
+            &["double_short_release"]
+        }
+        "short_release" | "release" | "single" | "click" | "short_press" | "single_press"
+        | "up" => {
+            if reports_press {
+                &["short_release"]
+            } else {
+                &["initial_press", "short_release"]
+            }
+        }
+        _ => &[],
+    }
+}
+
+#[must_use]
+pub fn make_button(owner: ResourceLink) -> Button {
+    Button {
+        owner,
+        metadata: ButtonMetadata { control_id: 1 },
+        button: ButtonData {
+            button_report: None,
+            last_event: None,
+            repeat_interval: Some(800),
+            event_values: Some(json!(BUTTON_EVENT_VALUES)),
+        },
+    }
+}
+
+#[must_use]
+pub const fn make_relative_rotary(owner: ResourceLink) -> RelativeRotary {
+    RelativeRotary {
+        owner,
+        relative_rotary: None,
+        rotary_report: None,
+    }
+}
+
+fn last_rotary_update(rotary: &RelativeRotary) -> Option<DateTime<Utc>> {
+    rotary
+        .relative_rotary
+        .as_ref()?
+        .get("rotary_report")?
+        .get("updated")?
+        .as_str()?
+        .parse()
+        .ok()
+}
+
+fn attribute_u64(state: &HassState, key: &str) -> Option<u64> {
+    state.attributes.get(key).and_then(Value::as_u64)
+}
+
+/// Find the buttons of ZHA remotes, and register them as entities of their
+/// device.
+pub async fn discover_remote_buttons(
+    client: &HassClient,
+    registry: &mut HassRegistry,
+) -> ApiResult<HashMap<String, HassRemoteButton>> {
+    let devices = registry
+        .devices_without("zha", "event")
+        .map(|device| {
+            let name = device.display_name().unwrap_or(&device.id).to_string();
+            (device.id.clone(), name)
+        })
+        .collect::<Vec<_>>();
+    let ids = devices
+        .iter()
+        .map(|(id, _)| id.as_str())
+        .collect::<Vec<_>>();
+    let triggers = client.get_device_triggers(&ids).await?;
+
+    let mut buttons = HashMap::new();
+    for ((device_id, name), triggers) in devices.iter().zip(triggers) {
+        for (entity_id, button) in remote_buttons(device_id, name, &triggers) {
+            registry.add_entity(&entity_id, device_id);
+            buttons.insert(entity_id, button);
+        }
+    }
+    Ok(buttons)
+}
+
+impl HassBackend {
+    /// Follow the device triggers of all known remote buttons on the
+    /// websocket connection, if there is one.
+    pub(super) async fn subscribe_remote_buttons(&mut self) {
+        let Some(ws) = &mut self.ws else {
+            return;
+        };
+        let triggers = self
+            .remote_buttons
+            .values()
+            .flat_map(|button| button.triggers.iter().cloned())
+            .collect();
+        let err = match ws
+            .subscribe_triggers(triggers, TRIGGER_SUBSCRIBE_TIMEOUT)
+            .await
+        {
+            Ok(Ok(())) => return,
+            Ok(Err(err)) => err.to_string(),
+            Err(err) => err.to_string(),
+        };
+        log::warn!("[{}] Cannot follow remote buttons: {err}", self.name);
+        self.ui_log(format!("Cannot follow remote buttons: {err}"))
+            .await;
+    }
+
+    /// Push a fired device trigger to its button, if that is exposed
+    pub(super) async fn handle_trigger(&self, trigger_id: &str) -> ApiResult<()> {
+        let Some((entity_id, trigger_type)) = trigger_id.split_once('|') else {
+            return Ok(());
+        };
+        let (Some(button), Some(binding)) = (
+            self.remote_buttons.get(entity_id),
+            self.entity_map.get(entity_id),
+        ) else {
+            return Ok(());
+        };
+
+        let state = button.state(Some(trigger_type));
+        let mut res = self.state.lock().await;
+        Self::emit_entity_event(binding, &state, &mut res)
+    }
+
+    /// Push a fired `event.*` entity to the Hue event stream as button or
+    /// rotary activity.
+    pub(super) fn emit_entity_event(
+        binding: &HassEntityBinding,
+        state: &HassState,
+        res: &mut Resources,
+    ) -> ApiResult<()> {
+        let Some(event_type) = state
+            .attributes
+            .get("event_type")
+            .and_then(Value::as_str)
+            .map(str::to_ascii_lowercase)
+        else {
+            return Ok(());
+        };
+
+        let link = binding.service_link;
+        let now = Utc::now();
+
+        match binding.service_kind {
+            HassServiceKind::Button => {
+                let reports_press = event_types(state).iter().any(|t| t == "initial_press");
+                for event in button_events(&event_type, reports_press) {
+                    res.update::<Button>(&link.rid, |button| {
+                        button.button.button_report = Some(ButtonReport {
+                            updated: now,
+                            event: (*event).to_string(),
+                        });
+                        button.button.last_event = Some(json!(event));
+                    })?;
+                }
+            }
+            HassServiceKind::RelativeRotary => {
+                let Some(direction) = rotary_direction(&event_type) else {
+                    return Ok(());
+                };
+                let previous = last_rotary_update(res.get::<RelativeRotary>(&link)?);
+                let action = if previous.is_some_and(|prev| {
+                    now - prev < Duration::milliseconds(ROTARY_REPEAT_WINDOW_MS)
+                }) {
+                    "repeat"
+                } else {
+                    "start"
+                };
+                let rotation = json!({
+                    "direction": direction,
+                    "steps": attribute_u64(state, "steps").unwrap_or(1),
+                    "duration": attribute_u64(state, "duration").unwrap_or(400),
+                });
+                let event = json!({
+                    "action": action,
+                    "rotation": rotation,
+                });
+
+                res.update::<RelativeRotary>(&link.rid, |rotary| {
+                    rotary.relative_rotary = Some(json!({
+                        "last_event": event,
+                        "rotary_report": {
+                            "updated": now.to_rfc3339(),
+                            "action": action,
+                            "rotation": rotation,
+                        },
+                    }));
+                })?;
+            }
+            HassServiceKind::Light
+            | HassServiceKind::Switch
+            | HassServiceKind::Motion
+            | HassServiceKind::Contact
+            | HassServiceKind::Temperature
+            | HassServiceKind::LightLevel => {}
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::{button_events, is_rotary, remote_buttons, trigger_event_type};
+
+    #[test]
+    fn button_events_synthesize_press() {
+        assert_eq!(
+            button_events("single", false),
+            ["initial_press", "short_release"]
+        );
+        assert_eq!(button_events("short_release", true), ["short_release"]);
+        assert_eq!(button_events("initial_press", true), ["initial_press"]);
+        assert_eq!(button_events("hold", false), ["long_press"]);
+        assert_eq!(button_events("double", false), ["double_short_release"]);
+        assert!(button_events("triple", false).is_empty());
+    }
+
+    #[test]
+    fn dials_only_rotate() {
+        assert!(is_rotary(&["clockwise".into(), "rotate_left".into()]));
+        assert!(!is_rotary(&["clockwise".into(), "single".into()]));
+        assert!(!is_rotary(&[]));
+    }
+
+    #[test]
+    fn trigger_types_map_to_events() {
+        assert_eq!(
+            trigger_event_type("remote_button_short_press", false),
+            "short_press"
+        );
+        assert_eq!(
+            trigger_event_type("remote_button_short_press", true),
+            "initial_press"
+        );
+        assert_eq!(
+            trigger_event_type("remote_button_long_release", true),
+            "long_release"
+        );
+    }
+
+    #[test]
+    fn remote_buttons_by_subtype() {
+        let trigger = |kind: &str, subtype: &str| {
+            json!({
+                "platform": "device",
+                "domain": "zha",
+                "device_id": "abc",
+                "type": kind,
+                "subtype": subtype,
+                "metadata": {},
+            })
+        };
+        let triggers = [
+            trigger("remote_button_short_press", "turn_on"),
+            trigger("remote_button_long_press", "turn_on"),
+            trigger("remote_button_short_press", "turn_off"),
+            trigger("device_offline", "device_offline"),
+        ];
+
+        let buttons = remote_buttons("abc", "Remote", &triggers);
+        assert_eq!(
+            buttons.keys().collect::<Vec<_>>(),
+            ["device_trigger.abc_turn_off", "device_trigger.abc_turn_on"]
+        );
+
+        let on = &buttons["device_trigger.abc_turn_on"];
+        assert_eq!(on.name, "Remote turn on");
+        assert_eq!(on.triggers.len(), 2);
+        assert_eq!(
+            on.triggers[1]["id"],
+            "device_trigger.abc_turn_on|remote_button_long_press"
+        );
+        assert!(on.triggers[1].get("metadata").is_none());
+
+        let state = on.state(Some("remote_button_short_press"));
+        assert_eq!(state.attributes["event_type"], "short_press");
+        assert_eq!(
+            state.attributes["event_types"],
+            json!(["short_press", "long_press"])
+        );
+    }
+}
@@ -1,4 +1,4 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
 use std::time::Duration;
 
 use futures::{SinkExt, StreamExt};
@@ -13,9 +13,10 @@ use url::Url;
 
 use bifrost_api::config::HassServer;
 
+use crate::backend::hass::registry::HassRegistry;
 use crate::error::{ApiError, ApiResult};
 
-#[derive(Clone, Debug, Deserialize)]
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq)]
 pub struct HassState {
     pub entity_id: String,
     pub state: String,
@@ -34,6 +35,7 @@ pub struct HassCoreConfig {
     pub longitude: Option<f64>,
 }
 
+#[derive(Clone)]
 pub struct HassClient {
     backend_name: String,
     base_url: Url,
@@ -48,11 +50,25 @@ pub struct HassStateChangedEvent {
     pub old_state: Option<HassState>,
 }
 
+/// Events the backend subscribes to
+#[derive(Clone, Debug)]
+pub enum HassWsEvent {
+    StateChanged(HassStateChangedEvent),
+    /// Home Assistant finished (re)starting
+    Started,
+    /// A subscribed device trigger fired, with the id it was subscribed with
+    Triggered(String),
+}
+
 #[derive(Debug, Deserialize)]
 struct HassWsEventEnvelope {
     #[serde(default)]
     pub event_type: String,
-    pub data: HassWsEventData,
+    #[serde(default)]
+    pub data: Value,
+    /// Set instead of `event_type` for `subscribe_trigger` events
+    #[serde(default)]
+    pub variables: Value,
 }
 
 #[derive(Debug, Deserialize)]
@@ -86,6 +102,10 @@ enum HassWsIncoming {
 
 pub struct HassWs {
     socket: WebSocketStream<MaybeTlsStream<TcpStream>>,
+    next_id: u64,
+    queued: VecDeque<HassWsEvent>,
+    /// Id of the current `subscribe_trigger` subscription
+    triggers: Option<u64>,
 }
 
 impl HassWs {
@@ -100,20 +120,152 @@ impl HassWs {
         Ok(Some(serde_json::from_str::<HassWsIncoming>(&text)?))
     }
 
-    pub async fn next_state_changed(&mut self) -> ApiResult<Option<HassStateChangedEvent>> {
+    fn parse_event(event: HassWsEventEnvelope) -> ApiResult<Option<HassWsEvent>> {
+        match event.event_type.as_str() {
+            "state_changed" => {
+                let data: HassWsEventData = serde_json::from_value(event.data)?;
+                Ok(Some(HassWsEvent::StateChanged(HassStateChangedEvent {
+                    entity_id: data.entity_id,
+                    new_state: data.new_state,
+                    old_state: data.old_state,
+                })))
+            }
+            "homeassistant_started" => Ok(Some(HassWsEvent::Started)),
+            "" => Ok(event
+                .variables
+                .pointer("/trigger/id")
+                .and_then(Value::as_str)
+                .map(|id| HassWsEvent::Triggered(id.to_string()))),
+            _ => Ok(None),
+        }
+    }
+
+    pub async fn next_event(&mut self) -> ApiResult<Option<HassWsEvent>> {
+        if let Some(event) = self.queued.pop_front() {
+            return Ok(Some(event));
+        }
         while let Some(msg) = self.recv_json().await? {
-            if let HassWsIncoming::Event { event } = msg {
-                if event.event_type == "state_changed" {
-                    return Ok(Some(HassStateChangedEvent {
-                        entity_id: event.data.entity_id,
-                        new_state: event.data.new_state,
-                        old_state: event.data.old_state,
-                    }));
-                }
+            let HassWsIncoming::Event { event } = msg else {
+                continue;
+            };
+            if let Some(event) = Self::parse_event(event)? {
+                return Ok(Some(event));
             }
         }
         Ok(None)
     }
+
+    /// Call a service over the websocket and wait for its result.
+    ///
+    /// Events received while waiting are kept for [`Self::next_event`]. The
+    /// outer error means the command was not delivered or timed out, the
+    /// inner one carries the error Home Assistant refused the command with.
+    pub async fn call_service(
+        &mut self,
+        domain: &str,
+        service: &str,
+        entity_id: &str,
+        data: Map<String, Value>,
+        timeout: Duration,
+    ) -> ApiResult<Result<(), Value>> {
+        let mut cmd = serde_json::json!({
+            "type": "call_service",
+            "domain": domain,
+            "service": service,
+            "service_data": data,
+        });
+        if !entity_id.trim().is_empty() {
+            cmd["target"] = serde_json::json!({ "entity_id": entity_id });
+        }
+        self.command(cmd, &format!("{domain}.{service}"), timeout)
+            .await
+            .map(|res| res.map(|_| ()))
+    }
+
+    /// Replace the device triggers this connection is subscribed to. Each
+    /// trigger needs an `id`, which is what [`HassWsEvent::Triggered`]
+    /// reports when it fires.
+    pub async fn subscribe_triggers(
+        &mut self,
+        triggers: Vec<Value>,
+        timeout: Duration,
+    ) -> ApiResult<Result<(), Value>> {
+        if let Some(subscription) = self.triggers.take() {
+            let cmd = serde_json::json!({
+                "type": "unsubscribe_events",
+                "subscription": subscription,
+            });
+            if let Err(err) = self.command(cmd, "unsubscribe_events", timeout).await? {
+                return Ok(Err(err));
+            }
+        }
+        if triggers.is_empty() {
+            return Ok(Ok(()));
+        }
+
+        let cmd = serde_json::json!({
+            "type": "subscribe_trigger",
+            "trigger": triggers,
+        });
+        let res = self.command(cmd, "subscribe_trigger", timeout).await?;
+        if let Ok(id) = res {
+            self.triggers = Some(id);
+        }
+        Ok(res.map(|_| ()))
+    }
+
+    /// Send a command and wait for its result, returning the id it was
+    /// sent with.
+    ///
+    /// Events received while waiting are kept for [`Self::next_event`].
+    async fn command(
+        &mut self,
+        mut cmd: Value,
+        what: &str,
+        timeout: Duration,
+    ) -> ApiResult<Result<u64, Value>> {
+        let id = self.next_id;
+        self.next_id += 1;
+
+        cmd["id"] = Value::from(id);
+        self.socket
+            .send(Message::Text(cmd.to_string().into()))
+            .await?;
+
+        let wait = async {
+            while let Some(msg) = self.recv_json().await? {
+                match msg {
+                    HassWsIncoming::Result {
+                        id: res_id,
+                        success,
+                        error,
+                    } if res_id == id => {
+                        return Ok(if success {
+                            Ok(id)
+                        } else {
+                            Err(error.unwrap_or(Value::Null))
+                        });
+                    }
+                    HassWsIncoming::Event { event } => {
+                        if let Some(event) = Self::parse_event(event)? {
+                            self.queued.push_back(event);
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            Err(ApiError::service_error(format!(
+                "Home Assistant websocket closed while calling {what}"
+            )))
+        };
+
+        tokio::time::timeout(timeout, wait).await.map_err(|_| {
+            ApiError::service_error(format!(
+                "Home Assistant did not answer {what} within {:.1}s",
+                timeout.as_secs_f64()
+            ))
+        })?
+    }
 }
 
 #[derive(Debug, Serialize)]
@@ -279,7 +431,7 @@ impl HassClient {
     pub async fn get_entity_areas(&self) -> ApiResult<HashMap<String, String>> {
         // Returns one line per entity in format: entity_id|area_name
         let template = r#"
-{% for s in states if s.entity_id.startswith('light.') or s.entity_id.startswith('switch.') or s.entity_id.startswith('binary_sensor.') %}
+{% for s in states if s.domain in ['light', 'switch', 'binary_sensor', 'sensor', 'event'] %}
 {{ s.entity_id }}|{{ area_name(s.entity_id) or '' }}
 {% endfor %}
 "#;
@@ -341,6 +493,68 @@ impl HassClient {
         Ok(())
     }
 
+    /// Stored configuration of a scene made in the Home Assistant UI, or
+    /// `None` if the scene is defined elsewhere.
+    pub async fn get_scene_config(&self, id: &str) -> ApiResult<Option<Value>> {
+        let url = self.endpoint_url(&format!("/api/config/scene/config/{id}"))?;
+        let response = self.http.get(url).bearer_auth(self.token()?).send().await?;
+        if response.status() == StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+        let response = self
+            .check_status(response, "GET /api/config/scene/config")
+            .await?;
+        Ok(Some(response.json().await?))
+    }
+
+    pub async fn set_scene_config(&self, id: &str, config: &Value) -> ApiResult<()> {
+        let url = self.endpoint_url(&format!("/api/config/scene/config/{id}"))?;
+        let response = self
+            .http
+            .post(url)
+            .bearer_auth(self.token()?)
+            .json(config)
+            .send()
+            .await?;
+        self.check_status(response, "POST /api/config/scene/config")
+            .await?;
+        Ok(())
+    }
+
+    pub async fn delete_scene_config(&self, id: &str) -> ApiResult<()> {
+        let url = self.endpoint_url(&format!("/api/config/scene/config/{id}"))?;
+        let response = self
+            .http
+            .delete(url)
+            .bearer_auth(self.token()?)
+            .send()
+            .await?;
+        self.check_status(response, "DELETE /api/config/scene/config")
+            .await?;
+        Ok(())
+    }
+
+    /// Create (or replace) a scene from explicit entity states, like
+    /// [`Self::create_scene_snapshot`] does from the current ones.
+    pub async fn create_scene(
+        &self,
+        scene_id: &str,
+        name: &str,
+        entities: Map<String, Value>,
+    ) -> ApiResult<()> {
+        let mut data = Map::new();
+        data.insert("scene_id".to_string(), Value::String(scene_id.to_string()));
+        data.insert("name".to_string(), Value::String(name.to_string()));
+        data.insert("entities".to_string(), Value::Object(entities));
+        self.call_service("scene", "create", "", data).await
+    }
+
+    /// Delete a scene made with `scene.create`
+    pub async fn delete_scene(&self, entity_id: &str) -> ApiResult<()> {
+        self.call_service("scene", "delete", entity_id, Map::new())
+            .await
+    }
+
     pub async fn create_scene_snapshot(
         &self,
         scene_id: &str,
@@ -378,84 +592,32 @@ impl HassClient {
     }
 
     pub async fn subscribe_state_changed(&self) -> ApiResult<HassWs> {
-        let ws_url = self.ws_endpoint_url()?;
-        let (mut socket, _response) = connect_async(ws_url.as_str()).await?;
-
-        // Consume initial auth challenge.
-        let _ = socket.next().await;
-
-        let auth = serde_json::json!({
-            "type": "auth",
-            "access_token": self.token()?,
-        });
-        socket.send(Message::Text(auth.to_string().into())).await?;
-
-        // Wait for auth_ok.
-        loop {
-            let Some(msg) = socket.next().await else {
-                return Err(ApiError::service_error(format!(
-                    "[{}] Home Assistant websocket closed during auth",
-                    self.backend_name
-                )));
-            };
-            let msg = msg.map_err(ApiError::from)?;
-            if let Message::Text(text) = msg {
-                let value: HassWsIncoming = serde_json::from_str(&text)?;
-                match value {
-                    HassWsIncoming::AuthOk => break,
-                    HassWsIncoming::AuthInvalid => {
-                        return Err(ApiError::service_error(format!(
-                            "[{}] Home Assistant websocket auth failed (check token)",
-                            self.backend_name
-                        )));
-                    }
-                    _ => {}
-                }
-            }
-        }
-
-        // Subscribe to state_changed events.
-        let sub = serde_json::json!({
-            "id": 1,
-            "type": "subscribe_events",
-            "event_type": "state_changed",
-        });
-        socket.send(Message::Text(sub.to_string().into())).await?;
-
-        // Wait for subscribe result.
-        loop {
-            let Some(msg) = socket.next().await else {
+        let mut ws = HassWs {
+            socket: self.connect_ws().await?,
+            next_id: 1,
+            queued: VecDeque::new(),
+            triggers: None,
+        };
+        let timeout = Duration::from_secs(Self::DEFAULT_TIMEOUT_SECS);
+
+        for event_type in ["state_changed", "homeassistant_started"] {
+            let sub = serde_json::json!({
+                "type": "subscribe_events",
+                "event_type": event_type,
+            });
+            if let Err(err) = ws.command(sub, "subscribe_events", timeout).await? {
                 return Err(ApiError::service_error(format!(
-                    "[{}] Home Assistant websocket closed during subscribe",
+                    "[{}] Home Assistant subscribe_events failed: {err}",
                     self.backend_name
                 )));
-            };
-            let msg = msg.map_err(ApiError::from)?;
-            if let Message::Text(text) = msg {
-                let value: HassWsIncoming = serde_json::from_str(&text)?;
-                if let HassWsIncoming::Result { id, success, error } = value {
-                    if id == 1 && success {
-                        break;
-                    }
-                    if id == 1 && !success {
-                        return Err(ApiError::service_error(format!(
-                            "[{}] Home Assistant subscribe_events failed: {}",
-                            self.backend_name,
-                            error.unwrap_or(Value::Null)
-                        )));
-                    }
-                }
             }
         }
 
-        Ok(HassWs { socket })
+        Ok(ws)
     }
 
-    pub async fn set_entity_registry_disabled(
-        &self,
-        entity_id: &str,
-        disabled: bool,
-    ) -> ApiResult<()> {
+    /// Open a websocket connection and authenticate it
+    async fn connect_ws(&self) -> ApiResult<WebSocketStream<MaybeTlsStream<TcpStream>>> {
         let ws_url = self.ws_endpoint_url()?;
         let (mut socket, _response) = connect_async(ws_url.as_str()).await?;
 
@@ -488,42 +650,237 @@ impl HassClient {
             })?
             .map_err(ApiError::from)?;
         if let Message::Text(text) = auth_reply {
+            match serde_json::from_str(&text)? {
+                HassWsIncoming::AuthOk => {}
+                HassWsIncoming::AuthInvalid => {
+                    return Err(ApiError::service_error(format!(
+                        "[{}] Home Assistant websocket auth failed (check token)",
+                        self.backend_name
+                    )));
+                }
+                _ => {
+                    return Err(ApiError::service_error(format!(
+                        "[{}] Home Assistant websocket auth failed: {text}",
+                        self.backend_name
+                    )));
+                }
+            }
+        }
+
+        Ok(socket)
+    }
+
+    /// Run websocket commands on a fresh connection, returning their results
+    /// in order. Each command is sent without `id`, which is assigned here.
+    async fn ws_commands(&self, commands: Vec<Value>) -> ApiResult<Vec<Value>> {
+        let mut socket = self.connect_ws().await?;
+
+        let count = commands.len();
+        for (id, mut cmd) in (1_u64..).zip(commands) {
+            cmd["id"] = Value::from(id);
+            socket.send(Message::Text(cmd.to_string().into())).await?;
+        }
+
+        let mut results = vec![Value::Null; count];
+        let mut pending = count;
+        while pending > 0 {
+            let Some(msg) = socket.next().await else {
+                return Err(ApiError::service_error(format!(
+                    "[{}] Home Assistant websocket closed before all replies arrived",
+                    self.backend_name
+                )));
+            };
+            let Message::Text(text) = msg? else {
+                continue;
+            };
             let value: Value = serde_json::from_str(&text)?;
-            if value.get("type").and_then(Value::as_str) != Some("auth_ok") {
+            if value.get("type").and_then(Value::as_str) != Some("result") {
+                continue;
+            }
+            let Some(index) = value
+                .get("id")
+                .and_then(Value::as_u64)
+                .and_then(|id| usize::try_from(id).ok())
+                .and_then(|id| id.checked_sub(1))
+                .filter(|index| *index < count)
+            else {
+                continue;
+            };
+            if value.get("success").and_then(Value::as_bool) != Some(true) {
                 return Err(ApiError::service_error(format!(
-                    "[{}] Home Assistant websocket auth failed: {}",
+                    "[{}] Home Assistant websocket command failed: {}",
                     self.backend_name, value
                 )));
             }
+            results[index] = value.get("result").cloned().unwrap_or(Value::Null);
+            pending -= 1;
+        }
+
+        Ok(results)
+    }
+
+    /// Fetch the device, entity and area registries in one go
+    pub async fn get_registry(&self) -> ApiResult<HassRegistry> {
+        let results = self
+            .ws_commands(vec![
+                serde_json::json!({"type": "config/device_registry/list"}),
+                serde_json::json!({"type": "config/entity_registry/list"}),
+                serde_json::json!({"type": "config/area_registry/list"}),
+            ])
+            .await?;
+        let [devices, entities, areas]: [Value; 3] = results.try_into().map_err(|_| {
+            ApiError::service_error(format!(
+                "[{}] Incomplete Home Assistant registry reply",
+                self.backend_name
+            ))
+        })?;
+
+        Ok(HassRegistry::new(
+            serde_json::from_value(devices)?,
+            serde_json::from_value(entities)?,
+            serde_json::from_value(areas)?,
+        ))
+    }
+
+    /// Device triggers of each of the given devices, in the same order
+    pub async fn get_device_triggers(&self, device_ids: &[&str]) -> ApiResult<Vec<Vec<Value>>> {
+        if device_ids.is_empty() {
+            return Ok(vec![]);
         }
+        let commands = device_ids
+            .iter()
+            .map(|device_id| {
+                serde_json::json!({
+                    "type": "device_automation/trigger/list",
+                    "device_id": device_id,
+                })
+            })
+            .collect();
+        let results = self.ws_commands(commands).await?;
+        Ok(results
+            .into_iter()
+            .map(|triggers| serde_json::from_value(triggers).unwrap_or_default())
+            .collect())
+    }
 
+    pub async fn set_entity_registry_disabled(
+        &self,
+        entity_id: &str,
+        disabled: bool,
+    ) -> ApiResult<()> {
         let req = serde_json::json!({
-            "id": 1,
             "type": "config/entity_registry/update",
             "entity_id": entity_id,
             "disabled_by": if disabled { Value::String("user".to_string()) } else { Value::Null },
         });
-        socket.send(Message::Text(req.to_string().into())).await?;
-
-        while let Some(msg) = socket.next().await {
-            let msg = msg?;
-            if let Message::Text(text) = msg {
-                let value: Value = serde_json::from_str(&text)?;
-                if value.get("id").and_then(Value::as_u64) == Some(1) {
-                    if value.get("success").and_then(Value::as_bool) == Some(true) {
-                        return Ok(());
-                    }
-                    return Err(ApiError::service_error(format!(
-                        "[{}] HA entity registry update failed: {}",
-                        self.backend_name, value
-                    )));
-                }
+
+        self.ws_commands(vec![req]).await.map_err(|err| {
+            ApiError::service_error(format!(
+                "[{}] HA entity registry update failed: {}",
+                self.backend_name, err
+            ))
+        })?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::{HassWs, HassWsEvent, HassWsIncoming};
+
+    fn incoming(msg: &serde_json::Value) -> HassWsIncoming {
+        serde_json::from_value(msg.clone()).unwrap()
+    }
+
+    fn event(msg: &serde_json::Value) -> Option<HassWsEvent> {
+        let HassWsIncoming::Event { event } = incoming(msg) else {
+            panic!("not an event: {msg}");
+        };
+        HassWs::parse_event(event).unwrap()
+    }
+
+    #[test]
+    fn command_results() {
+        let ok = incoming(&json!({"id": 3, "type": "result", "success": true, "result": null}));
+        assert!(matches!(
+            ok,
+            HassWsIncoming::Result {
+                id: 3,
+                success: true,
+                error: None
             }
-        }
+        ));
+
+        let refused = incoming(&json!({
+            "id": 4,
+            "type": "result",
+            "success": false,
+            "error": {"code": "not_found", "message": "Service not found"},
+        }));
+        let HassWsIncoming::Result { id, success, error } = refused else {
+            panic!("not a result");
+        };
+        assert_eq!((id, success), (4, false));
+        assert_eq!(error.unwrap()["code"], "not_found");
+
+        assert!(matches!(
+            incoming(&json!({"type": "pong", "id": 5})),
+            HassWsIncoming::Other
+        ));
+    }
+
+    #[test]
+    fn state_changed_events() {
+        let ev = event(&json!({
+            "id": 1,
+            "type": "event",
+            "event": {
+                "event_type": "state_changed",
+                "data": {
+                    "entity_id": "light.desk",
+                    "old_state": null,
+                    "new_state": {"entity_id": "light.desk", "state": "on", "attributes": {}},
+                },
+            },
+        }));
+        let Some(HassWsEvent::StateChanged(ev)) = ev else {
+            panic!("not a state change: {ev:?}");
+        };
+        assert_eq!(ev.entity_id, "light.desk");
+        assert!(ev.old_state.is_none());
+        assert_eq!(ev.new_state.unwrap().state, "on");
+
+        let ev = event(&json!({
+            "id": 2,
+            "type": "event",
+            "event": {"event_type": "homeassistant_started", "data": {}},
+        }));
+        assert!(matches!(ev, Some(HassWsEvent::Started)));
+
+        let ev = event(&json!({
+            "id": 2,
+            "type": "event",
+            "event": {"event_type": "call_service", "data": {}},
+        }));
+        assert!(ev.is_none());
+    }
 
-        Err(ApiError::service_error(format!(
-            "[{}] No websocket response for entity registry update",
-            self.backend_name
-        )))
+    #[test]
+    fn trigger_events() {
+        let ev = event(&json!({
+            "id": 7,
+            "type": "event",
+            "event": {
+                "variables": {"trigger": {"id": "device_trigger.abc_on|remote_button_short_press", "idx": "0"}},
+                "context": null,
+            },
+        }));
+        let Some(HassWsEvent::Triggered(id)) = ev else {
+            panic!("not a trigger: {ev:?}");
+        };
+        assert_eq!(id, "device_trigger.abc_on|remote_button_short_press");
     }
 }
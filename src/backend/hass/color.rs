@@ -0,0 +1,151 @@
+use serde_json::{Value, json};
+
+use hue::api::ColorGamut;
+use hue::clamp::Clamp;
+use hue::colorspace;
+use hue::hs::HS;
+use hue::xy::XY;
+
+use crate::backend::hass::HassColorMode;
+
+/// Home Assistant expresses `rgb_color` and `hs_color` in sRGB
+const COLOR_SPACE: colorspace::ColorSpace = colorspace::SRGB;
+
+/// Closest point to `xy` on the line from `a` to `b`
+fn closest_on_line(a: XY, b: XY, xy: XY) -> XY {
+    let (dx, dy) = (b.x - a.x, b.y - a.y);
+    let len = dx.mul_add(dx, dy * dy);
+    if len <= 0.0 {
+        return a;
+    }
+    let t = ((xy.x - a.x).mul_add(dx, (xy.y - a.y) * dy) / len).clamp(0.0, 1.0);
+    XY::new(t.mul_add(dx, a.x), t.mul_add(dy, a.y))
+}
+
+/// Move a color outside the gamut to the closest one the light can show,
+/// keeping its hue as far as possible (clipping each channel would not).
+#[must_use]
+pub fn clamp_to_gamut(gamut: &ColorGamut, xy: XY) -> XY {
+    let corners = [gamut.red, gamut.green, gamut.blue];
+    let side = |a: XY, b: XY| (b.x - a.x).mul_add(xy.y - a.y, -((b.y - a.y) * (xy.x - a.x)));
+    let sides = [
+        side(corners[0], corners[1]),
+        side(corners[1], corners[2]),
+        side(corners[2], corners[0]),
+    ];
+    if sides.iter().all(|s| *s >= 0.0) || sides.iter().all(|s| *s <= 0.0) {
+        return xy;
+    }
+
+    [(0, 1), (1, 2), (2, 0)]
+        .into_iter()
+        .map(|(a, b)| closest_on_line(corners[a], corners[b], xy))
+        .min_by(|a, b| {
+            let dist = |p: &XY| (p.x - xy.x).hypot(p.y - xy.y);
+            dist(a).total_cmp(&dist(b))
+        })
+        .unwrap_or(xy)
+}
+
+/// Unit RGB at full brightness. Colors outside the sRGB gamut should be
+/// moved inside with [`clamp_to_gamut`] first; what is left is clipped.
+fn xy_to_rgb_unit(xy: XY) -> [f64; 3] {
+    COLOR_SPACE
+        .xy_to_rgb_color(xy.x, xy.y, 255.0)
+        .map(|c| c.clamp(0.0, 1.0))
+}
+
+#[allow(clippy::many_single_char_names)]
+fn rgb_unit_to_xy(rgb: [f64; 3]) -> Option<XY> {
+    let [r, g, b] = rgb;
+    let [x, y, _] = COLOR_SPACE.rgb_to_xyy(r, g, b);
+    (x.is_finite() && y.is_finite()).then(|| XY::new(x.clamp(0.0, 1.0), y.clamp(0.0, 1.0)))
+}
+
+#[must_use]
+pub fn xy_to_rgb(xy: XY) -> [u8; 3] {
+    xy_to_rgb_unit(xy).map(Clamp::unit_to_u8_clamped)
+}
+
+/// Hue in degrees (0-360) and saturation in percent (0-100), like HA wants them
+#[must_use]
+pub fn xy_to_hs(xy: XY) -> [f64; 2] {
+    let [r, g, b] = xy_to_rgb_unit(xy);
+    let (hs, _) = HS::from_rgb_unit(r, g, b);
+    [
+        (hs.hue * 360.0 * 100.0).round() / 100.0,
+        (hs.sat * 100.0 * 100.0).round() / 100.0,
+    ]
+}
+
+#[must_use]
+pub fn rgb_to_xy(rgb: [u8; 3]) -> Option<XY> {
+    rgb_unit_to_xy(rgb.map(Clamp::unit_from_u8))
+}
+
+#[must_use]
+pub fn hs_to_xy(hue: f64, sat: f64) -> Option<XY> {
+    let hs = HS {
+        hue: hue / 360.0,
+        sat: (sat / 100.0).clamp(0.0, 1.0),
+    };
+    rgb_unit_to_xy(hs.to_rgb_unit(1.0))
+}
+
+/// Service call field carrying `xy` in the light's native color mode, moved
+/// into the gamut of that mode
+#[must_use]
+pub fn color_service_data(mode: HassColorMode, xy: XY) -> (&'static str, Value) {
+    let xy = clamp_to_gamut(&mode.gamut(), xy);
+    match mode {
+        HassColorMode::Xy => ("xy_color", json!([xy.x, xy.y])),
+        HassColorMode::Hs => ("hs_color", json!(xy_to_hs(xy))),
+        HassColorMode::Rgb => ("rgb_color", json!(xy_to_rgb(xy))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use hue::api::ColorGamut;
+    use hue::xy::XY;
+
+    use super::{clamp_to_gamut, hs_to_xy, rgb_to_xy, xy_to_rgb};
+
+    fn assert_near(a: XY, b: XY) {
+        assert!(
+            (a.x - b.x).abs() < 0.001 && (a.y - b.y).abs() < 0.001,
+            "{a:?} != {b:?}"
+        );
+    }
+
+    #[test]
+    fn colors_inside_the_gamut_stay() {
+        let white = XY::new(0.3127, 0.3290);
+        assert_eq!(clamp_to_gamut(&ColorGamut::SRGB, white), white);
+        assert_eq!(
+            clamp_to_gamut(&ColorGamut::SRGB, ColorGamut::SRGB.red),
+            ColorGamut::SRGB.red
+        );
+    }
+
+    #[test]
+    fn colors_outside_move_to_the_closest_edge() {
+        // Hue gamut C red is beyond sRGB red
+        assert_near(
+            clamp_to_gamut(&ColorGamut::SRGB, ColorGamut::GAMUT_C.red),
+            ColorGamut::SRGB.red,
+        );
+        // Halfway between sRGB red and green, pushed outwards
+        assert_near(
+            clamp_to_gamut(&ColorGamut::SRGB, XY::new(0.5, 0.5)),
+            XY::new(0.4714, 0.4639),
+        );
+    }
+
+    #[test]
+    fn primaries_round_trip() {
+        assert_eq!(xy_to_rgb(ColorGamut::SRGB.red), [255, 0, 0]);
+        assert_near(rgb_to_xy([0, 255, 0]).unwrap(), ColorGamut::SRGB.green);
+        assert_near(hs_to_xy(240.0, 100.0).unwrap(), ColorGamut::SRGB.blue);
+    }
+}
@@ -0,0 +1,216 @@
+use std::collections::BTreeSet;
+use std::time::Duration;
+
+use tokio::time::Instant;
+
+use serde_json::{Map, Value};
+
+use hue::api::LightUpdate;
+
+use crate::backend::hass::{HassBackend, HassEntityBinding};
+use crate::error::{ApiError, ApiResult};
+
+/// How long a service call may take, over all its attempts. Backend
+/// requests are handled one at a time, so a slow call holds up every request
+/// behind it.
+const COMMAND_BUDGET: Duration = Duration::from_secs(3);
+
+/// Attempts for a service call that failed in transit (not refused by HA),
+/// as long as the budget allows
+const COMMAND_ATTEMPTS: u32 = 3;
+
+const COMMAND_RETRY_DELAY: Duration = Duration::from_millis(250);
+
+/// A Home Assistant service call, without its target
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct HassServiceCall {
+    pub domain: &'static str,
+    pub service: &'static str,
+    pub data: Map<String, Value>,
+}
+
+impl HassServiceCall {
+    #[must_use]
+    pub const fn new(
+        domain: &'static str,
+        service: &'static str,
+        data: Map<String, Value>,
+    ) -> Self {
+        Self {
+            domain,
+            service,
+            data,
+        }
+    }
+}
+
+impl HassBackend {
+    /// Call a Home Assistant service, retrying when the command got lost on
+    /// the way, within [`COMMAND_BUDGET`]. Commands refused by Home Assistant
+    /// are not retried.
+    ///
+    /// Uses the websocket while it is connected, and REST otherwise. A
+    /// websocket that fails to deliver a command is dropped, so the event
+    /// loop reconnects (and resyncs) it.
+    pub(super) async fn call_service(
+        &mut self,
+        domain: &str,
+        service: &str,
+        entity_id: &str,
+        data: Map<String, Value>,
+    ) -> ApiResult<()> {
+        let deadline = Instant::now() + COMMAND_BUDGET;
+        let mut attempt = 0;
+        loop {
+            attempt += 1;
+            let timeout = deadline.saturating_duration_since(Instant::now());
+
+            let res = if let Some(ws) = &mut self.ws {
+                match ws
+                    .call_service(domain, service, entity_id, data.clone(), timeout)
+                    .await
+                {
+                    Ok(Ok(())) => Ok(()),
+                    Ok(Err(error)) => {
+                        return Err(ApiError::service_error(format!(
+                            "Home Assistant refused {domain}.{service} for {entity_id}: {error}"
+                        )));
+                    }
+                    Err(err) => {
+                        log::debug!("[{}] WS command failed: {}", self.name, err);
+                        self.ws_lost();
+                        Err(err)
+                    }
+                }
+            } else {
+                let call = self
+                    .client
+                    .call_service(domain, service, entity_id, data.clone());
+                match tokio::time::timeout(timeout, call).await {
+                    // Error responses from Home Assistant are final
+                    Ok(Err(err @ ApiError::SvcError(_))) => return Err(err),
+                    Ok(res) => res,
+                    Err(_) => Err(ApiError::service_error(format!(
+                        "Home Assistant did not answer {domain}.{service} in time"
+                    ))),
+                }
+            };
+
+            let delay = COMMAND_RETRY_DELAY * attempt;
+            match res {
+                Ok(()) => return Ok(()),
+                Err(err) if attempt >= COMMAND_ATTEMPTS || Instant::now() + delay >= deadline => {
+                    return Err(err);
+                }
+                Err(err) => {
+                    log::debug!(
+                        "[{}] Retrying {domain}.{service} for {entity_id} ({attempt}/{COMMAND_ATTEMPTS}): {err}",
+                        self.name
+                    );
+                    tokio::time::sleep(delay).await;
+                }
+            }
+        }
+    }
+
+    /// Send a light update to Home Assistant. If the command fails, the Hue
+    /// state (already updated optimistically) is reverted to the actual
+    /// entity state, so clients see the correction.
+    pub(super) async fn light_command(&mut self, binding: &HassEntityBinding, upd: &LightUpdate) {
+        let Err(err) = self.backend_light_update(binding, upd).await else {
+            return;
+        };
+
+        self.ui_log(format!("Command for {} failed: {}", binding.entity_id, err))
+            .await;
+
+        if let Err(err) = self.revert_entity(&binding.entity_id).await {
+            log::warn!(
+                "[{}] Failed to revert {} after failed command: {}",
+                self.name,
+                binding.entity_id,
+                err
+            );
+        }
+    }
+
+    /// Home Assistant light group with exactly the given members, if any
+    fn light_group(&self, entity_ids: &BTreeSet<String>) -> Option<&str> {
+        self.light_groups
+            .iter()
+            .find(|(group, members)| *members == entity_ids && !entity_ids.contains(*group))
+            .map(|(group, _)| group.as_str())
+    }
+
+    /// Send one service call to several entities at once. Uses a matching
+    /// light group where possible, and reverts all entities if the call fails.
+    pub(super) async fn batch_command(
+        &mut self,
+        call: HassServiceCall,
+        bindings: &[HassEntityBinding],
+    ) {
+        let entity_ids = bindings
+            .iter()
+            .map(|binding| binding.entity_id.clone())
+            .collect::<BTreeSet<_>>();
+        for entity_id in &entity_ids {
+            self.stop_signal(entity_id).await;
+        }
+
+        let target = match call.domain {
+            "light" if entity_ids.len() > 1 => self.light_group(&entity_ids).map(str::to_string),
+            _ => None,
+        }
+        .unwrap_or_else(|| entity_ids.iter().cloned().collect::<Vec<_>>().join(", "));
+
+        let Err(err) = self
+            .call_service(call.domain, call.service, &target, call.data)
+            .await
+        else {
+            return;
+        };
+
+        self.ui_log(format!("Command for {target} failed: {err}"))
+            .await;
+
+        if let Err(err) = self.revert_entities(&entity_ids).await {
+            log::warn!(
+                "[{}] Failed to revert {target} after failed command: {err}",
+                self.name
+            );
+        }
+    }
+
+    async fn revert_entity(&mut self, entity_id: &str) -> ApiResult<()> {
+        let state = tokio::time::timeout(COMMAND_BUDGET, self.client.get_state(entity_id))
+            .await
+            .map_err(|_| {
+                ApiError::service_error(format!(
+                    "Home Assistant did not return the state of {entity_id} in time"
+                ))
+            })??;
+        self.handle_state_update(state, None).await
+    }
+
+    /// Revert several entities at once, fetching their states in a single
+    /// request (within the same budget as a single entity)
+    async fn revert_entities(&mut self, entity_ids: &BTreeSet<String>) -> ApiResult<()> {
+        if let (1, Some(entity_id)) = (entity_ids.len(), entity_ids.first()) {
+            return self.revert_entity(entity_id).await;
+        }
+
+        let states = tokio::time::timeout(COMMAND_BUDGET, self.client.get_states())
+            .await
+            .map_err(|_| {
+                ApiError::service_error("Home Assistant did not return its states in time")
+            })??;
+
+        for state in states {
+            if entity_ids.contains(&state.entity_id) {
+                self.handle_state_update(state, None).await?;
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,243 @@
+use std::collections::BTreeMap;
+
+use serde_json::Value;
+
+use hue::api::{
+    LightEffect, LightEffectStatus, LightEffectValues, LightEffects, LightEffectsUpdate,
+    LightEffectsV2, LightEffectsV2Update, LightUpdate,
+};
+
+/// Guess the Hue effect closest to a Home Assistant effect name.
+///
+/// Integrations name their effects freely, so this matches on common words
+/// rather than exact names. Anything not listed here needs an override.
+fn closest_hue_effect(name: &str) -> Option<LightEffect> {
+    let name = name.trim().to_ascii_lowercase().replace([' ', '-'], "_");
+
+    let effect = match name.as_str() {
+        "none" | "off" | "stop" | "stop_effect" | "stop_hue_effect" | "solid" => {
+            LightEffect::NoEffect
+        }
+        "candle" | "candlelight" | "candle_light" | "flicker" => LightEffect::Candle,
+        "fire" | "fireplace" | "flame" | "fire_flicker" => LightEffect::Fire,
+        "prism" | "colorloop" | "color_loop" | "colour_loop" | "rainbow" | "colorful" => {
+            LightEffect::Prism
+        }
+        "sparkle" | "sparkles" | "twinkle" | "glitter" => LightEffect::Sparkle,
+        "opal" => LightEffect::Opal,
+        "glisten" | "shimmer" => LightEffect::Glisten,
+        "underwater" | "ocean" | "sea" => LightEffect::Underwater,
+        "cosmos" | "galaxy" | "stars" | "starlight" => LightEffect::Cosmos,
+        "sunbeam" | "sunny" => LightEffect::Sunbeam,
+        "enchant" | "magic" => LightEffect::Enchant,
+        _ => return None,
+    };
+
+    Some(effect)
+}
+
+/// Effect that stops any effect on Home Assistant lights
+const HASS_EFFECT_OFF: &str = "off";
+
+fn parse_hue_effect(name: &str) -> Option<LightEffect> {
+    serde_json::from_value(Value::String(name.trim().to_string())).ok()
+}
+
+/// The Hue effects a light offers, and the Home Assistant effect behind each
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct HassEffectMap {
+    effects: Vec<(LightEffect, String)>,
+}
+
+impl HassEffectMap {
+    /// Build the mapping from a light's `effect_list`.
+    ///
+    /// `overrides` maps Hue effect names (like `candle`) to Home Assistant
+    /// effect names, and takes precedence over the detected mapping. An empty
+    /// override value removes that Hue effect.
+    #[must_use]
+    pub fn new(effect_list: &[String], overrides: &BTreeMap<String, String>) -> Self {
+        let mut effects: Vec<(LightEffect, String)> = vec![];
+
+        for hass in effect_list {
+            if let Some(hue) = closest_hue_effect(hass) {
+                if !effects.iter().any(|(fx, _)| *fx == hue) {
+                    effects.push((hue, hass.clone()));
+                }
+            }
+        }
+
+        for (hue, hass) in overrides {
+            let Some(hue) = parse_hue_effect(hue) else {
+                log::warn!("Ignoring effect override for unknown Hue effect {hue:?}");
+                continue;
+            };
+            effects.retain(|(fx, _)| *fx != hue);
+            let hass = hass.trim();
+            if !hass.is_empty() {
+                effects.push((hue, hass.to_string()));
+            }
+        }
+
+        // A lone "stop" effect is no reason to show the effects picker
+        if effects.iter().all(|(fx, _)| *fx == LightEffect::NoEffect) {
+            effects.clear();
+        }
+
+        effects.sort_by_key(|(fx, _)| LightEffect::ALL.iter().position(|x| x == fx));
+
+        Self { effects }
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.effects.is_empty()
+    }
+
+    /// Home Assistant effect for a Hue effect. Without a stop effect of its
+    /// own, `no_effect` becomes the standard `off` effect.
+    #[must_use]
+    pub fn hass_effect(&self, effect: LightEffect) -> Option<&str> {
+        self.effects
+            .iter()
+            .find(|(fx, _)| *fx == effect)
+            .map(|(_, name)| name.as_str())
+            .or_else(|| {
+                (effect == LightEffect::NoEffect && !self.is_empty()).then_some(HASS_EFFECT_OFF)
+            })
+    }
+
+    #[must_use]
+    pub fn hue_effect(&self, hass: &str) -> Option<LightEffect> {
+        self.effects
+            .iter()
+            .find(|(_, name)| name.eq_ignore_ascii_case(hass))
+            .map(|(fx, _)| *fx)
+    }
+
+    /// Supported Hue effects. `no_effect` is always offered, so the Hue app
+    /// can show the effect as stopped (and stop it, see [`Self::hass_effect`]).
+    #[must_use]
+    pub fn hue_effects(&self) -> Vec<LightEffect> {
+        let mut values = vec![LightEffect::NoEffect];
+        values.extend(
+            self.effects
+                .iter()
+                .map(|(fx, _)| *fx)
+                .filter(|fx| *fx != LightEffect::NoEffect),
+        );
+        values
+    }
+
+    /// Hue `effects` and `effects_v2` for a light currently running `current`
+    #[must_use]
+    pub fn light_effects(&self, current: Option<&str>) -> Option<(LightEffects, LightEffectsV2)> {
+        if self.is_empty() {
+            return None;
+        }
+
+        let values = self.hue_effects();
+        let status = current
+            .and_then(|name| self.hue_effect(name))
+            .unwrap_or_default();
+
+        let effects = LightEffects {
+            status_values: values.clone(),
+            status,
+            effect_values: values.clone(),
+        };
+        let effects_v2 = LightEffectsV2 {
+            action: LightEffectValues {
+                effect_values: values.clone(),
+            },
+            status: LightEffectStatus {
+                effect: status,
+                effect_values: values,
+                parameters: None,
+            },
+        };
+
+        Some((effects, effects_v2))
+    }
+}
+
+/// The Hue effect requested by a light update, if any
+#[must_use]
+pub fn requested_effect(upd: &LightUpdate) -> Option<LightEffect> {
+    let v2 = upd
+        .effects_v2
+        .as_ref()
+        .and_then(|LightEffectsV2Update { action, .. }| action.as_ref()?.effect);
+
+    v2.or_else(|| {
+        let LightEffectsUpdate { action, status } = upd.effects.as_ref()?;
+        action.as_ref().and_then(|act| act.effect).or(*status)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use hue::api::LightEffect;
+
+    use super::HassEffectMap;
+
+    fn effect_map(effect_list: &[&str], overrides: &[(&str, &str)]) -> HassEffectMap {
+        let effect_list = effect_list
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>();
+        let overrides = overrides
+            .iter()
+            .map(|(hue, hass)| ((*hue).to_string(), (*hass).to_string()))
+            .collect::<BTreeMap<_, _>>();
+        HassEffectMap::new(&effect_list, &overrides)
+    }
+
+    #[test]
+    fn detects_effects_by_name() {
+        let map = effect_map(&["Candle Light", "Colorloop", "Strobe"], &[]);
+        assert_eq!(map.hass_effect(LightEffect::Candle), Some("Candle Light"));
+        assert_eq!(map.hue_effect("colorloop"), Some(LightEffect::Prism));
+        assert_eq!(map.hue_effect("Strobe"), None);
+        assert_eq!(
+            map.hue_effects(),
+            [
+                LightEffect::NoEffect,
+                LightEffect::Candle,
+                LightEffect::Prism
+            ]
+        );
+    }
+
+    #[test]
+    fn overrides_win() {
+        let map = effect_map(
+            &["candle", "fireplace"],
+            &[("candle", ""), ("sparkle", "Twinkle"), ("bogus", "x")],
+        );
+        assert_eq!(map.hass_effect(LightEffect::Candle), None);
+        assert_eq!(map.hass_effect(LightEffect::Sparkle), Some("Twinkle"));
+        assert_eq!(map.hass_effect(LightEffect::Fire), Some("fireplace"));
+    }
+
+    #[test]
+    fn lone_stop_effect_is_no_effect_list() {
+        let map = effect_map(&["none", "Strobe"], &[]);
+        assert!(map.is_empty());
+        assert!(map.light_effects(None).is_none());
+    }
+
+    #[test]
+    fn no_effect_stops_the_effect() {
+        let map = effect_map(&["stop", "candle"], &[]);
+        assert_eq!(map.hass_effect(LightEffect::NoEffect), Some("stop"));
+
+        let map = effect_map(&["candle"], &[]);
+        assert_eq!(map.hass_effect(LightEffect::NoEffect), Some("off"));
+
+        let map = effect_map(&[], &[]);
+        assert_eq!(map.hass_effect(LightEffect::NoEffect), None);
+    }
+}
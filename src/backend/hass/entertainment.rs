@@ -0,0 +1,344 @@
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+use futures::future::join_all;
+use serde_json::{Map, Value, json};
+use tokio::task::JoinHandle;
+
+use hue::stream::HueStreamLightsV2;
+use hue::xy::XY;
+
+use crate::backend::hass::HassColorMode;
+use crate::backend::hass::client::{HassClient, HassState};
+use crate::backend::hass::color;
+use crate::model::throttle::Throttle;
+
+/// A single Home Assistant light taking part in an entertainment stream
+pub struct HassEntTarget {
+    pub entity_id: String,
+    /// How to send colors, or `None` for lights that only dim
+    pub color_mode: Option<HassColorMode>,
+    pub throttle: Throttle,
+    /// Service call that restores the light to its pre-stream state
+    pub restore: Option<(&'static str, Map<String, Value>)>,
+}
+
+/// A pending `light.turn_on` call produced from a stream frame
+pub struct HassEntCommand {
+    pub entity_id: String,
+    pub data: Map<String, Value>,
+}
+
+/// How long the calls of a frame may take. A frame is outdated by the next
+/// one anyway.
+const FRAME_TIMEOUT: Duration = Duration::from_secs(1);
+
+#[derive(Default)]
+pub struct HassEntStream {
+    channels: BTreeMap<u32, Vec<String>>,
+    targets: BTreeMap<String, HassEntTarget>,
+    /// Service calls of the last frame, still on their way
+    sending: Option<JoinHandle<()>>,
+}
+
+impl HassEntStream {
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.targets.is_empty()
+    }
+
+    pub fn add_channel_member(&mut self, channel: u32, entity_id: &str) {
+        let members = self.channels.entry(channel).or_default();
+        if !members.iter().any(|id| id == entity_id) {
+            members.push(entity_id.to_string());
+        }
+    }
+
+    pub fn add_target(&mut self, target: HassEntTarget) {
+        self.targets.insert(target.entity_id.clone(), target);
+    }
+
+    pub fn targets(&self) -> impl Iterator<Item = &HassEntTarget> {
+        self.targets.values()
+    }
+
+    /// Build the service call data that brings a light back to the state
+    /// Home Assistant reported before streaming started.
+    #[must_use]
+    pub fn restore_from_state(state: &HassState) -> (&'static str, Map<String, Value>) {
+        let mut data = Map::new();
+
+        if state.state != "on" {
+            return ("turn_off", data);
+        }
+
+        if let Some(bri) = state.attributes.get("brightness").filter(|v| !v.is_null()) {
+            data.insert("brightness".to_string(), bri.clone());
+        }
+
+        let color_mode = state
+            .attributes
+            .get("color_mode")
+            .and_then(Value::as_str)
+            .unwrap_or_default();
+
+        let keys: &[&str] = match color_mode {
+            "color_temp" => &["color_temp_kelvin", "color_temp"],
+            "hs" => &["hs_color"],
+            "rgb" => &["rgb_color"],
+            "rgbw" => &["rgbw_color"],
+            "rgbww" => &["rgbww_color"],
+            _ => &["xy_color"],
+        };
+
+        if let Some((key, value)) = keys.iter().find_map(|key| {
+            state
+                .attributes
+                .get(*key)
+                .filter(|v| !v.is_null())
+                .map(|v| (*key, v.clone()))
+        }) {
+            data.insert(key.to_string(), value);
+        }
+
+        ("turn_on", data)
+    }
+
+    /// Send a stream frame to Home Assistant, without waiting for it.
+    ///
+    /// The calls for all lights go out at once, next to the event loop.
+    /// Frames that arrive while the previous one is still being sent are
+    /// dropped, so a slow Home Assistant does not fall further and further
+    /// behind.
+    pub fn send_frame(
+        &mut self,
+        client: &HassClient,
+        backend_name: &str,
+        frame: &HueStreamLightsV2,
+    ) {
+        if self
+            .sending
+            .as_ref()
+            .is_some_and(|task| !task.is_finished())
+        {
+            return;
+        }
+        let cmds = self.frame(frame);
+        if cmds.is_empty() {
+            return;
+        }
+
+        let client = client.clone();
+        let backend_name = backend_name.to_string();
+        self.sending = Some(tokio::spawn(async move {
+            let calls = cmds.into_iter().map(|cmd| {
+                let client = &client;
+                let backend_name = &backend_name;
+                async move {
+                    let call = client.call_service("light", "turn_on", &cmd.entity_id, cmd.data);
+                    // A single slow or unavailable light must not end the
+                    // stream, and a lost frame is not retried
+                    match tokio::time::timeout(FRAME_TIMEOUT, call).await {
+                        Ok(Ok(())) => {}
+                        Ok(Err(err)) => log::debug!(
+                            "[{backend_name}] Entertainment frame for {} failed: {err}",
+                            cmd.entity_id
+                        ),
+                        Err(_) => log::debug!(
+                            "[{backend_name}] Entertainment frame for {} timed out",
+                            cmd.entity_id
+                        ),
+                    }
+                }
+            });
+            join_all(calls).await;
+        }));
+    }
+
+    /// Wait for the last frame to be sent, so it cannot land after the
+    /// lights are restored
+    pub async fn finish(&mut self) {
+        if let Some(task) = self.sending.take() {
+            let _ = task.await;
+        }
+    }
+
+    /// Translate a stream frame into zero-transition `light.turn_on` calls.
+    ///
+    /// When several channels map to the same light, the last one wins. Lights
+    /// whose throttle has not expired yet are skipped for this frame.
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    pub fn frame(&mut self, frame: &HueStreamLightsV2) -> Vec<HassEntCommand> {
+        let records: Vec<(u32, XY, f64)> = match frame {
+            HueStreamLightsV2::Rgb(rgb) => rgb
+                .iter()
+                .map(|light| {
+                    let (xy, bright) = light.rgb.to_xy();
+                    (u32::from(light.channel), xy, bright)
+                })
+                .collect(),
+            HueStreamLightsV2::Xy(xy) => xy
+                .iter()
+                .map(|light| {
+                    let (xy, bright) = light.xy.to_xy();
+                    (u32::from(light.channel), xy, bright)
+                })
+                .collect(),
+        };
+
+        let mut values: BTreeMap<&str, (XY, u8)> = BTreeMap::new();
+        for (channel, xy, bright) in records {
+            let Some(members) = self.channels.get(&channel) else {
+                continue;
+            };
+            let brightness = bright.round().clamp(0.0, 255.0) as u8;
+            for entity_id in members {
+                values.insert(entity_id, (xy, brightness));
+            }
+        }
+
+        let mut cmds = vec![];
+        for (entity_id, (xy, brightness)) in values {
+            let Some(target) = self.targets.get_mut(entity_id) else {
+                continue;
+            };
+            if !target.throttle.tick() {
+                continue;
+            }
+
+            let mut data = Map::new();
+            data.insert("brightness".to_string(), json!(brightness));
+            if let Some(mode) = target.color_mode {
+                let (key, value) = color::color_service_data(mode, xy);
+                data.insert(key.to_string(), value);
+            }
+            data.insert("transition".to_string(), json!(0));
+
+            cmds.push(HassEntCommand {
+                entity_id: entity_id.to_string(),
+                data,
+            });
+        }
+
+        cmds
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Duration;
+    use serde_json::{Map, Value, json};
+
+    use hue::stream::{HueStreamLightsV2, Xy16, Xy16V2};
+
+    use crate::backend::hass::HassColorMode;
+    use crate::backend::hass::client::HassState;
+    use crate::model::throttle::Throttle;
+
+    use super::{HassEntStream, HassEntTarget};
+
+    fn target(
+        entity_id: &str,
+        color_mode: Option<HassColorMode>,
+        throttle: Throttle,
+    ) -> HassEntTarget {
+        HassEntTarget {
+            entity_id: entity_id.to_string(),
+            color_mode,
+            throttle,
+            restore: None,
+        }
+    }
+
+    fn xy_frame(lights: &[(u8, u16, u16, u16)]) -> HueStreamLightsV2 {
+        HueStreamLightsV2::Xy(
+            lights
+                .iter()
+                .map(|&(channel, x, y, b)| Xy16V2 {
+                    channel,
+                    xy: Xy16 { x, y, b },
+                })
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn frame_to_service_calls() {
+        let mut es = HassEntStream::default();
+        es.add_channel_member(0, "light.a");
+        es.add_channel_member(1, "light.a");
+        es.add_channel_member(1, "light.b");
+        es.add_channel_member(2, "light.c");
+        es.add_target(target(
+            "light.a",
+            Some(HassColorMode::Xy),
+            Throttle::new(Duration::zero()),
+        ));
+        es.add_target(target("light.b", None, Throttle::new(Duration::zero())));
+        // Just sent a frame, so this one has to wait
+        es.add_target(target("light.c", None, Throttle::new(Duration::hours(1))));
+
+        let cmds = es.frame(&xy_frame(&[
+            (0, 0, 0, 0),
+            (1, 0x8000, 0x4000, 0xFFFF),
+            (2, 0, 0, 0xFFFF),
+            (3, 0, 0, 0xFFFF),
+        ]));
+        let cmds = cmds
+            .iter()
+            .map(|cmd| (cmd.entity_id.as_str(), Value::Object(cmd.data.clone())))
+            .collect::<Vec<_>>();
+
+        // Both lights follow the last channel they are in
+        assert_eq!(cmds.len(), 2);
+        assert_eq!(cmds[0].0, "light.a");
+        assert_eq!(cmds[0].1["brightness"], json!(255));
+        assert_eq!(cmds[0].1["transition"], json!(0));
+        let xy = cmds[0].1["xy_color"].as_array().unwrap();
+        assert!((xy[0].as_f64().unwrap() - 0.5).abs() < 0.001);
+        assert_eq!(
+            cmds[1],
+            ("light.b", json!({"brightness": 255, "transition": 0}))
+        );
+    }
+
+    fn state(state: &str, attributes: &Value) -> HassState {
+        HassState {
+            entity_id: "light.a".to_string(),
+            state: state.to_string(),
+            attributes: attributes.as_object().cloned().unwrap_or_default(),
+        }
+    }
+
+    #[test]
+    fn restore_previous_state() {
+        let off = state("off", &json!({"brightness": null}));
+        assert_eq!(
+            HassEntStream::restore_from_state(&off),
+            ("turn_off", Map::new())
+        );
+
+        let warm = state(
+            "on",
+            &json!({
+                "brightness": 100,
+                "color_mode": "color_temp",
+                "color_temp_kelvin": 2700,
+                "xy_color": [0.5, 0.4],
+            }),
+        );
+        let (service, data) = HassEntStream::restore_from_state(&warm);
+        assert_eq!(service, "turn_on");
+        assert_eq!(
+            Value::Object(data),
+            json!({"brightness": 100, "color_temp_kelvin": 2700})
+        );
+
+        let red = state(
+            "on",
+            &json!({"brightness": null, "color_mode": "hs", "hs_color": [0, 100]}),
+        );
+        let (_, data) = HassEntStream::restore_from_state(&red);
+        assert_eq!(Value::Object(data), json!({"hs_color": [0, 100]}));
+    }
+}
@@ -6,28 +6,38 @@ use maplit::btreeset;
 use serde_json::{Value, json};
 
 use hue::api::{
-    ColorTemperature, Device, DeviceArchetype, DeviceProductData, Dimming, DimmingUpdate,
-    GroupedLight, Light, LightColor, LightMetadata, Metadata, MirekSchema, Motion, On, RType,
-    Resource, ResourceLink, Room, RoomArchetype, RoomMetadata, ZigbeeConnectivity,
-    ZigbeeConnectivityStatus,
+    Button, ColorTemperature, Contact, Device, DeviceArchetype, DevicePower, DeviceProductData,
+    DeviceSoftwareUpdate, Dimming, DimmingUpdate, Entertainment, EntertainmentSegment,
+    EntertainmentSegments, GroupedLight, Light, LightColor, LightLevel, LightMetadata, Metadata,
+    MirekSchema, Motion, On, RType, RelativeRotary, Resource, ResourceLink, Room, RoomArchetype,
+    RoomMetadata, SoftwareUpdateState, Temperature, ZigbeeConnectivity, ZigbeeConnectivityStatus,
 };
 use hue::xy::XY;
 use uuid::Uuid;
 
+use crate::backend::hass::button::{self, make_button, make_relative_rotary};
 use crate::backend::hass::client::HassState;
+use crate::backend::hass::color;
+use crate::backend::hass::effects::HassEffectMap;
+use crate::backend::hass::registry::HassDeviceEntry;
+use crate::backend::hass::scenes;
 use crate::backend::hass::{
-    HassBackend, HassEntityBinding, HassEntityKind, HassLightCapabilities, HassServiceKind,
+    HassBackend, HassColorMode, HassEntityBinding, HassEntityKind, HassLightCapabilities,
+    HassServiceKind, mirek_kelvin,
 };
 use crate::error::ApiResult;
 use crate::model::hass::{
     HassEntitySummary, HassLightArchetype, HassSensorKind, HassSwitchMode, HassUiConfig,
 };
+use crate::model::state::AuxData;
 use crate::resource::Resources;
 
 #[derive(Clone, Debug)]
 struct ImportedEntity {
     entity_id: String,
     name: String,
+    /// Name set in Bifrost (or the Hue app), also used for grouped devices
+    alias: Option<String>,
     kind: HassEntityKind,
     service_kind: HassServiceKind,
     state: String,
@@ -36,8 +46,16 @@ struct ImportedEntity {
     brightness: Option<f64>,
     xy_color: Option<XY>,
     color_temp: Option<u16>,
+    /// Temperature in °C, or Hue `light_level` for illuminance sensors
+    sensor_value: Option<f64>,
     area_name: Option<String>,
+    /// The physical device this entity belongs to, from the device registry
+    device: Option<HassDeviceEntry>,
     capabilities: HassLightCapabilities,
+    /// Effects offered by Home Assistant, and the one currently running
+    effect_list: Vec<String>,
+    effect: Option<String>,
+    effects: HassEffectMap,
     detected_sensor_kind: Option<HassSensorKind>,
     sensor_enabled: bool,
     switch_mode: Option<HassSwitchMode>,
@@ -50,6 +68,8 @@ impl ImportedEntity {
             HassEntityKind::Light => "light",
             HassEntityKind::Switch => "switch",
             HassEntityKind::BinarySensor => "binary_sensor",
+            HassEntityKind::Sensor => "sensor",
+            HassEntityKind::Event => "event",
         }
     }
 
@@ -65,11 +85,15 @@ impl ImportedEntity {
             }
             HassServiceKind::Motion => "motion".to_string(),
             HassServiceKind::Contact => "contact".to_string(),
+            HassServiceKind::Temperature => "temperature".to_string(),
+            HassServiceKind::LightLevel => "light_level".to_string(),
+            HassServiceKind::Button => "button".to_string(),
+            HassServiceKind::RelativeRotary => "relative_rotary".to_string(),
         }
     }
 }
 
-fn value_to_f64(value: &Value) -> Option<f64> {
+pub(super) fn value_to_f64(value: &Value) -> Option<f64> {
     value
         .as_f64()
         .or_else(|| {
@@ -91,7 +115,7 @@ fn value_to_u16(value: &Value) -> Option<u16> {
         .or_else(|| value.as_i64().and_then(|x| u16::try_from(x).ok()))
 }
 
-fn parse_xy_color(value: &Value) -> Option<XY> {
+pub(super) fn parse_xy_color(value: &Value) -> Option<XY> {
     let arr = value.as_array()?;
     let [x, y] = arr.as_slice() else {
         return None;
@@ -104,6 +128,76 @@ fn parse_xy_color(value: &Value) -> Option<XY> {
     })
 }
 
+fn light_groups(states: &[HassState]) -> HashMap<String, BTreeSet<String>> {
+    states
+        .iter()
+        .filter_map(|state| Some((state.entity_id.clone(), light_group_members(state)?)))
+        .collect()
+}
+
+/// Members of a Home Assistant light group
+fn light_group_members(state: &HassState) -> Option<BTreeSet<String>> {
+    if !state.entity_id.starts_with("light.") {
+        return None;
+    }
+    let members = state
+        .attributes
+        .get("entity_id")?
+        .as_array()?
+        .iter()
+        .filter_map(Value::as_str)
+        .map(ToString::to_string)
+        .collect::<BTreeSet<_>>();
+    (!members.is_empty()).then_some(members)
+}
+
+fn parse_native_color(state: &HassState, mode: HassColorMode) -> Option<XY> {
+    let values = |key: &str| -> Option<Vec<f64>> {
+        let arr = state.attributes.get(key)?.as_array()?;
+        arr.iter().map(value_to_f64).collect()
+    };
+
+    match mode {
+        HassColorMode::Xy => None,
+        HassColorMode::Hs => match values("hs_color")?.as_slice() {
+            [hue, sat] => color::hs_to_xy(*hue, *sat),
+            _ => None,
+        },
+        HassColorMode::Rgb => {
+            let rgb = values("rgb_color")?
+                .into_iter()
+                .map(|c| {
+                    format!("{:.0}", c.round().clamp(0.0, 255.0))
+                        .parse::<u8>()
+                        .ok()
+                })
+                .collect::<Option<Vec<u8>>>()?;
+            color::rgb_to_xy(rgb.try_into().ok()?)
+        }
+    }
+}
+
+fn parse_effects(state: &HassState) -> (Vec<String>, Option<String>) {
+    let effect_list = state
+        .attributes
+        .get("effect_list")
+        .and_then(Value::as_array)
+        .map(|arr| {
+            arr.iter()
+                .filter_map(Value::as_str)
+                .map(ToString::to_string)
+                .collect()
+        })
+        .unwrap_or_default();
+    let effect = state
+        .attributes
+        .get("effect")
+        .and_then(Value::as_str)
+        .map(ToString::to_string);
+
+    (effect_list, effect)
+}
+
 fn parse_supported_color_modes(state: &HassState) -> BTreeSet<String> {
     state
         .attributes
@@ -118,10 +212,14 @@ fn parse_supported_color_modes(state: &HassState) -> BTreeSet<String> {
         .unwrap_or_default()
 }
 
+/// `LightEntityFeature.FLASH` in the `supported_features` bitmask
+const LIGHT_FEATURE_FLASH: u64 = 8;
+
 fn parse_light_capabilities(state: &HassState) -> HassLightCapabilities {
     let modes = parse_supported_color_modes(state);
     let has_brightness_attr = state.attributes.contains_key("brightness");
-    let has_color_temp_attr = state.attributes.contains_key("color_temp");
+    let has_color_temp_attr = state.attributes.contains_key("color_temp")
+        || state.attributes.contains_key("color_temp_kelvin");
     let has_xy_attr = state.attributes.contains_key("xy_color");
 
     let supports_color = modes
@@ -136,13 +234,62 @@ fn parse_light_capabilities(state: &HassState) -> HassLightCapabilities {
             )
         });
 
+    // Prefer xy, since that is what Hue uses. Other modes are converted.
+    let color_mode = if modes.contains("xy") || (modes.is_empty() && has_xy_attr) {
+        HassColorMode::Xy
+    } else if modes.contains("hs") {
+        HassColorMode::Hs
+    } else if modes
+        .iter()
+        .any(|m| matches!(m.as_str(), "rgb" | "rgbw" | "rgbww"))
+    {
+        HassColorMode::Rgb
+    } else {
+        HassColorMode::Xy
+    };
+
+    let supported_features = state
+        .attributes
+        .get("supported_features")
+        .and_then(Value::as_u64)
+        .unwrap_or_default();
+
     HassLightCapabilities {
         supports_brightness,
         supports_color: supports_color || has_xy_attr,
         supports_color_temp,
+        supports_flash: supported_features & LIGHT_FEATURE_FLASH != 0,
+        color_mode,
+        mirek_schema: if supports_color_temp {
+            parse_mirek_schema(state)
+        } else {
+            None
+        },
     }
 }
 
+/// Read the supported color temperature range, preferring the Kelvin
+/// attributes over the deprecated mired ones.
+fn parse_mirek_schema(state: &HassState) -> Option<MirekSchema> {
+    let attr = |key: &str| state.attributes.get(key).and_then(value_to_f64);
+
+    let (min, max) = match (
+        attr("max_color_temp_kelvin").and_then(mirek_kelvin),
+        attr("min_color_temp_kelvin").and_then(mirek_kelvin),
+    ) {
+        (Some(min), Some(max)) => (min, max),
+        _ => (
+            attr("min_mireds").and_then(|x| format!("{:.0}", x.round()).parse::<u16>().ok())?,
+            attr("max_mireds").and_then(|x| format!("{:.0}", x.round()).parse::<u16>().ok())?,
+        ),
+    };
+
+    (min > 0 && min <= max).then_some(MirekSchema {
+        mirek_minimum: u32::from(min),
+        mirek_maximum: u32::from(max),
+    })
+}
+
 fn detected_sensor_kind(state: &HassState) -> HassSensorKind {
     match state
         .attributes
@@ -154,13 +301,64 @@ fn detected_sensor_kind(state: &HassState) -> HassSensorKind {
     {
         "motion" | "occupancy" | "presence" => HassSensorKind::Motion,
         "door" | "opening" | "window" | "garage_door" => HassSensorKind::Contact,
+        "temperature" => HassSensorKind::Temperature,
+        "illuminance" => HassSensorKind::LightLevel,
         _ => HassSensorKind::Ignore,
     }
 }
 
-fn parse_imported_entity(state: &HassState, area_name: Option<String>) -> Option<ImportedEntity> {
-    let (domain, _) = state.entity_id.split_once('.')?;
-    let (kind, service_kind, capabilities, detected_kind) = match domain {
+const fn fahrenheit_to_celsius(value: f64) -> f64 {
+    (value - 32.0) * 5.0 / 9.0
+}
+
+fn parse_sensor_value(state: &HassState, kind: HassSensorKind) -> Option<f64> {
+    let value = state.state.trim().parse::<f64>().ok()?;
+    if !value.is_finite() {
+        return None;
+    }
+    let unit = state
+        .attributes
+        .get("unit_of_measurement")
+        .and_then(Value::as_str)
+        .unwrap_or_default();
+
+    match kind {
+        HassSensorKind::Temperature => {
+            let celsius = if unit.eq_ignore_ascii_case("°F") {
+                fahrenheit_to_celsius(value)
+            } else {
+                value
+            };
+            Some((celsius * 100.0).round() / 100.0)
+        }
+        HassSensorKind::LightLevel => Some(LightLevel::light_level_from_lux(value)),
+        HassSensorKind::Motion | HassSensorKind::Contact | HassSensorKind::Ignore => None,
+    }
+}
+
+/// Map the sensor kind picked in the UI to a Hue service, if it fits the entity domain
+const fn selected_service_kind(
+    imported: &ImportedEntity,
+    selected: HassSensorKind,
+) -> HassServiceKind {
+    match (imported.kind, selected) {
+        (HassEntityKind::BinarySensor, HassSensorKind::Motion) => HassServiceKind::Motion,
+        (HassEntityKind::BinarySensor, HassSensorKind::Contact) => HassServiceKind::Contact,
+        (HassEntityKind::Sensor, HassSensorKind::Temperature) => HassServiceKind::Temperature,
+        (HassEntityKind::Sensor, HassSensorKind::LightLevel) => HassServiceKind::LightLevel,
+        _ => imported.service_kind,
+    }
+}
+
+type EntityClass = (
+    HassEntityKind,
+    HassServiceKind,
+    HassLightCapabilities,
+    Option<HassSensorKind>,
+);
+
+fn classify_entity(domain: &str, state: &HassState) -> Option<EntityClass> {
+    let entity = match domain {
         "light" => (
             HassEntityKind::Light,
             HassServiceKind::Light,
@@ -176,9 +374,11 @@ fn parse_imported_entity(state: &HassState, area_name: Option<String>) -> Option
         "binary_sensor" => {
             let detected = detected_sensor_kind(state);
             let sk = match detected {
-                HassSensorKind::Motion => HassServiceKind::Motion,
                 HassSensorKind::Contact => HassServiceKind::Contact,
-                HassSensorKind::Ignore => HassServiceKind::Motion,
+                HassSensorKind::Motion
+                | HassSensorKind::Temperature
+                | HassSensorKind::LightLevel
+                | HassSensorKind::Ignore => HassServiceKind::Motion,
             };
             (
                 HassEntityKind::BinarySensor,
@@ -187,10 +387,56 @@ fn parse_imported_entity(state: &HassState, area_name: Option<String>) -> Option
                 Some(detected),
             )
         }
+        "sensor" => {
+            // Only measurements with a Hue equivalent; HA has far too many sensors to list them all
+            let detected = detected_sensor_kind(state);
+            let sk = match detected {
+                HassSensorKind::Temperature => HassServiceKind::Temperature,
+                HassSensorKind::LightLevel => HassServiceKind::LightLevel,
+                HassSensorKind::Motion | HassSensorKind::Contact | HassSensorKind::Ignore => {
+                    return None;
+                }
+            };
+            (
+                HassEntityKind::Sensor,
+                sk,
+                HassLightCapabilities::default(),
+                Some(detected),
+            )
+        }
+        "event" | button::DEVICE_TRIGGER_DOMAIN => {
+            let sk = if button::is_rotary(&button::event_types(state)) {
+                HassServiceKind::RelativeRotary
+            } else {
+                HassServiceKind::Button
+            };
+            (
+                HassEntityKind::Event,
+                sk,
+                HassLightCapabilities::default(),
+                None,
+            )
+        }
         _ => return None,
     };
 
-    let available = !matches!(state.state.as_str(), "unavailable" | "unknown");
+    Some(entity)
+}
+
+fn parse_imported_entity(state: &HassState, area_name: Option<String>) -> Option<ImportedEntity> {
+    let (domain, _) = state.entity_id.split_once('.')?;
+    let (kind, service_kind, capabilities, detected_kind) = classify_entity(domain, state)?;
+
+    let sensor_value = match (kind, detected_kind) {
+        (HassEntityKind::Sensor, Some(detected)) => parse_sensor_value(state, detected),
+        _ => None,
+    };
+    let available = match kind {
+        // event entities stay "unknown" until they fire for the first time
+        HassEntityKind::Event => state.state != "unavailable",
+        HassEntityKind::Sensor => sensor_value.is_some(),
+        _ => !matches!(state.state.as_str(), "unavailable" | "unknown"),
+    };
     let on = available && state.state == "on";
 
     let name = state
@@ -210,23 +456,33 @@ fn parse_imported_entity(state: &HassState, area_name: Option<String>) -> Option
         None
     };
     let xy_color = if matches!(kind, HassEntityKind::Light) && capabilities.supports_color {
-        state.attributes.get("xy_color").and_then(parse_xy_color)
+        parse_native_color(state, capabilities.color_mode)
+            .or_else(|| state.attributes.get("xy_color").and_then(parse_xy_color))
     } else {
         None
     };
     let color_temp = if matches!(kind, HassEntityKind::Light) && capabilities.supports_color_temp {
         state
             .attributes
-            .get("color_temp")
-            .and_then(value_to_u16)
-            .map(|x| x.clamp(153, 500))
+            .get("color_temp_kelvin")
+            .and_then(value_to_f64)
+            .and_then(mirek_kelvin)
+            .or_else(|| state.attributes.get("color_temp").and_then(value_to_u16))
+            .map(|x| capabilities.clamp_mirek(x))
     } else {
         None
     };
 
+    let (effect_list, effect) = if matches!(kind, HassEntityKind::Light) {
+        parse_effects(state)
+    } else {
+        (vec![], None)
+    };
+
     Some(ImportedEntity {
         entity_id: state.entity_id.clone(),
         name,
+        alias: None,
         kind,
         service_kind,
         state: state.state.clone(),
@@ -235,8 +491,13 @@ fn parse_imported_entity(state: &HassState, area_name: Option<String>) -> Option
         brightness,
         xy_color,
         color_temp,
+        sensor_value,
         area_name,
+        device: None,
         capabilities,
+        effect_list,
+        effect,
+        effects: HassEffectMap::default(),
         detected_sensor_kind: detected_kind,
         sensor_enabled: true,
         switch_mode: if matches!(kind, HassEntityKind::Switch) {
@@ -276,6 +537,14 @@ fn device_archetype(archetype: HassLightArchetype) -> DeviceArchetype {
     }
 }
 
+/// The light archetype matching a device archetype picked in the Hue app
+pub(super) fn hass_light_archetype(archetype: &DeviceArchetype) -> Option<HassLightArchetype> {
+    // Both use the Hue names, so serde does the mapping
+    serde_json::to_value(archetype)
+        .and_then(serde_json::from_value)
+        .ok()
+}
+
 fn light_archetype(imported: &ImportedEntity) -> DeviceArchetype {
     match imported.kind {
         HassEntityKind::Light => device_archetype(
@@ -294,31 +563,157 @@ fn light_archetype(imported: &ImportedEntity) -> DeviceArchetype {
                 DeviceArchetype::Plug
             }
         }
-        HassEntityKind::BinarySensor => DeviceArchetype::UnknownArchetype,
+        HassEntityKind::BinarySensor | HassEntityKind::Sensor | HassEntityKind::Event => {
+            DeviceArchetype::UnknownArchetype
+        }
+    }
+}
+
+/// Marks devices created by this backend, since registry devices carry
+/// their real manufacturer and model
+const HASS_PLATFORM_TYPE: &str = "home-assistant";
+
+fn device_name(imported: &ImportedEntity) -> &str {
+    imported
+        .alias
+        .as_deref()
+        .or_else(|| {
+            imported
+                .device
+                .as_ref()
+                .and_then(HassDeviceEntry::display_name)
+        })
+        .unwrap_or(&imported.name)
+}
+
+fn product_data(imported: &ImportedEntity, archetype: DeviceArchetype) -> DeviceProductData {
+    let non_empty = |value: &Option<String>| {
+        value
+            .as_deref()
+            .map(str::trim)
+            .filter(|x| !x.is_empty())
+            .map(ToString::to_string)
+    };
+    let device = imported.device.as_ref();
+    let model = device.and_then(|dev| non_empty(&dev.model));
+
+    DeviceProductData {
+        model_id: device
+            .and_then(|dev| non_empty(&dev.model_id))
+            .or_else(|| model.clone())
+            .unwrap_or_else(|| format!("hass-{}", imported.domain())),
+        manufacturer_name: device
+            .and_then(|dev| non_empty(&dev.manufacturer))
+            .unwrap_or_else(|| "Home Assistant".to_string()),
+        product_name: model.unwrap_or_else(|| device_name(imported).to_string()),
+        product_archetype: archetype,
+        certified: false,
+        software_version: device
+            .and_then(|dev| non_empty(&dev.sw_version))
+            .unwrap_or_else(|| "1.0.0".to_string()),
+        hardware_platform_type: Some(HASS_PLATFORM_TYPE.to_string()),
     }
 }
 
 fn make_device(service_link: ResourceLink, imported: &ImportedEntity) -> Device {
     let archetype = light_archetype(imported);
-    let domain = imported.domain();
 
     Device {
-        product_data: DeviceProductData {
-            model_id: format!("hass-{domain}"),
-            manufacturer_name: "Home Assistant".to_string(),
-            product_name: imported.name.clone(),
-            product_archetype: archetype.clone(),
-            certified: false,
-            software_version: "1.0.0".to_string(),
-            hardware_platform_type: None,
-        },
-        metadata: Metadata::new(archetype, &imported.name),
+        product_data: product_data(imported, archetype.clone()),
+        metadata: Metadata::new(archetype, device_name(imported)),
         services: btreeset![service_link],
         usertest: None,
         identify: None,
     }
 }
 
+pub(super) fn is_battery(state: &HassState) -> bool {
+    battery_level(state).is_some()
+}
+
+pub(super) fn is_software_update(state: &HassState) -> bool {
+    software_update(state).is_some()
+}
+
+/// State of an `update.*` entity, with its progress (in percent) while
+/// installing
+fn software_update(state: &HassState) -> Option<(SoftwareUpdateState, Option<f64>)> {
+    let (domain, _) = state.entity_id.split_once('.')?;
+    if domain != "update" {
+        return None;
+    }
+
+    // Before Home Assistant 2024.12, the progress was in `in_progress`
+    let in_progress = state.attributes.get("in_progress");
+    let progress = state
+        .attributes
+        .get("update_percentage")
+        .and_then(Value::as_f64)
+        .or_else(|| in_progress.and_then(Value::as_f64));
+
+    let update_state = if progress.is_some() || in_progress.and_then(Value::as_bool) == Some(true) {
+        SoftwareUpdateState::Installing
+    } else {
+        match state.state.as_str() {
+            "on" => SoftwareUpdateState::ReadyToInstall,
+            "off" => SoftwareUpdateState::NoUpdate,
+            _ => return None,
+        }
+    };
+
+    Some((update_state, progress))
+}
+
+/// Battery percentage of a `sensor.*` entity with the battery device class
+fn battery_level(state: &HassState) -> Option<f64> {
+    let (domain, _) = state.entity_id.split_once('.')?;
+    let class = state.attributes.get("device_class").and_then(Value::as_str);
+    if domain != "sensor" || class != Some("battery") {
+        return None;
+    }
+    let level = state.state.trim().parse::<f64>().ok()?;
+    level.is_finite().then(|| level.clamp(0.0, 100.0))
+}
+
+fn sync_device_power(
+    res: &mut Resources,
+    device_link: ResourceLink,
+    link_power: ResourceLink,
+    level: f64,
+) -> ApiResult<()> {
+    if res.get::<DevicePower>(&link_power).is_err() {
+        let mut power = DevicePower::new(device_link);
+        power.report(level);
+        res.add(&link_power, Resource::DevicePower(power))?;
+        res.update::<Device>(&device_link.rid, |dev| {
+            dev.services.insert(link_power);
+        })?;
+    } else {
+        res.update::<DevicePower>(&link_power.rid, |power| power.report(level))?;
+    }
+
+    Ok(())
+}
+
+fn make_entertainment(device_link: ResourceLink, light_link: ResourceLink) -> Entertainment {
+    Entertainment {
+        equalizer: true,
+        owner: device_link,
+        proxy: true,
+        renderer: true,
+        max_streams: None,
+        renderer_reference: Some(light_link),
+        segments: Some(EntertainmentSegments {
+            configurable: false,
+            max_segments: 1,
+            segments: vec![EntertainmentSegment {
+                start: 0,
+                length: 1,
+            }],
+        }),
+    }
+}
+
 fn ieee_like_from_uuid(id: &Uuid) -> String {
     let b = id.as_bytes();
     // Hue expects an EUI-64 style string for zigbee_connectivity.
@@ -364,21 +759,30 @@ fn apply_light_state(light: &mut Light, imported: &ImportedEntity) {
                         y: 0.3290,
                     }));
                 }
+                if let Some(color) = &mut light.color {
+                    // Lights driven in RGB/HS cannot go beyond sRGB, so tell Hue clients
+                    // the gamut commands are clamped to
+                    color.gamut = Some(imported.capabilities.color_mode.gamut());
+                }
             } else {
                 light.color = None;
             }
 
             if imported.capabilities.supports_color_temp {
+                let mirek_schema = imported.capabilities.mirek_schema();
                 if let Some(mirek) = imported.color_temp {
                     light.color_temperature = Some(ColorTemperature {
                         mirek: Some(mirek),
-                        mirek_schema: MirekSchema::DEFAULT,
+                        mirek_schema,
                         mirek_valid: true,
                     });
-                } else if light.color_temperature.is_none() {
+                } else if let Some(ct) = &mut light.color_temperature {
+                    ct.mirek_schema = mirek_schema;
+                    ct.mirek = ct.mirek.map(|x| imported.capabilities.clamp_mirek(x));
+                } else {
                     light.color_temperature = Some(ColorTemperature {
-                        mirek: Some(366),
-                        mirek_schema: MirekSchema::DEFAULT,
+                        mirek: Some(imported.capabilities.clamp_mirek(366)),
+                        mirek_schema,
                         mirek_valid: true,
                     });
                 }
@@ -388,48 +792,541 @@ fn apply_light_state(light: &mut Light, imported: &ImportedEntity) {
             if !imported.capabilities.supports_color_temp {
                 light.color_temperature_delta = None;
             }
+
+            if let Some((effects, effects_v2)) =
+                imported.effects.light_effects(imported.effect.as_deref())
+            {
+                light.effects = Some(effects);
+                light.effects_v2 = Some(effects_v2);
+            } else {
+                light.effects = None;
+                light.effects_v2 = None;
+            }
         }
-        HassEntityKind::Switch | HassEntityKind::BinarySensor => {
+        HassEntityKind::Switch
+        | HassEntityKind::BinarySensor
+        | HassEntityKind::Sensor
+        | HassEntityKind::Event => {
             light.dimming = None;
             light.color = None;
             light.color_temperature = None;
             light.color_temperature_delta = None;
+            light.effects = None;
+            light.effects_v2 = None;
         }
     }
 }
 
-fn make_contact_resource(imported: &ImportedEntity, device_link: ResourceLink) -> Value {
-    json!({
-        "owner": device_link,
-        "enabled": imported.sensor_enabled,
-        "contact": {
-            "contact": imported.on,
-            "contact_valid": imported.available,
-            "last_updated": Utc::now().to_rfc3339(),
+/// Home Assistant contact sensors are on when open
+fn sync_contact(
+    res: &mut Resources,
+    imported: &ImportedEntity,
+    binding: &HassEntityBinding,
+) -> ApiResult<()> {
+    if res.get::<Contact>(&binding.service_link).is_err() {
+        let mut contact = Contact::new(binding.device_link);
+        contact.enabled = imported.sensor_enabled;
+        if imported.available {
+            contact.report(imported.on);
         }
-    })
+        res.add(&binding.service_link, Resource::Contact(contact))?;
+    } else {
+        res.update::<Contact>(&binding.service_link.rid, |contact| {
+            contact.enabled = imported.sensor_enabled;
+            if imported.available {
+                contact.report(imported.on);
+            }
+        })?;
+    }
+
+    Ok(())
+}
+
+fn sync_motion(
+    res: &mut Resources,
+    imported: &ImportedEntity,
+    binding: &HassEntityBinding,
+) -> ApiResult<()> {
+    if res.get::<Motion>(&binding.service_link).is_err() {
+        res.add(
+            &binding.service_link,
+            Resource::Motion(Motion {
+                enabled: imported.sensor_enabled,
+                owner: binding.device_link,
+                motion: json!({
+                    "motion": imported.on,
+                    "motion_valid": imported.available,
+                    "last_updated": Utc::now().to_rfc3339(),
+                }),
+                sensitivity: json!({}),
+            }),
+        )?;
+    } else {
+        res.update::<Motion>(&binding.service_link.rid, |motion| {
+            motion.enabled = imported.sensor_enabled;
+            motion.motion = json!({
+                "motion": imported.on,
+                "motion_valid": imported.available,
+                "last_updated": Utc::now().to_rfc3339(),
+            });
+        })?;
+    }
+
+    Ok(())
+}
+
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+fn sync_measurement(
+    res: &mut Resources,
+    imported: &ImportedEntity,
+    binding: &HassEntityBinding,
+) -> ApiResult<()> {
+    let link = &binding.service_link;
+    let changed = Utc::now().to_rfc3339();
+
+    match imported.service_kind {
+        HassServiceKind::Temperature => {
+            let report = imported
+                .sensor_value
+                .filter(|_| imported.available)
+                .map(|t| {
+                    json!({
+                        "temperature": t,
+                        "temperature_valid": true,
+                        "temperature_report": {
+                            "changed": changed,
+                            "temperature": t,
+                        },
+                    })
+                });
+
+            if res.get::<Temperature>(link).is_err() {
+                let temperature = report.unwrap_or_else(|| {
+                    json!({
+                        "temperature": 0.0,
+                        "temperature_valid": false,
+                    })
+                });
+                let obj = Temperature {
+                    enabled: imported.sensor_enabled,
+                    owner: binding.device_link,
+                    temperature,
+                };
+                res.add(link, Resource::Temperature(obj))?;
+            } else {
+                res.update::<Temperature>(&link.rid, |obj| {
+                    obj.enabled = imported.sensor_enabled;
+                    if let Some(report) = report {
+                        obj.temperature = report;
+                    } else {
+                        // keep the last known reading, but flag it as stale
+                        obj.temperature["temperature_valid"] = json!(false);
+                    }
+                })?;
+            }
+        }
+        HassServiceKind::LightLevel => {
+            let report = imported
+                .sensor_value
+                .filter(|_| imported.available)
+                .map(|level| {
+                    let level = level as u32;
+                    json!({
+                        "light_level": level,
+                        "light_level_valid": true,
+                        "light_level_report": {
+                            "changed": changed,
+                            "light_level": level,
+                        },
+                    })
+                });
+
+            if res.get::<LightLevel>(link).is_err() {
+                let light = report.unwrap_or_else(|| {
+                    json!({
+                        "light_level": 0,
+                        "light_level_valid": false,
+                    })
+                });
+                let obj = LightLevel {
+                    enabled: imported.sensor_enabled,
+                    light,
+                    owner: binding.device_link,
+                };
+                res.add(link, Resource::LightLevel(obj))?;
+            } else {
+                res.update::<LightLevel>(&link.rid, |obj| {
+                    obj.enabled = imported.sensor_enabled;
+                    if let Some(report) = report {
+                        obj.light = report;
+                    } else {
+                        obj.light["light_level_valid"] = json!(false);
+                    }
+                })?;
+            }
+        }
+        HassServiceKind::Light
+        | HassServiceKind::Switch
+        | HassServiceKind::Motion
+        | HassServiceKind::Contact
+        | HassServiceKind::Button
+        | HassServiceKind::RelativeRotary => {}
+    }
+
+    Ok(())
+}
+
+fn sync_device(
+    res: &mut Resources,
+    imported: &ImportedEntity,
+    binding: &HassEntityBinding,
+    link_zbc: ResourceLink,
+    link_enttm: ResourceLink,
+) -> ApiResult<()> {
+    // Only color lights can render entertainment streams
+    let entertainment = imported.kind == HassEntityKind::Light
+        && imported.service_kind == HassServiceKind::Light
+        && imported.capabilities.supports_color;
+
+    let mut services = btreeset![binding.service_link, link_zbc];
+    if entertainment {
+        services.insert(link_enttm);
+    }
+
+    // Services (like entertainment) must belong to the device they were created for
+    if res
+        .get_resource(&link_enttm)
+        .is_ok_and(|rr| rr.obj.owner() != Some(binding.device_link))
+    {
+        res.delete(&link_enttm)?;
+    }
+
+    // On a device shared by several entities, the light decides what it looks like
+    let grouped = imported.device.is_some();
+    let owns_device = !grouped
+        || matches!(
+            imported.service_kind,
+            HassServiceKind::Light | HassServiceKind::Switch
+        );
+
+    if res.get::<Device>(&binding.device_link).is_err() {
+        let mut dev = make_device(binding.service_link, imported);
+        dev.services.clone_from(&services);
+        res.add(&binding.device_link, Resource::Device(dev))?;
+    } else {
+        res.update::<Device>(&binding.device_link.rid, |dev| {
+            if owns_device {
+                dev.metadata.name = device_name(imported).to_string();
+                dev.metadata.archetype = light_archetype(imported);
+            }
+            dev.product_data = product_data(imported, dev.metadata.archetype.clone());
+            if grouped {
+                dev.services.extend(services);
+            } else {
+                dev.services = services;
+            }
+        })?;
+    }
+
+    if res.get::<ZigbeeConnectivity>(&link_zbc).is_err() {
+        // Hue app expects zigbee_connectivity for "real" devices. For HA entities we emulate it.
+        let zbc = ZigbeeConnectivity {
+            owner: binding.device_link,
+            mac_address: ieee_like_from_uuid(&binding.device_link.rid),
+            status: ZigbeeConnectivityStatus::Connected,
+            channel: Some(json!({
+                "status": "set",
+                "value": "channel_25",
+            })),
+            extended_pan_id: None,
+        };
+        res.add(&link_zbc, Resource::ZigbeeConnectivity(zbc))?;
+    }
+
+    let has_enttm = res.get::<Entertainment>(&link_enttm).is_ok();
+    if entertainment && !has_enttm {
+        let enttm = make_entertainment(binding.device_link, binding.service_link);
+        res.add(&link_enttm, Resource::Entertainment(enttm))?;
+    } else if !entertainment && has_enttm {
+        res.delete(&link_enttm)?;
+    }
+
+    Ok(())
+}
+
+fn sync_service(
+    res: &mut Resources,
+    imported: &ImportedEntity,
+    binding: &HassEntityBinding,
+) -> ApiResult<()> {
+    match imported.service_kind {
+        HassServiceKind::Light | HassServiceKind::Switch => {
+            if res.get::<Light>(&binding.service_link).is_err() {
+                let mut light = Light::new(
+                    binding.device_link,
+                    LightMetadata::new(light_archetype(imported), &imported.name),
+                );
+                apply_light_state(&mut light, imported);
+                res.add(&binding.service_link, Resource::Light(light))?;
+            } else {
+                res.update::<Light>(&binding.service_link.rid, |light| {
+                    apply_light_state(light, imported);
+                })?;
+            }
+        }
+        HassServiceKind::Motion => sync_motion(res, imported, binding)?,
+        HassServiceKind::Contact => sync_contact(res, imported, binding)?,
+        HassServiceKind::Temperature | HassServiceKind::LightLevel => {
+            sync_measurement(res, imported, binding)?;
+        }
+        HassServiceKind::Button => {
+            // Button state is only ever driven by fired events
+            if res.get::<Button>(&binding.service_link).is_err() {
+                let button = make_button(binding.device_link);
+                res.add(&binding.service_link, Resource::Button(button))?;
+            }
+        }
+        HassServiceKind::RelativeRotary => {
+            if res.get::<RelativeRotary>(&binding.service_link).is_err() {
+                let rotary = make_relative_rotary(binding.device_link);
+                res.add(&binding.service_link, Resource::RelativeRotary(rotary))?;
+            }
+        }
+    }
+
+    Ok(())
 }
 
 impl HassBackend {
-    fn links_for_entity(
-        &self,
-        entity_id: &str,
-        service_kind: HassServiceKind,
-    ) -> (ResourceLink, ResourceLink) {
-        let key = format!("hass:{}:{}", self.name, entity_id);
-        let service = match service_kind {
+    /// Key for the Hue device of an entity. Entities of the same physical
+    /// device share it, while the rest get a device of their own.
+    fn device_key(&self, imported: &ImportedEntity) -> String {
+        imported.device.as_ref().map_or_else(
+            || format!("hass:{}:{}", self.name, imported.entity_id),
+            |dev| self.registry_device_key(&dev.id),
+        )
+    }
+
+    fn registry_device_key(&self, device_id: &str) -> String {
+        format!("hass:{}:device:{}", self.name, device_id)
+    }
+
+    fn links_for_entity(&self, imported: &ImportedEntity) -> (ResourceLink, ResourceLink) {
+        let key = format!("hass:{}:{}", self.name, imported.entity_id);
+        let service = match imported.service_kind {
             HassServiceKind::Light | HassServiceKind::Switch => {
                 RType::Light.deterministic(format!("{key}:light"))
             }
             HassServiceKind::Motion => RType::Motion.deterministic(format!("{key}:motion")),
             HassServiceKind::Contact => RType::Contact.deterministic(format!("{key}:contact")),
+            HassServiceKind::Temperature => {
+                RType::Temperature.deterministic(format!("{key}:temperature"))
+            }
+            HassServiceKind::LightLevel => {
+                RType::LightLevel.deterministic(format!("{key}:light_level"))
+            }
+            HassServiceKind::Button => RType::Button.deterministic(format!("{key}:button")),
+            HassServiceKind::RelativeRotary => {
+                RType::RelativeRotary.deterministic(format!("{key}:relative_rotary"))
+            }
         };
         (
-            RType::Device.deterministic(format!("{key}:device")),
+            RType::Device.deterministic(format!("{}:device", self.device_key(imported))),
             service,
         )
     }
 
+    /// Hue rooms hold devices, so place each device only once. A device
+    /// follows the room of its light, if it has one.
+    fn children_by_room(
+        &self,
+        entity_room: &HashMap<String, String>,
+    ) -> HashMap<String, BTreeSet<ResourceLink>> {
+        let mut children_by_room = self
+            .room_map
+            .keys()
+            .map(|room_id| (room_id.clone(), BTreeSet::<ResourceLink>::new()))
+            .collect::<HashMap<_, _>>();
+
+        let mut bindings = self.entity_map.values().collect::<Vec<_>>();
+        bindings.sort_by_key(|binding| {
+            (
+                !matches!(binding.service_kind, HassServiceKind::Light),
+                &binding.entity_id,
+            )
+        });
+
+        let mut placed = HashSet::new();
+        for binding in bindings {
+            if !placed.insert(binding.device_link) {
+                continue;
+            }
+            let room_id = entity_room
+                .get(&binding.entity_id)
+                .cloned()
+                .unwrap_or_else(|| HassUiConfig::DEFAULT_ROOM_ID.to_string());
+            children_by_room
+                .entry(room_id)
+                .or_default()
+                .insert(binding.device_link);
+        }
+
+        children_by_room
+    }
+
+    /// Drop an entity from the bridge. Its device goes with it, unless other
+    /// entities of the same physical device still use it.
+    fn remove_binding(
+        &mut self,
+        res: &mut Resources,
+        binding: &HassEntityBinding,
+    ) -> ApiResult<()> {
+        self.light_map.remove(&binding.service_link.rid);
+        self.sensor_map.remove(&binding.service_link.rid);
+
+        let shared = self
+            .entity_map
+            .values()
+            .any(|other| other.device_link == binding.device_link);
+        if shared {
+            if self.device_map.get(&binding.device_link.rid) == Some(&binding.entity_id) {
+                if let Some(other) = self
+                    .entity_map
+                    .values()
+                    .find(|other| other.device_link == binding.device_link)
+                {
+                    self.device_map
+                        .insert(binding.device_link.rid, other.entity_id.clone());
+                }
+            }
+            let link_enttm = RType::Entertainment.deterministic(format!(
+                "hass:{}:{}:entertainment",
+                self.name, binding.entity_id
+            ));
+            for link in [binding.service_link, link_enttm] {
+                if res.get_resource(&link).is_ok() {
+                    res.delete(&link)?;
+                }
+            }
+        } else {
+            self.device_map.remove(&binding.device_link.rid);
+            if res.get_resource(&binding.device_link).is_ok() {
+                res.delete(&binding.device_link)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Refresh the registry snapshot. Without one, fall back to looking up
+    /// areas through a template.
+    async fn refresh_registry(&mut self) -> HashMap<String, String> {
+        match self.client.get_registry().await {
+            Ok(mut registry) => {
+                match button::discover_remote_buttons(&self.client, &mut registry).await {
+                    Ok(buttons) => self.remote_buttons = buttons,
+                    Err(err) => {
+                        log::warn!(
+                            "[{}] Failed to query Home Assistant device triggers: {}",
+                            self.name,
+                            err
+                        );
+                        self.ui_log(format!("Remote button discovery failed: {err}"))
+                            .await;
+                        self.remote_buttons.clear();
+                    }
+                }
+                self.registry = registry;
+            }
+            Err(err) => {
+                log::warn!(
+                    "[{}] Failed to query Home Assistant registries. Devices will not be grouped: {}",
+                    self.name,
+                    err
+                );
+                self.ui_log(format!(
+                    "Registry sync fallback (no device grouping): {err}"
+                ))
+                .await;
+            }
+        }
+
+        if !self.registry.is_empty() {
+            return HashMap::new();
+        }
+
+        match self.client.get_entity_areas().await {
+            Ok(map) => map,
+            Err(err) => {
+                log::warn!(
+                    "[{}] Failed to query Home Assistant areas. Continuing without area mapping: {}",
+                    self.name,
+                    err
+                );
+                self.ui_log(format!("Area sync fallback (no areas): {err}"))
+                    .await;
+                HashMap::new()
+            }
+        }
+    }
+
+    /// The registry key and Hue device of the device an entity belongs to,
+    /// if that device is exposed to Hue
+    fn exposed_device(&self, entity_id: &str) -> Option<(String, ResourceLink)> {
+        let device = self.registry.device(entity_id)?;
+
+        let key = self.registry_device_key(&device.id);
+        let device_link = RType::Device.deterministic(format!("{key}:device"));
+        self.entity_map
+            .values()
+            .any(|binding| binding.device_link == device_link)
+            .then_some((key, device_link))
+    }
+
+    /// Update the battery and software update of exposed devices, which
+    /// come from other entities of the same device
+    fn sync_device_services(&self, state: &HassState, res: &mut Resources) -> ApiResult<()> {
+        self.sync_battery(state, res)?;
+        self.sync_software_update(state, res)
+    }
+
+    /// Update the battery of an exposed device from its battery sensor
+    fn sync_battery(&self, state: &HassState, res: &mut Resources) -> ApiResult<()> {
+        let Some(level) = battery_level(state) else {
+            return Ok(());
+        };
+        let Some((key, device_link)) = self.exposed_device(&state.entity_id) else {
+            return Ok(());
+        };
+
+        let link_power = RType::DevicePower.deterministic(format!("{key}:device_power"));
+        sync_device_power(res, device_link, link_power, level)
+    }
+
+    /// Update the software update of an exposed device from its `update`
+    /// entity. The entity is kept as the topic, to install the update.
+    fn sync_software_update(&self, state: &HassState, res: &mut Resources) -> ApiResult<()> {
+        let Some((update_state, progress)) = software_update(state) else {
+            return Ok(());
+        };
+        let Some((key, device_link)) = self.exposed_device(&state.entity_id) else {
+            return Ok(());
+        };
+
+        let link_swu = RType::DeviceSoftwareUpdate.deterministic(format!("{key}:software_update"));
+        if res.get::<DeviceSoftwareUpdate>(&link_swu).is_err() {
+            let swu = DeviceSoftwareUpdate::new(device_link);
+            res.add(&link_swu, Resource::DeviceSoftwareUpdate(swu))?;
+            res.update::<Device>(&device_link.rid, |dev| {
+                dev.services.insert(link_swu);
+            })?;
+        }
+        res.aux_set(&link_swu, AuxData::new().with_topic(&state.entity_id));
+        res.update::<DeviceSoftwareUpdate>(&link_swu.rid, |swu| {
+            swu.report(update_state, progress);
+        })
+    }
+
     pub(super) fn ensure_rooms(
         &mut self,
         res: &mut Resources,
@@ -452,14 +1349,20 @@ impl HassBackend {
             if res.get::<Room>(&binding.room_link).is_err() {
                 let room = Room {
                     children: BTreeSet::new(),
-                    metadata: RoomMetadata::new(RoomArchetype::Home, &binding.room_name),
+                    metadata: RoomMetadata::new(
+                        room.archetype.unwrap_or(RoomArchetype::Home),
+                        &binding.room_name,
+                    ),
                     services: btreeset![binding.grouped_light_link],
                 };
                 res.add(&binding.room_link, Resource::Room(room))?;
             } else {
-                res.update::<Room>(&binding.room_link.rid, |room| {
-                    room.metadata.name.clone_from(&binding.room_name);
-                    room.services = btreeset![binding.grouped_light_link];
+                res.update::<Room>(&binding.room_link.rid, |hue_room| {
+                    hue_room.metadata.name.clone_from(&binding.room_name);
+                    if let Some(archetype) = room.archetype {
+                        hue_room.metadata.archetype = archetype;
+                    }
+                    hue_room.services = btreeset![binding.grouped_light_link];
                 })?;
             }
 
@@ -522,22 +1425,7 @@ impl HassBackend {
         let mut res = state.lock().await;
         self.ensure_rooms(&mut res, &ui_config)?;
 
-        let mut children_by_room = self
-            .room_map
-            .keys()
-            .map(|room_id| (room_id.clone(), BTreeSet::<ResourceLink>::new()))
-            .collect::<HashMap<_, _>>();
-
-        for binding in self.entity_map.values() {
-            let room_id = entity_room
-                .get(&binding.entity_id)
-                .cloned()
-                .unwrap_or_else(|| HassUiConfig::DEFAULT_ROOM_ID.to_string());
-            children_by_room
-                .entry(room_id)
-                .or_default()
-                .insert(binding.device_link);
-        }
+        let children_by_room = self.children_by_room(&entity_room);
 
         for room in self.room_map.values() {
             let children = children_by_room
@@ -571,10 +1459,13 @@ impl HassBackend {
         imported: &ImportedEntity,
         res: &mut Resources,
     ) -> ApiResult<()> {
-        let (device_link, service_link) =
-            self.links_for_entity(&imported.entity_id, imported.service_kind);
-        let link_zbc = RType::ZigbeeConnectivity
-            .deterministic(format!("hass:{}:{}:zbc", self.name, imported.entity_id));
+        let (device_link, service_link) = self.links_for_entity(imported);
+        let link_zbc =
+            RType::ZigbeeConnectivity.deterministic(format!("{}:zbc", self.device_key(imported)));
+        let link_enttm = RType::Entertainment.deterministic(format!(
+            "hass:{}:{}:entertainment",
+            self.name, imported.entity_id
+        ));
         let binding = self
             .entity_map
             .entry(imported.entity_id.clone())
@@ -586,16 +1477,19 @@ impl HassBackend {
                 service_link,
                 device_link,
                 capabilities: imported.capabilities,
+                effects: imported.effects.clone(),
                 switch_mode: imported.switch_mode,
             });
 
         let previous_service_link = binding.service_link;
+        let previous_device_link = binding.device_link;
         binding.name.clone_from(&imported.name);
         binding.kind = imported.kind;
         binding.service_kind = imported.service_kind;
         binding.service_link = service_link;
         binding.device_link = device_link;
         binding.capabilities = imported.capabilities;
+        binding.effects.clone_from(&imported.effects);
         binding.switch_mode = imported.switch_mode;
 
         if previous_service_link != binding.service_link {
@@ -605,102 +1499,49 @@ impl HassBackend {
                 let _ = res.delete(&previous_service_link);
             }
         }
+        if previous_device_link != binding.device_link
+            && self.device_map.get(&previous_device_link.rid) == Some(&imported.entity_id)
+        {
+            self.device_map.remove(&previous_device_link.rid);
+        }
+
+        // A service still owned by another device (e.g. from before it was
+        // grouped) is recreated below, so its device can be pruned safely.
+        if res
+            .get_resource(&binding.service_link)
+            .is_ok_and(|rr| rr.obj.owner() != Some(binding.device_link))
+        {
+            res.delete(&binding.service_link)?;
+        }
 
-        self.device_map
-            .insert(binding.device_link.rid, imported.entity_id.clone());
+        // Shared devices are looked up through their light, if they have one
+        if matches!(imported.service_kind, HassServiceKind::Light)
+            || !self.device_map.contains_key(&binding.device_link.rid)
+        {
+            self.device_map
+                .insert(binding.device_link.rid, imported.entity_id.clone());
+        }
         match imported.service_kind {
             HassServiceKind::Light | HassServiceKind::Switch => {
                 self.light_map
                     .insert(binding.service_link.rid, imported.entity_id.clone());
                 self.sensor_map.remove(&binding.service_link.rid);
             }
-            HassServiceKind::Motion | HassServiceKind::Contact => {
+            HassServiceKind::Motion
+            | HassServiceKind::Contact
+            | HassServiceKind::Temperature
+            | HassServiceKind::LightLevel
+            | HassServiceKind::Button
+            | HassServiceKind::RelativeRotary => {
                 self.sensor_map
                     .insert(binding.service_link.rid, imported.entity_id.clone());
                 self.light_map.remove(&binding.service_link.rid);
             }
         }
 
-        if res.get::<Device>(&binding.device_link).is_err() {
-            let mut dev = make_device(binding.service_link, imported);
-            dev.services.insert(link_zbc);
-            res.add(&binding.device_link, Resource::Device(dev))?;
-        } else {
-            res.update::<Device>(&binding.device_link.rid, |dev| {
-                dev.metadata.name.clone_from(&imported.name);
-                dev.metadata.archetype = light_archetype(imported);
-                dev.product_data.product_name.clone_from(&imported.name);
-                dev.product_data.product_archetype = light_archetype(imported);
-                dev.services = btreeset![binding.service_link, link_zbc];
-            })?;
-        }
-
-        if res.get::<ZigbeeConnectivity>(&link_zbc).is_err() {
-            // Hue app expects zigbee_connectivity for "real" devices. For HA entities we emulate it.
-            let zbc = ZigbeeConnectivity {
-                owner: binding.device_link,
-                mac_address: ieee_like_from_uuid(&binding.device_link.rid),
-                status: ZigbeeConnectivityStatus::Connected,
-                channel: Some(json!({
-                    "status": "set",
-                    "value": "channel_25",
-                })),
-                extended_pan_id: None,
-            };
-            res.add(&link_zbc, Resource::ZigbeeConnectivity(zbc))?;
-        }
+        sync_device(res, imported, binding, link_zbc, link_enttm)?;
 
-        match imported.service_kind {
-            HassServiceKind::Light | HassServiceKind::Switch => {
-                if res.get::<Light>(&binding.service_link).is_err() {
-                    let mut light = Light::new(
-                        binding.device_link,
-                        LightMetadata::new(light_archetype(imported), &imported.name),
-                    );
-                    apply_light_state(&mut light, imported);
-                    res.add(&binding.service_link, Resource::Light(light))?;
-                } else {
-                    res.update::<Light>(&binding.service_link.rid, |light| {
-                        apply_light_state(light, imported);
-                    })?;
-                }
-            }
-            HassServiceKind::Motion => {
-                if res.get::<Motion>(&binding.service_link).is_err() {
-                    res.add(
-                        &binding.service_link,
-                        Resource::Motion(Motion {
-                            enabled: imported.sensor_enabled,
-                            owner: binding.device_link,
-                            motion: json!({
-                                "motion": imported.on,
-                                "motion_valid": imported.available,
-                                "last_updated": Utc::now().to_rfc3339(),
-                            }),
-                            sensitivity: json!({}),
-                        }),
-                    )?;
-                } else {
-                    res.update::<Motion>(&binding.service_link.rid, |motion| {
-                        motion.enabled = imported.sensor_enabled;
-                        motion.motion = json!({
-                            "motion": imported.on,
-                            "motion_valid": imported.available,
-                            "last_updated": Utc::now().to_rfc3339(),
-                        });
-                    })?;
-                }
-            }
-            HassServiceKind::Contact => {
-                let value = make_contact_resource(imported, binding.device_link);
-                if res.get_resource(&binding.service_link).is_ok() {
-                    let _ = res.delete(&binding.service_link);
-                }
-                res.add(&binding.service_link, Resource::Contact(value))?;
-            }
-        }
-
-        Ok(())
+        sync_service(res, imported, binding)
     }
 
     fn prune_homeassistant_devices(
@@ -722,10 +1563,11 @@ impl HassBackend {
                 continue;
             };
 
-            if dev.product_data.manufacturer_name != "Home Assistant" {
-                continue;
-            }
-            if !dev.product_data.model_id.starts_with("hass-") {
+            let created_here = dev.product_data.hardware_platform_type.as_deref()
+                == Some(HASS_PLATFORM_TYPE)
+                || (dev.product_data.manufacturer_name == "Home Assistant"
+                    && dev.product_data.model_id.starts_with("hass-"));
+            if !created_here {
                 continue;
             }
 
@@ -763,7 +1605,9 @@ impl HassBackend {
                     HassEntityKind::Switch => {
                         binding.switch_mode.unwrap_or(HassSwitchMode::Plug) == HassSwitchMode::Light
                     }
-                    HassEntityKind::BinarySensor => false,
+                    HassEntityKind::BinarySensor
+                    | HassEntityKind::Sensor
+                    | HassEntityKind::Event => false,
                 };
                 if !grouped_as_light {
                     continue;
@@ -819,24 +1663,25 @@ impl HassBackend {
 
         let states = self.client.get_states().await?;
         let core_config = self.client.get_core_config().await.ok();
-        let area_map = match self.client.get_entity_areas().await {
-            Ok(map) => map,
-            Err(err) => {
-                log::warn!(
-                    "[{}] Failed to query Home Assistant areas. Continuing without area mapping: {}",
-                    self.name,
-                    err
-                );
-                self.ui_log(format!("Area sync fallback (no areas): {err}"))
-                    .await;
-                HashMap::new()
-            }
-        };
+        let area_map = self.refresh_registry().await;
+
+        let button_states = self
+            .remote_buttons
+            .values()
+            .map(|button| button.state(None))
+            .collect::<Vec<_>>();
 
         let mut parsed = states
             .iter()
+            .chain(&button_states)
             .filter_map(|state| {
-                parse_imported_entity(state, area_map.get(&state.entity_id).cloned())
+                let area_name = self
+                    .registry
+                    .area_name(&state.entity_id)
+                    .or_else(|| area_map.get(&state.entity_id).cloned());
+                let mut imported = parse_imported_entity(state, area_name)?;
+                imported.device = self.registry.device(&state.entity_id).cloned();
+                Some(imported)
             })
             .collect::<Vec<_>>();
         parsed.sort_by(|a, b| a.entity_id.cmp(&b.entity_id));
@@ -884,7 +1729,8 @@ impl HassBackend {
         for imported in &parsed {
             let mut imported = imported.clone();
             if let Some(alias) = ui_config.entity_alias(&imported.entity_id) {
-                imported.name = alias;
+                imported.name.clone_from(&alias);
+                imported.alias = Some(alias);
             }
             if matches!(imported.kind, HassEntityKind::Switch) {
                 imported.switch_mode = Some(ui_config.switch_mode(&imported.entity_id));
@@ -895,17 +1741,21 @@ impl HassBackend {
             {
                 imported.light_archetype = Some(ui_config.light_archetype(&imported.entity_id));
             }
+            if matches!(imported.kind, HassEntityKind::Light) {
+                imported.effects = HassEffectMap::new(
+                    &imported.effect_list,
+                    &ui_config.effect_map(&imported.entity_id),
+                );
+            }
 
             let detected_sensor_kind = imported
                 .detected_sensor_kind
                 .unwrap_or(HassSensorKind::Ignore);
-            if matches!(imported.kind, HassEntityKind::BinarySensor) {
-                imported.service_kind =
-                    match ui_config.sensor_kind(&imported.entity_id, detected_sensor_kind) {
-                        HassSensorKind::Motion => HassServiceKind::Motion,
-                        HassSensorKind::Contact => HassServiceKind::Contact,
-                        HassSensorKind::Ignore => imported.service_kind,
-                    };
+            if imported.kind.is_sensor() {
+                imported.service_kind = selected_service_kind(
+                    &imported,
+                    ui_config.sensor_kind(&imported.entity_id, detected_sensor_kind),
+                );
                 imported.sensor_enabled = ui_config.sensor_enabled(&imported.entity_id);
             }
 
@@ -915,12 +1765,17 @@ impl HassBackend {
             let selected_sensor_kind = match imported.service_kind {
                 HassServiceKind::Motion => Some(HassSensorKind::Motion),
                 HassServiceKind::Contact => Some(HassSensorKind::Contact),
-                HassServiceKind::Light | HassServiceKind::Switch => None,
+                HassServiceKind::Temperature => Some(HassSensorKind::Temperature),
+                HassServiceKind::LightLevel => Some(HassSensorKind::LightLevel),
+                HassServiceKind::Light
+                | HassServiceKind::Switch
+                | HassServiceKind::Button
+                | HassServiceKind::RelativeRotary => None,
             };
 
             let mut included =
                 ui_config.should_include(&imported.entity_id, &imported.name, imported.available);
-            if matches!(imported.kind, HassEntityKind::BinarySensor)
+            if imported.kind.is_sensor()
                 && matches!(
                     ui_config.sensor_kind(&imported.entity_id, detected_sensor_kind),
                     HassSensorKind::Ignore
@@ -952,6 +1807,7 @@ impl HassBackend {
                 switch_mode: imported.switch_mode,
                 sensor_kind: selected_sensor_kind,
                 light_archetype: imported.light_archetype,
+                effect_list: imported.effect_list.clone(),
                 enabled: imported.sensor_enabled,
             });
         }
@@ -968,17 +1824,16 @@ impl HassBackend {
         for imported in imported_included.values() {
             self.sync_single_entity(imported, &mut res)?;
         }
+        for state in &states {
+            self.sync_device_services(state, &mut res)?;
+        }
 
         // If the user previously exposed many entities, they may still exist in the persisted
         // Hue resource DB after a restart (since `entity_map` is in-memory only). Always prune
         // any Home Assistant-generated devices that are no longer included.
         let keep_device_rids = imported_included
             .values()
-            .map(|imported| {
-                let (device_link, _service_link) =
-                    self.links_for_entity(&imported.entity_id, imported.service_kind);
-                device_link.rid
-            })
+            .map(|imported| self.links_for_entity(imported).0.rid)
             .collect::<HashSet<_>>();
         let pruned = self.prune_homeassistant_devices(&mut res, &keep_device_rids)?;
         if pruned > 0 {
@@ -996,10 +1851,7 @@ impl HassBackend {
             .collect::<Vec<_>>();
         for entity_id in stale {
             if let Some(binding) = self.entity_map.remove(&entity_id) {
-                self.light_map.remove(&binding.service_link.rid);
-                self.sensor_map.remove(&binding.service_link.rid);
-                self.device_map.remove(&binding.device_link.rid);
-                if let Err(err) = res.delete(&binding.device_link) {
+                if let Err(err) = self.remove_binding(&mut res, &binding) {
                     log::warn!(
                         "[{}] Failed to delete stale entity {}: {}",
                         self.name,
@@ -1010,22 +1862,7 @@ impl HassBackend {
             }
         }
 
-        let mut children_by_room = self
-            .room_map
-            .keys()
-            .map(|room_id| (room_id.clone(), BTreeSet::<ResourceLink>::new()))
-            .collect::<HashMap<_, _>>();
-
-        for binding in self.entity_map.values() {
-            let room_id = entity_room
-                .get(&binding.entity_id)
-                .cloned()
-                .unwrap_or_else(|| HassUiConfig::DEFAULT_ROOM_ID.to_string());
-            children_by_room
-                .entry(room_id)
-                .or_default()
-                .insert(binding.device_link);
-        }
+        let children_by_room = self.children_by_room(&entity_room);
 
         for room in self.room_map.values() {
             let children = children_by_room
@@ -1038,12 +1875,18 @@ impl HassBackend {
         }
 
         self.sync_grouped_light_states(&imported_included, &entity_room, &mut res)?;
+        drop(res);
+
+        self.light_groups = light_groups(&states);
+        let scenes = self.import_scenes(&states).await?;
+        self.subscribe_remote_buttons().await;
 
         self.ui_log(format!(
-            "Synced {} entities ({} exposed, {} hidden) across {} rooms",
+            "Synced {} entities ({} exposed, {} hidden) and {} scenes across {} rooms",
             parsed.len(),
             imported_included.len(),
             parsed.len().saturating_sub(imported_included.len()),
+            scenes,
             self.room_map.len()
         ))
         .await;
@@ -1054,20 +1897,30 @@ impl HassBackend {
     pub(super) async fn sync_entity_by_id(&mut self, entity_id: &str) -> ApiResult<()> {
         self.apply_runtime_connection().await?;
 
-        let state = self.client.get_state(entity_id).await?;
-        let area_name = self.client.get_entity_area(entity_id).await.ok().flatten();
+        let state = match self.remote_buttons.get(entity_id) {
+            Some(button) => button.state(None),
+            None => self.client.get_state(entity_id).await?,
+        };
+        let area_name = match self.registry.area_name(entity_id) {
+            Some(area_name) => Some(area_name),
+            None if self.registry.is_empty() => {
+                self.client.get_entity_area(entity_id).await.ok().flatten()
+            }
+            None => None,
+        };
         let Some(mut imported) = parse_imported_entity(&state, area_name) else {
             return Err(crate::error::ApiError::service_error(format!(
                 "[{}] Unsupported Home Assistant entity {}",
                 self.name, entity_id
             )));
         };
+        imported.device = self.registry.device(entity_id).cloned();
 
         let ui_state = self.ui_state.lock().await;
         let ui_config = ui_state.config_normalized();
         let mut include =
             ui_config.should_include(&imported.entity_id, &imported.name, imported.available);
-        if matches!(imported.kind, HassEntityKind::BinarySensor) {
+        if imported.kind.is_sensor() {
             let detected_sensor_kind = imported
                 .detected_sensor_kind
                 .unwrap_or(HassSensorKind::Ignore);
@@ -1092,7 +1945,8 @@ impl HassBackend {
 
         // Apply alias + sensor settings (UI config is source of truth).
         if let Some(alias) = ui_config.entity_alias(&imported.entity_id) {
-            imported.name = alias;
+            imported.name.clone_from(&alias);
+            imported.alias = Some(alias);
         }
         if matches!(imported.kind, HassEntityKind::Switch) {
             imported.switch_mode = Some(ui_config.switch_mode(&imported.entity_id));
@@ -1103,15 +1957,20 @@ impl HassBackend {
         {
             imported.light_archetype = Some(ui_config.light_archetype(&imported.entity_id));
         }
-        if matches!(imported.kind, HassEntityKind::BinarySensor) {
+        if matches!(imported.kind, HassEntityKind::Light) {
+            imported.effects = HassEffectMap::new(
+                &imported.effect_list,
+                &ui_config.effect_map(&imported.entity_id),
+            );
+        }
+        if imported.kind.is_sensor() {
             let detected = imported
                 .detected_sensor_kind
                 .unwrap_or(HassSensorKind::Ignore);
-            imported.service_kind = match ui_config.sensor_kind(&imported.entity_id, detected) {
-                HassSensorKind::Motion => HassServiceKind::Motion,
-                HassSensorKind::Contact => HassServiceKind::Contact,
-                HassSensorKind::Ignore => imported.service_kind,
-            };
+            imported.service_kind = selected_service_kind(
+                &imported,
+                ui_config.sensor_kind(&imported.entity_id, detected),
+            );
             imported.sensor_enabled = ui_config.sensor_enabled(&imported.entity_id);
         }
 
@@ -1124,7 +1983,7 @@ impl HassBackend {
         self.sync_single_entity(&imported, &mut res)?;
 
         // Move to selected room (remove from others first).
-        let (device_link, _svc) = self.links_for_entity(&imported.entity_id, imported.service_kind);
+        let (device_link, _svc) = self.links_for_entity(&imported);
         for room in self.room_map.values() {
             res.try_update::<Room>(&room.room_link.rid, |hue_room| {
                 hue_room.children.remove(&device_link);
@@ -1143,15 +2002,31 @@ impl HassBackend {
         Ok(())
     }
 
-    pub(super) async fn handle_state_update(&mut self, state: HassState) -> ApiResult<()> {
+    pub(super) async fn handle_state_update(
+        &mut self,
+        state: HassState,
+        old_state: Option<&HassState>,
+    ) -> ApiResult<()> {
+        if scenes::is_scene(&state.entity_id) {
+            return self.handle_scene_update(&state, old_state).await;
+        }
+        if let Some(members) = light_group_members(&state) {
+            self.light_groups.insert(state.entity_id.clone(), members);
+        }
+
         // Realtime HA -> Hue sync: update only included entities without polling.
         let ui_state = self.ui_state.lock().await;
         let ui_config = ui_state.config_normalized();
         drop(ui_state);
 
         let Some(mut imported) = parse_imported_entity(&state, None) else {
+            if is_battery(&state) || is_software_update(&state) {
+                let mut res = self.state.lock().await;
+                self.sync_device_services(&state, &mut res)?;
+            }
             return Ok(());
         };
+        imported.device = self.registry.device(&imported.entity_id).cloned();
 
         // HA websocket state_changed events can omit capability metadata like supported_color_modes.
         // Never downgrade a light to "on/off only" just because the incremental payload is sparse.
@@ -1162,11 +2037,23 @@ impl HassBackend {
                 imported.capabilities = existing.capabilities;
             }
         }
+        if imported.capabilities.supports_color_temp && imported.capabilities.mirek_schema.is_none()
+        {
+            if let Some(existing) = self.entity_map.get(&imported.entity_id) {
+                imported.capabilities.mirek_schema = existing.capabilities.mirek_schema;
+            }
+        }
+        if matches!(imported.kind, HassEntityKind::Event) && button::event_types(&state).is_empty()
+        {
+            if let Some(existing) = self.entity_map.get(&imported.entity_id) {
+                imported.service_kind = existing.service_kind;
+            }
+        }
 
         // Decide inclusion based on UI config (explicit visible overrides patterns/defaults).
         let mut include =
             ui_config.should_include(&imported.entity_id, &imported.name, imported.available);
-        if matches!(imported.kind, HassEntityKind::BinarySensor) {
+        if imported.kind.is_sensor() {
             let detected = imported
                 .detected_sensor_kind
                 .unwrap_or(HassSensorKind::Ignore);
@@ -1182,7 +2069,8 @@ impl HassBackend {
         }
 
         if let Some(alias) = ui_config.entity_alias(&imported.entity_id) {
-            imported.name = alias;
+            imported.name.clone_from(&alias);
+            imported.alias = Some(alias);
         }
         if matches!(imported.kind, HassEntityKind::Switch) {
             imported.switch_mode = Some(ui_config.switch_mode(&imported.entity_id));
@@ -1193,39 +2081,61 @@ impl HassBackend {
         {
             imported.light_archetype = Some(ui_config.light_archetype(&imported.entity_id));
         }
-        if matches!(imported.kind, HassEntityKind::BinarySensor) {
+        if matches!(imported.kind, HassEntityKind::Light) {
+            imported.effects = HassEffectMap::new(
+                &imported.effect_list,
+                &ui_config.effect_map(&imported.entity_id),
+            );
+        }
+        if imported.kind.is_sensor() {
             let detected = imported
                 .detected_sensor_kind
                 .unwrap_or(HassSensorKind::Ignore);
-            imported.service_kind = match ui_config.sensor_kind(&imported.entity_id, detected) {
-                HassSensorKind::Motion => HassServiceKind::Motion,
-                HassSensorKind::Contact => HassServiceKind::Contact,
-                HassSensorKind::Ignore => imported.service_kind,
-            };
+            imported.service_kind = selected_service_kind(
+                &imported,
+                ui_config.sensor_kind(&imported.entity_id, detected),
+            );
             imported.sensor_enabled = ui_config.sensor_enabled(&imported.entity_id);
         }
 
-        let state = self.state.clone();
-        let mut res = state.lock().await;
+        // Like capabilities, the effect list is not part of every update
+        if imported.effects.is_empty() && !state.attributes.contains_key("effect_list") {
+            if let Some(existing) = self.entity_map.get(&imported.entity_id) {
+                imported.effects = existing.effects.clone();
+            }
+        }
+
+        let resources = self.state.clone();
+        let mut res = resources.lock().await;
         self.ensure_rooms(&mut res, &ui_config)?;
         self.sync_single_entity(&imported, &mut res)?;
 
+        // The state of an event entity is the timestamp of its last event, so
+        // any change of it (but not of attributes alone) means it just fired.
+        // Coming back from "unavailable" (or "unknown") is not a press either.
+        let fired = old_state.is_some_and(|old| {
+            old.state != state.state && !matches!(old.state.as_str(), "unavailable" | "unknown")
+        }) && !matches!(state.state.as_str(), "unavailable" | "unknown");
+        if fired && matches!(imported.kind, HassEntityKind::Event) {
+            if let Some(binding) = self.entity_map.get(&imported.entity_id) {
+                Self::emit_entity_event(binding, &state, &mut res)?;
+            }
+        }
+
         Ok(())
     }
 
     pub(super) async fn remove_entity_by_id(&mut self, entity_id: &str) -> ApiResult<()> {
-        let device_link =
-            RType::Device.deterministic(format!("hass:{}:{}:device", self.name, entity_id));
-
         {
-            let mut res = self.state.lock().await;
-            let _ = res.delete(&device_link);
-        }
-
-        if let Some(binding) = self.entity_map.remove(entity_id) {
-            self.light_map.remove(&binding.service_link.rid);
-            self.sensor_map.remove(&binding.service_link.rid);
-            self.device_map.remove(&binding.device_link.rid);
+            let state = Arc::clone(&self.state);
+            let mut res = state.lock().await;
+            if let Some(binding) = self.entity_map.remove(entity_id) {
+                self.remove_binding(&mut res, &binding)?;
+            } else {
+                let device_link =
+                    RType::Device.deterministic(format!("hass:{}:{}:device", self.name, entity_id));
+                let _ = res.delete(&device_link);
+            }
         }
 
         self.ui_log(format!("Removed {} from Hue bridge", entity_id))
@@ -1233,3 +2143,197 @@ impl HassBackend {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use hue::api::DeviceArchetype;
+    use serde_json::{Map, Value, json};
+
+    use crate::backend::hass::client::HassState;
+    use crate::backend::hass::{HassEntityKind, HassServiceKind};
+    use crate::model::hass::{HassLightArchetype, HassSensorKind};
+
+    use super::{
+        detected_sensor_kind, device_name, hass_light_archetype, is_battery, parse_imported_entity,
+        parse_mirek_schema, parse_sensor_value,
+    };
+
+    fn state(entity_id: &str, state: &str, attributes: Value) -> HassState {
+        HassState {
+            entity_id: entity_id.to_string(),
+            state: state.to_string(),
+            attributes: match attributes {
+                Value::Object(attributes) => attributes,
+                _ => Map::new(),
+            },
+        }
+    }
+
+    #[test]
+    fn mirek_schema_prefers_kelvin() {
+        let schema = |attributes| {
+            parse_mirek_schema(&state("light.x", "on", attributes))
+                .map(|schema| (schema.mirek_minimum, schema.mirek_maximum))
+        };
+        assert_eq!(
+            schema(json!({
+                "min_color_temp_kelvin": 2202,
+                "max_color_temp_kelvin": 6535,
+                "min_mireds": 1,
+                "max_mireds": 2,
+            })),
+            Some((153, 454))
+        );
+        assert_eq!(
+            schema(json!({"min_mireds": 153.2, "max_mireds": 500})),
+            Some((153, 500))
+        );
+        assert_eq!(schema(json!({"min_mireds": 400, "max_mireds": 200})), None);
+        assert_eq!(schema(json!({})), None);
+    }
+
+    #[test]
+    fn color_temp_from_kelvin() {
+        let light = state(
+            "light.desk",
+            "on",
+            json!({
+                "supported_color_modes": ["color_temp"],
+                "color_temp_kelvin": 2700,
+                "color_temp": 100,
+                "min_color_temp_kelvin": 2000,
+                "max_color_temp_kelvin": 6500,
+            }),
+        );
+        let imported = parse_imported_entity(&light, None).unwrap();
+        assert!(imported.capabilities.supports_color_temp);
+        assert_eq!(imported.color_temp, Some(370));
+
+        // Out of range values end up at the edge of the range
+        let light = state(
+            "light.desk",
+            "on",
+            json!({
+                "supported_color_modes": ["color_temp"],
+                "color_temp_kelvin": 1500,
+                "min_color_temp_kelvin": 2000,
+                "max_color_temp_kelvin": 6500,
+            }),
+        );
+        let imported = parse_imported_entity(&light, None).unwrap();
+        assert_eq!(imported.color_temp, Some(500));
+    }
+
+    #[test]
+    fn sensor_kind_from_device_class() {
+        for (device_class, kind) in [
+            ("motion", HassSensorKind::Motion),
+            ("occupancy", HassSensorKind::Motion),
+            ("window", HassSensorKind::Contact),
+            ("Temperature", HassSensorKind::Temperature),
+            ("illuminance", HassSensorKind::LightLevel),
+            ("humidity", HassSensorKind::Ignore),
+        ] {
+            let st = state("sensor.x", "1", json!({"device_class": device_class}));
+            assert_eq!(detected_sensor_kind(&st), kind, "{device_class}");
+        }
+    }
+
+    #[test]
+    fn temperature_in_celsius() {
+        let temp = |value: &str, unit: &str| {
+            let st = state("sensor.t", value, json!({"unit_of_measurement": unit}));
+            parse_sensor_value(&st, HassSensorKind::Temperature)
+        };
+        assert_eq!(temp("21.456", "°C"), Some(21.46));
+        assert_eq!(temp("212", "°F"), Some(100.0));
+        assert_eq!(temp("32", "°f"), Some(0.0));
+        assert_eq!(temp("unavailable", "°C"), None);
+        assert_eq!(temp("NaN", "°C"), None);
+    }
+
+    #[test]
+    fn illuminance_as_light_level() {
+        let level = |value: &str| {
+            parse_sensor_value(
+                &state("sensor.l", value, json!({})),
+                HassSensorKind::LightLevel,
+            )
+        };
+        assert_eq!(level("0"), Some(0.0));
+        assert_eq!(level("1"), Some(1.0));
+        assert_eq!(level("1000"), Some(30001.0));
+    }
+
+    #[test]
+    fn sensors_need_a_known_measurement() {
+        let temp = state(
+            "sensor.outside",
+            "12.5",
+            json!({"device_class": "temperature", "unit_of_measurement": "°C"}),
+        );
+        let imported = parse_imported_entity(&temp, None).unwrap();
+        assert_eq!(imported.kind, HassEntityKind::Sensor);
+        assert_eq!(imported.service_kind, HassServiceKind::Temperature);
+        assert_eq!(imported.sensor_value, Some(12.5));
+        assert!(imported.available);
+
+        let unknown = state(
+            "sensor.outside",
+            "unknown",
+            json!({"device_class": "temperature"}),
+        );
+        assert!(!parse_imported_entity(&unknown, None).unwrap().available);
+
+        let humidity = state("sensor.humidity", "40", json!({"device_class": "humidity"}));
+        assert!(parse_imported_entity(&humidity, None).is_none());
+    }
+
+    #[test]
+    fn light_archetype_from_hue_app() {
+        assert_eq!(
+            hass_light_archetype(&DeviceArchetype::CandleBulb),
+            Some(HassLightArchetype::CandleBulb)
+        );
+        assert_eq!(
+            hass_light_archetype(&DeviceArchetype::HueLightstrip),
+            Some(HassLightArchetype::HueLightstrip)
+        );
+        assert_eq!(hass_light_archetype(&DeviceArchetype::BridgeV2), None);
+    }
+
+    #[test]
+    fn device_name_prefers_alias() {
+        let desk = state("light.desk", "on", json!({"friendly_name": "Desk"}));
+        let mut imported = parse_imported_entity(&desk, None).unwrap();
+        assert_eq!(device_name(&imported), "Desk");
+
+        imported.device =
+            Some(serde_json::from_value(json!({"id": "abc", "name": "Desk lamp"})).unwrap());
+        assert_eq!(device_name(&imported), "Desk lamp");
+
+        imported.alias = Some("Reading lamp".to_string());
+        assert_eq!(device_name(&imported), "Reading lamp");
+    }
+
+    #[test]
+    fn battery_entities() {
+        let battery = json!({"device_class": "battery"});
+        assert!(is_battery(&state(
+            "sensor.remote_battery",
+            "87",
+            battery.clone()
+        )));
+        assert!(!is_battery(&state(
+            "sensor.remote_battery",
+            "unavailable",
+            battery.clone()
+        )));
+        assert!(!is_battery(&state(
+            "binary_sensor.remote_battery",
+            "87",
+            battery
+        )));
+        assert!(!is_battery(&state("sensor.remote_level", "87", json!({}))));
+    }
+}
@@ -1,8 +1,16 @@
 mod backend_event;
+mod button;
 mod client;
+mod color;
+mod command;
+mod effects;
+mod entertainment;
 mod import;
+mod registry;
+mod scenes;
+mod signaling;
 
-use std::collections::HashMap;
+use std::collections::{BTreeSet, HashMap};
 use std::sync::Arc;
 use std::time::Instant;
 
@@ -11,20 +19,26 @@ use svc::error::SvcError;
 use svc::template::ServiceTemplate;
 use svc::traits::{BoxDynService, Service};
 use thiserror::Error;
-use tokio::sync::{Mutex, broadcast::Receiver};
+use tokio::sync::Mutex;
+use tokio::sync::broadcast::{Receiver, error::RecvError};
 use tokio::time::{Duration, MissedTickBehavior, interval};
 use uuid::Uuid;
 
 use bifrost_api::backend::BackendRequest;
 use bifrost_api::config::HassServer;
-use hue::api::{RType, ResourceLink};
+use hue::api::{ColorGamut, MirekSchema, RType, ResourceLink};
 
 use crate::error::{ApiError, ApiResult};
 use crate::model::hass::{HassRoomConfig, HassRuntimeState, HassSwitchMode, HassUiState};
 use crate::resource::Resources;
 use crate::server::appstate::AppState;
 
-use self::client::{HassClient, HassWs};
+use self::button::HassRemoteButton;
+use self::client::{HassClient, HassState, HassWs, HassWsEvent};
+use self::effects::HassEffectMap;
+use self::entertainment::HassEntStream;
+use self::registry::HassRegistry;
+use self::signaling::HassSignal;
 
 #[derive(Error, Debug)]
 pub enum TemplateError {
@@ -37,6 +51,15 @@ pub(super) enum HassEntityKind {
     Light,
     Switch,
     BinarySensor,
+    Sensor,
+    Event,
+}
+
+impl HassEntityKind {
+    /// Entities whose Hue service type is selected by the sensor kind setting
+    pub(super) const fn is_sensor(self) -> bool {
+        matches!(self, Self::BinarySensor | Self::Sensor)
+    }
 }
 
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
@@ -45,13 +68,65 @@ pub(super) enum HassServiceKind {
     Switch,
     Motion,
     Contact,
+    Temperature,
+    LightLevel,
+    Button,
+    RelativeRotary,
+}
+
+/// The color representation a light is natively driven with
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub(super) enum HassColorMode {
+    #[default]
+    Xy,
+    Hs,
+    Rgb,
 }
 
+impl HassColorMode {
+    /// Colors a light driven in this mode can show. Home Assistant does not
+    /// report the gamut of a light, so lights taking xy get the Hue one.
+    pub const fn gamut(self) -> ColorGamut {
+        match self {
+            Self::Xy => ColorGamut::GAMUT_C,
+            Self::Hs | Self::Rgb => ColorGamut::SRGB,
+        }
+    }
+}
+
+#[allow(clippy::struct_excessive_bools)]
 #[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
 pub(super) struct HassLightCapabilities {
     pub supports_brightness: bool,
     pub supports_color: bool,
     pub supports_color_temp: bool,
+    /// The light takes the `flash` option of `light.turn_on`
+    pub supports_flash: bool,
+    pub color_mode: HassColorMode,
+    /// Color temperature range reported by Home Assistant, if any
+    pub mirek_schema: Option<MirekSchema>,
+}
+
+impl HassLightCapabilities {
+    pub fn mirek_schema(&self) -> MirekSchema {
+        self.mirek_schema.unwrap_or(MirekSchema::DEFAULT)
+    }
+
+    pub fn clamp_mirek(&self, mirek: u16) -> u16 {
+        let schema = self.mirek_schema();
+        let min = u16::try_from(schema.mirek_minimum).unwrap_or(u16::MIN);
+        let max = u16::try_from(schema.mirek_maximum).unwrap_or(u16::MAX);
+        mirek.clamp(min, max.max(min))
+    }
+}
+
+/// Mirek and Kelvin are reciprocal: `mirek = 1_000_000 / kelvin`
+pub(super) fn mirek_kelvin(value: f64) -> Option<u16> {
+    if value <= 0.0 || !value.is_finite() {
+        return None;
+    }
+    let converted = (1_000_000.0 / value).round();
+    format!("{converted:.0}").parse().ok()
 }
 
 #[derive(Clone, Debug)]
@@ -63,6 +138,7 @@ pub(super) struct HassEntityBinding {
     pub service_link: ResourceLink,
     pub device_link: ResourceLink,
     pub capabilities: HassLightCapabilities,
+    pub effects: HassEffectMap,
     pub switch_mode: Option<HassSwitchMode>,
 }
 
@@ -105,6 +181,33 @@ impl ServiceTemplate for HassServiceTemplate {
     }
 }
 
+/// The result of comparing a poll of all states with the previous one
+struct PolledStates {
+    snapshot: HashMap<String, HassState>,
+    /// Changed or new states, with their previous state
+    changed: Vec<(HassState, Option<HassState>)>,
+    removed: Vec<String>,
+}
+
+impl PolledStates {
+    fn diff(mut previous: HashMap<String, HassState>, states: Vec<HassState>) -> Self {
+        let mut snapshot = HashMap::with_capacity(states.len());
+        let mut changed = vec![];
+        for state in states {
+            let old = previous.remove(&state.entity_id);
+            if old.as_ref() != Some(&state) {
+                changed.push((state.clone(), old));
+            }
+            snapshot.insert(state.entity_id.clone(), state);
+        }
+        Self {
+            snapshot,
+            changed,
+            removed: previous.into_keys().collect(),
+        }
+    }
+}
+
 pub struct HassBackend {
     name: String,
     server: HassServer,
@@ -117,11 +220,32 @@ pub struct HassBackend {
     sensor_map: HashMap<Uuid, String>,
     device_map: HashMap<Uuid, String>,
     room_map: HashMap<String, HassRoomBinding>,
-    scene_map: HashMap<Uuid, String>,
+    registry: HassRegistry,
+    /// Buttons of remotes that only fire device triggers, by made up entity id
+    remote_buttons: HashMap<String, HassRemoteButton>,
+    /// Home Assistant light groups, and their members
+    light_groups: HashMap<String, BTreeSet<String>>,
     ws: Option<HassWs>,
+    /// When the websocket was lost, to resync and report the outage on reconnect
+    ws_lost_at: Option<Instant>,
+    poll_interval: Duration,
+    poll_only: bool,
+    /// Last polled states, to detect changes while the websocket is down
+    snapshot: Option<HashMap<String, HassState>>,
+    fps: u32,
+    entstream: Option<HassEntStream>,
+    /// Backend requests were skipped during the stream, so resync after it
+    resync_after_stream: bool,
+    /// Software blink sequences in progress, by entity
+    signals: HashMap<String, HassSignal>,
 }
 
 impl HassBackend {
+    // Every streamed frame is a separate service call per light, so keep
+    // the rate low enough for Home Assistant to keep up.
+    const DEFAULT_FPS: u32 = 10;
+    const DEFAULT_POLL_INTERVAL_SECS: u32 = 5;
+
     pub fn new(
         name: String,
         server: HassServer,
@@ -129,6 +253,13 @@ impl HassBackend {
         ui_state: Arc<Mutex<HassUiState>>,
         runtime_state: Arc<Mutex<HassRuntimeState>>,
     ) -> ApiResult<Self> {
+        let fps = server.streaming_fps.map_or(Self::DEFAULT_FPS, u32::from);
+        let poll_interval = Duration::from_secs(u64::from(
+            server
+                .poll_interval_secs
+                .map_or(Self::DEFAULT_POLL_INTERVAL_SECS, u32::from),
+        ));
+        let poll_only = server.poll_only.unwrap_or(false);
         Ok(Self {
             client: HassClient::new(&name, &server)?,
             name,
@@ -141,20 +272,37 @@ impl HassBackend {
             sensor_map: HashMap::new(),
             device_map: HashMap::new(),
             room_map: HashMap::new(),
-            scene_map: HashMap::new(),
+            registry: HassRegistry::default(),
+            remote_buttons: HashMap::new(),
+            light_groups: HashMap::new(),
+            signals: HashMap::new(),
             ws: None,
+            ws_lost_at: None,
+            poll_interval,
+            poll_only,
+            snapshot: None,
+            fps,
+            entstream: None,
+            resync_after_stream: false,
         })
     }
 
-    pub(super) fn room_links_for_id(&self, room_id: &str) -> (ResourceLink, ResourceLink) {
+    /// Rooms created in the Hue app keep the id they were created with
+    fn room_links(&self, room: &HassRoomConfig) -> (ResourceLink, ResourceLink) {
+        if let Some(rid) = room.hue_room_id {
+            return (
+                RType::Room.link_to(rid),
+                RType::GroupedLight.deterministic(rid),
+            );
+        }
         (
-            RType::Room.deterministic(format!("hass:{}:room:{}", self.name, room_id)),
-            RType::GroupedLight.deterministic(format!("hass:{}:grouped:{}", self.name, room_id)),
+            RType::Room.deterministic(format!("hass:{}:room:{}", self.name, room.id)),
+            RType::GroupedLight.deterministic(format!("hass:{}:grouped:{}", self.name, room.id)),
         )
     }
 
     fn room_binding(&self, room: &HassRoomConfig) -> HassRoomBinding {
-        let (room_link, grouped_light_link) = self.room_links_for_id(&room.id);
+        let (room_link, grouped_light_link) = self.room_links(room);
         HassRoomBinding {
             room_id: room.id.clone(),
             room_name: room.name.clone(),
@@ -238,7 +386,7 @@ impl HassBackend {
     }
 
     async fn ensure_ws_connected(&mut self) {
-        if self.ws.is_some() {
+        if self.ws.is_some() || self.poll_only {
             return;
         }
 
@@ -258,8 +406,17 @@ impl HassBackend {
         match self.client.subscribe_state_changed().await {
             Ok(ws) => {
                 self.ws = Some(ws);
+                self.snapshot = None;
                 self.ui_log("Realtime state sync connected (Home Assistant websocket)")
                     .await;
+                self.subscribe_remote_buttons().await;
+
+                // Catch up on everything that changed while we were not listening
+                if let Some(lost_at) = self.ws_lost_at.take() {
+                    let outage = lost_at.elapsed().as_secs();
+                    self.resync_and_log(&format!("Reconnected after {outage}s outage"))
+                        .await;
+                }
             }
             Err(err) => {
                 log::debug!("[{}] WS connect failed: {}", self.name, err);
@@ -267,6 +424,119 @@ impl HassBackend {
         }
     }
 
+    fn ws_lost(&mut self) {
+        self.ws = None;
+        self.ws_lost_at.get_or_insert_with(Instant::now);
+    }
+
+    /// Refresh every entity (and battery or update) exposed to Hue from the
+    /// current Home Assistant states, without replaying events.
+    async fn resync_bound_entities(&mut self) -> ApiResult<usize> {
+        self.apply_runtime_connection().await?;
+        let states = self.client.get_states().await?;
+
+        let mut count = 0;
+        for state in states {
+            if self.entity_map.contains_key(&state.entity_id)
+                || import::is_battery(&state)
+                || import::is_software_update(&state)
+            {
+                let entity_id = state.entity_id.clone();
+                match self.handle_state_update(state, None).await {
+                    Ok(()) => count += 1,
+                    Err(err) => log::warn!("[{}] Resync of {entity_id} failed: {err}", self.name),
+                }
+            }
+        }
+
+        Ok(count)
+    }
+
+    async fn resync_and_log(&mut self, reason: &str) {
+        match self.resync_bound_entities().await {
+            Ok(count) => {
+                self.ui_log(format!("{reason}, resynced {count} entities"))
+                    .await;
+            }
+            Err(err) => {
+                self.ui_log(format!("{reason}, resync failed: {err}")).await;
+            }
+        }
+    }
+
+    /// Fetch all states and feed the ones that changed since the last poll
+    /// through the same path as websocket events.
+    async fn poll_states(&mut self) {
+        if let Err(err) = self.apply_runtime_connection().await {
+            log::debug!("[{}] Poll skipped: {}", self.name, err);
+            return;
+        }
+
+        let states = match self.client.get_states().await {
+            Ok(states) => states,
+            Err(err) => {
+                log::debug!("[{}] Poll failed: {}", self.name, err);
+                return;
+            }
+        };
+
+        let Some(previous) = self.snapshot.take() else {
+            self.ui_log(format!(
+                "Polling Home Assistant states every {}s",
+                self.poll_interval.as_secs()
+            ))
+            .await;
+            self.snapshot = Some(
+                states
+                    .into_iter()
+                    .map(|state| (state.entity_id.clone(), state))
+                    .collect(),
+            );
+            return;
+        };
+
+        let polled = PolledStates::diff(previous, states);
+        for (state, old) in polled.changed {
+            let _ = self.handle_state_update(state, old.as_ref()).await;
+        }
+        for entity_id in &polled.removed {
+            let _ = self.handle_state_removed(entity_id).await;
+        }
+        self.snapshot = Some(polled.snapshot);
+    }
+
+    async fn handle_backend_recv(
+        &mut self,
+        req: Result<Arc<BackendRequest>, RecvError>,
+    ) -> ApiResult<()> {
+        match req {
+            Ok(req) => self.handle_backend_event(req).await,
+            // While streaming, skipped requests are most likely frames, which
+            // are outdated anyway. Anything else is caught up on once the
+            // stream stops.
+            Err(RecvError::Lagged(count)) if self.entstream.is_some() => {
+                log::debug!(
+                    "[{}] Fell behind while streaming, skipped {count} backend requests",
+                    self.name
+                );
+                self.resync_after_stream = true;
+                Ok(())
+            }
+            // Skipped requests can be anything, so bring the Hue state back
+            // in line with Home Assistant.
+            Err(RecvError::Lagged(count)) => {
+                log::warn!(
+                    "[{}] Fell behind, skipped {count} backend requests",
+                    self.name
+                );
+                self.resync_and_log(&format!("Skipped {count} backend requests"))
+                    .await;
+                Ok(())
+            }
+            Err(err) => Err(err.into()),
+        }
+    }
+
     async fn event_loop(&mut self, chan: &mut Receiver<Arc<BackendRequest>>) -> ApiResult<()> {
         if let Err(err) = self.run_sync("startup").await {
             log::error!(
@@ -279,6 +549,12 @@ impl HassBackend {
         let mut ws_tick = interval(Duration::from_secs(10));
         ws_tick.set_missed_tick_behavior(MissedTickBehavior::Skip);
 
+        // Only used while the websocket is down (or disabled). The first
+        // websocket attempt gets a head start.
+        let mut poll_tick = interval(self.poll_interval);
+        poll_tick.set_missed_tick_behavior(MissedTickBehavior::Skip);
+        poll_tick.reset();
+
         loop {
             if let Some(ws) = &mut self.ws {
                 tokio::select! {
@@ -286,27 +562,33 @@ impl HassBackend {
                         self.ensure_ws_connected().await;
                     }
                     req = chan.recv() => {
-                        let req = req?;
-                        self.handle_backend_event(req).await?;
+                        self.handle_backend_recv(req).await?;
                     }
-                    ev = ws.next_state_changed() => {
+                    ev = ws.next_event() => {
                         match ev {
-                            Ok(Some(ev)) => {
-                                // Keep fields "used" to avoid -D warnings while still being explicit
-                                // about which parts drive Hue state updates.
-                                let _entity_id = ev.entity_id;
-                                let _old_state = ev.old_state;
+                            Ok(Some(HassWsEvent::StateChanged(ev))) => {
                                 if let Some(new_state) = ev.new_state {
-                                    let _ = self.handle_state_update(new_state).await;
+                                    let _ = self
+                                        .handle_state_update(new_state, ev.old_state.as_ref())
+                                        .await;
+                                } else {
+                                    let _ = self.handle_state_removed(&ev.entity_id).await;
                                 }
                             }
+                            Ok(Some(HassWsEvent::Triggered(id))) => {
+                                let _ = self.handle_trigger(&id).await;
+                            }
+                            Ok(Some(HassWsEvent::Started)) => {
+                                // Entities are often unavailable while HA starts up
+                                self.resync_and_log("Home Assistant started").await;
+                            }
                             Ok(None) => {
                                 // websocket closed, reconnect later
-                                self.ws = None;
+                                self.ws_lost();
                             }
                             Err(err) => {
                                 log::debug!("[{}] WS error: {}", self.name, err);
-                                self.ws = None;
+                                self.ws_lost();
                             }
                         }
                     }
@@ -316,9 +598,11 @@ impl HassBackend {
                     _ = ws_tick.tick() => {
                         self.ensure_ws_connected().await;
                     }
+                    _ = poll_tick.tick() => {
+                        self.poll_states().await;
+                    }
                     req = chan.recv() => {
-                        let req = req?;
-                        self.handle_backend_event(req).await?;
+                        self.handle_backend_recv(req).await?;
                     }
                 }
             }
@@ -361,3 +645,80 @@ impl Service for HassBackend {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use hue::api::MirekSchema;
+    use serde_json::Map;
+
+    use super::client::HassState;
+    use super::{HassLightCapabilities, PolledStates, mirek_kelvin};
+
+    fn state(entity_id: &str, state: &str) -> HassState {
+        HassState {
+            entity_id: entity_id.to_string(),
+            state: state.to_string(),
+            attributes: Map::new(),
+        }
+    }
+
+    #[test]
+    fn mirek_kelvin_reciprocal() {
+        assert_eq!(mirek_kelvin(6500.0), Some(154));
+        assert_eq!(mirek_kelvin(2000.0), Some(500));
+        assert_eq!(mirek_kelvin(500.0), Some(2000));
+        assert_eq!(mirek_kelvin(0.0), None);
+        assert_eq!(mirek_kelvin(-1.0), None);
+        assert_eq!(mirek_kelvin(f64::NAN), None);
+        // Too small a temperature for a mirek value
+        assert_eq!(mirek_kelvin(1.0), None);
+    }
+
+    #[test]
+    fn clamp_mirek_to_schema() {
+        let caps = HassLightCapabilities {
+            mirek_schema: Some(MirekSchema {
+                mirek_minimum: 200,
+                mirek_maximum: 370,
+            }),
+            ..HassLightCapabilities::default()
+        };
+        assert_eq!(caps.clamp_mirek(153), 200);
+        assert_eq!(caps.clamp_mirek(250), 250);
+        assert_eq!(caps.clamp_mirek(500), 370);
+
+        let default = HassLightCapabilities::default();
+        assert_eq!(default.clamp_mirek(100), 153);
+        assert_eq!(default.clamp_mirek(600), 500);
+    }
+
+    #[test]
+    fn polled_states_diff() {
+        let previous = HashMap::from([
+            ("light.desk".to_string(), state("light.desk", "on")),
+            ("light.hall".to_string(), state("light.hall", "off")),
+            ("light.gone".to_string(), state("light.gone", "on")),
+        ]);
+        let polled = PolledStates::diff(
+            previous,
+            vec![
+                state("light.desk", "on"),
+                state("light.hall", "on"),
+                state("light.new", "off"),
+            ],
+        );
+
+        assert_eq!(
+            polled.changed,
+            vec![
+                (state("light.hall", "on"), Some(state("light.hall", "off"))),
+                (state("light.new", "off"), None),
+            ]
+        );
+        assert_eq!(polled.removed, vec!["light.gone".to_string()]);
+        assert_eq!(polled.snapshot.len(), 3);
+        assert_eq!(polled.snapshot["light.hall"], state("light.hall", "on"));
+    }
+}
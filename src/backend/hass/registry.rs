@@ -0,0 +1,221 @@
+use std::collections::HashMap;
+
+use serde::Deserialize;
+use serde_json::Value;
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct HassDeviceEntry {
+    pub id: String,
+    #[serde(default)]
+    pub name: Option<String>,
+    #[serde(default)]
+    pub name_by_user: Option<String>,
+    #[serde(default)]
+    pub manufacturer: Option<String>,
+    #[serde(default)]
+    pub model: Option<String>,
+    #[serde(default)]
+    pub model_id: Option<String>,
+    #[serde(default)]
+    pub sw_version: Option<String>,
+    #[serde(default)]
+    pub area_id: Option<String>,
+    /// `[integration, id]` pairs identifying the device
+    #[serde(default)]
+    pub identifiers: Vec<Vec<Value>>,
+}
+
+impl HassDeviceEntry {
+    /// The name shown in Home Assistant, preferring the one set by the user
+    #[must_use]
+    pub fn display_name(&self) -> Option<&str> {
+        [&self.name_by_user, &self.name]
+            .into_iter()
+            .flatten()
+            .map(|x| x.trim())
+            .find(|x| !x.is_empty())
+    }
+
+    /// Whether the device is provided by the given integration (like `zha`)
+    #[must_use]
+    pub fn is_from(&self, integration: &str) -> bool {
+        self.identifiers
+            .iter()
+            .any(|ident| ident.first().and_then(Value::as_str) == Some(integration))
+    }
+}
+
+#[allow(clippy::struct_field_names)]
+#[derive(Clone, Debug, Deserialize)]
+pub struct HassEntityEntry {
+    pub entity_id: String,
+    #[serde(default)]
+    pub device_id: Option<String>,
+    #[serde(default)]
+    pub area_id: Option<String>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct HassAreaEntry {
+    pub area_id: String,
+    pub name: String,
+}
+
+/// Snapshot of the Home Assistant device, entity and area registries
+#[derive(Clone, Debug, Default)]
+pub struct HassRegistry {
+    devices: HashMap<String, HassDeviceEntry>,
+    entities: HashMap<String, HassEntityEntry>,
+    areas: HashMap<String, HassAreaEntry>,
+}
+
+impl HassRegistry {
+    #[must_use]
+    pub fn new(
+        devices: Vec<HassDeviceEntry>,
+        entities: Vec<HassEntityEntry>,
+        areas: Vec<HassAreaEntry>,
+    ) -> Self {
+        Self {
+            devices: devices.into_iter().map(|x| (x.id.clone(), x)).collect(),
+            entities: entities
+                .into_iter()
+                .map(|x| (x.entity_id.clone(), x))
+                .collect(),
+            areas: areas.into_iter().map(|x| (x.area_id.clone(), x)).collect(),
+        }
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.entities.is_empty()
+    }
+
+    /// Devices provided by the given integration, that have no entity of
+    /// the given domain
+    pub fn devices_without(
+        &self,
+        integration: &str,
+        domain: &str,
+    ) -> impl Iterator<Item = &HassDeviceEntry> {
+        let prefix = format!("{domain}.");
+        self.devices.values().filter(move |device| {
+            device.is_from(integration)
+                && !self.entities.values().any(|entity| {
+                    entity.device_id.as_ref() == Some(&device.id)
+                        && entity.entity_id.starts_with(&prefix)
+                })
+        })
+    }
+
+    /// Register an entity made up by Bifrost, so it is grouped (and placed
+    /// in an area) like the entities of its device
+    pub fn add_entity(&mut self, entity_id: &str, device_id: &str) {
+        self.entities.insert(
+            entity_id.to_string(),
+            HassEntityEntry {
+                entity_id: entity_id.to_string(),
+                device_id: Some(device_id.to_string()),
+                area_id: None,
+            },
+        );
+    }
+
+    /// The physical device an entity belongs to, if it is registered with one
+    #[must_use]
+    pub fn device(&self, entity_id: &str) -> Option<&HassDeviceEntry> {
+        let device_id = self.entities.get(entity_id)?.device_id.as_ref()?;
+        self.devices.get(device_id)
+    }
+
+    /// Area of an entity. Like Home Assistant itself, an area set on the
+    /// entity wins over the area of its device.
+    #[must_use]
+    pub fn area_name(&self, entity_id: &str) -> Option<String> {
+        let entity = self.entities.get(entity_id)?;
+        let area_id = entity.area_id.as_ref().or_else(|| {
+            let device_id = entity.device_id.as_ref()?;
+            self.devices.get(device_id)?.area_id.as_ref()
+        })?;
+        self.areas.get(area_id).map(|area| area.name.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::HassRegistry;
+
+    fn registry() -> HassRegistry {
+        HassRegistry::new(
+            serde_json::from_value(json!([
+                {
+                    "id": "dev1",
+                    "name": "Hue bulb",
+                    "name_by_user": " ",
+                    "area_id": "kitchen",
+                    "identifiers": [["hue", "abc"]],
+                },
+                {"id": "dev2", "name": "Dimmer", "name_by_user": "Hall remote", "identifiers": [["zha", "00:11"]]},
+            ]))
+            .unwrap(),
+            serde_json::from_value(json!([
+                {"entity_id": "light.bulb", "device_id": "dev1"},
+                {"entity_id": "sensor.bulb_power", "device_id": "dev1", "area_id": "hall"},
+                {"entity_id": "sensor.dimmer_battery", "device_id": "dev2"},
+                {"entity_id": "light.loose"},
+            ]))
+            .unwrap(),
+            serde_json::from_value(json!([
+                {"area_id": "kitchen", "name": "Kitchen"},
+                {"area_id": "hall", "name": "Hall"},
+            ]))
+            .unwrap(),
+        )
+    }
+
+    #[test]
+    fn entities_belong_to_devices() {
+        let registry = registry();
+        let device = registry.device("light.bulb").unwrap();
+        assert_eq!(device.id, "dev1");
+        assert_eq!(device.display_name(), Some("Hue bulb"));
+        assert_eq!(
+            registry
+                .device("sensor.dimmer_battery")
+                .unwrap()
+                .display_name(),
+            Some("Hall remote")
+        );
+        assert!(registry.device("light.loose").is_none());
+        assert!(registry.device("light.unknown").is_none());
+    }
+
+    #[test]
+    fn entity_area_wins_over_device_area() {
+        let registry = registry();
+        assert_eq!(registry.area_name("light.bulb").as_deref(), Some("Kitchen"));
+        assert_eq!(
+            registry.area_name("sensor.bulb_power").as_deref(),
+            Some("Hall")
+        );
+        assert_eq!(registry.area_name("light.loose"), None);
+    }
+
+    #[test]
+    fn made_up_entities_join_their_device() {
+        let mut registry = registry();
+        let remotes = registry
+            .devices_without("zha", "event")
+            .map(|device| device.id.clone())
+            .collect::<Vec<_>>();
+        assert_eq!(remotes, ["dev2"]);
+
+        registry.add_entity("device_trigger.dev1_on", "dev1");
+        assert_eq!(
+            registry.area_name("device_trigger.dev1_on").as_deref(),
+            Some("Kitchen")
+        );
+    }
+}
@@ -0,0 +1,586 @@
+use std::collections::HashSet;
+
+use serde_json::{Map, Value, json};
+
+use hue::api::{
+    ColorTemperatureUpdate, ColorUpdate, DimmingUpdate, On, RType, Resource, ResourceLink, Room,
+    Scene, SceneAction, SceneActionElement, SceneActive, SceneMetadata, SceneRecall, SceneStatus,
+};
+use uuid::Uuid;
+
+use crate::backend::hass::client::HassState;
+use crate::backend::hass::import::{parse_xy_color, value_to_f64};
+use crate::backend::hass::{HassBackend, HassEntityKind, mirek_kelvin};
+use crate::error::{ApiError, ApiResult};
+use crate::model::hass::HassSwitchMode;
+use crate::model::state::AuxData;
+use crate::resource::Resources;
+
+/// Scenes created by Bifrost when writing Hue scenes back to Home Assistant
+const WRITEBACK_PREFIX: &str = "scene.bifrost_";
+
+#[must_use]
+pub fn is_scene(entity_id: &str) -> bool {
+    entity_id.starts_with("scene.")
+}
+
+/// A Home Assistant scene, with the stored state of each member (if known)
+#[derive(Clone, Debug)]
+pub struct HassScene {
+    pub entity_id: String,
+    pub name: String,
+    pub members: Vec<(String, Option<Map<String, Value>>)>,
+}
+
+fn scene_members(state: &HassState) -> Vec<String> {
+    state
+        .attributes
+        .get("entity_id")
+        .and_then(Value::as_array)
+        .map(|arr| {
+            arr.iter()
+                .filter_map(Value::as_str)
+                .map(ToString::to_string)
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Hue scene action for the state Home Assistant stored for a scene member.
+///
+/// Scenes without a stored configuration only tell us which entities they
+/// contain, so those members are simply turned on.
+fn scene_action(stored: Option<&Map<String, Value>>) -> SceneAction {
+    let mut action = SceneAction {
+        color: None,
+        color_temperature: None,
+        dimming: None,
+        on: Some(On { on: true }),
+        gradient: None,
+        effects: Value::Null,
+    };
+
+    let Some(stored) = stored else {
+        return action;
+    };
+
+    if stored.get("state").and_then(Value::as_str) == Some("off") {
+        action.on = Some(On { on: false });
+        return action;
+    }
+
+    if let Some(bri) = stored.get("brightness").and_then(value_to_f64) {
+        action.dimming = Some(DimmingUpdate::new((bri / 255.0 * 100.0).clamp(0.0, 100.0)));
+    }
+
+    let mirek = stored
+        .get("color_temp_kelvin")
+        .and_then(value_to_f64)
+        .and_then(mirek_kelvin)
+        .or_else(|| {
+            let mireds = stored.get("color_temp").and_then(value_to_f64)?;
+            format!("{:.0}", mireds.round()).parse().ok()
+        });
+
+    match stored.get("color_mode").and_then(Value::as_str) {
+        Some("color_temp") => {
+            action.color_temperature = mirek.map(ColorTemperatureUpdate::new);
+        }
+        _ => {
+            if let Some(xy) = stored.get("xy_color").and_then(parse_xy_color) {
+                action.color = Some(ColorUpdate::new(xy));
+            } else {
+                action.color_temperature = mirek.map(ColorTemperatureUpdate::new);
+            }
+        }
+    }
+
+    action
+}
+
+/// Home Assistant entity state for a Hue scene action, as taken by `scene.create`
+fn hass_entity_state(action: &SceneAction) -> Value {
+    if action.on.is_some_and(|on| !on.on) {
+        return json!({ "state": "off" });
+    }
+
+    let mut state = Map::new();
+    state.insert("state".to_string(), json!("on"));
+    if let Some(dim) = action.dimming {
+        state.insert(
+            "brightness".to_string(),
+            json!((dim.brightness * 255.0 / 100.0).round().clamp(0.0, 255.0)),
+        );
+    }
+    if let Some(xy) = action.color.map(|color| color.xy) {
+        state.insert("xy_color".to_string(), json!([xy.x, xy.y]));
+    } else if let Some(kelvin) = action
+        .color_temperature
+        .and_then(|ct| ct.mirek)
+        .and_then(|mirek| mirek_kelvin(f64::from(mirek)))
+    {
+        state.insert("color_temp_kelvin".to_string(), json!(kelvin));
+    }
+
+    Value::Object(state)
+}
+
+impl HassBackend {
+    pub(super) fn scene_link(&self, entity_id: &str) -> ResourceLink {
+        RType::Scene.deterministic(format!("hass:{}:scene:{}", self.name, entity_id))
+    }
+
+    /// Read a scene and the stored state of its members.
+    ///
+    /// Returns `None` for scenes that cannot be shown in Hue: scenes Bifrost
+    /// wrote back itself, and scenes with members not exposed to Hue.
+    pub(super) async fn fetch_scene(&self, state: &HassState) -> Option<HassScene> {
+        if !is_scene(&state.entity_id) || state.entity_id.starts_with(WRITEBACK_PREFIX) {
+            return None;
+        }
+
+        let members = scene_members(state);
+        if members.is_empty()
+            || !members
+                .iter()
+                .all(|member| self.entity_map.contains_key(member))
+        {
+            return None;
+        }
+
+        // Only scenes made in the Home Assistant UI have a stored config
+        let mut stored = match state.attributes.get("id").and_then(Value::as_str) {
+            Some(id) => match self.client.get_scene_config(id).await {
+                Ok(config) => config
+                    .and_then(|mut config| match config.get_mut("entities")?.take() {
+                        Value::Object(entities) => Some(entities),
+                        _ => None,
+                    })
+                    .unwrap_or_default(),
+                Err(err) => {
+                    log::debug!(
+                        "[{}] Failed to read scene config of {}: {}",
+                        self.name,
+                        state.entity_id,
+                        err
+                    );
+                    Map::new()
+                }
+            },
+            None => Map::new(),
+        };
+
+        let name = state
+            .attributes
+            .get("friendly_name")
+            .and_then(Value::as_str)
+            .map_or_else(|| state.entity_id.clone(), ToString::to_string);
+
+        let members = members
+            .into_iter()
+            .map(|member| {
+                let stored = match stored.remove(&member) {
+                    Some(Value::Object(map)) => Some(map),
+                    _ => None,
+                };
+                (member, stored)
+            })
+            .collect();
+
+        Some(HassScene {
+            entity_id: state.entity_id.clone(),
+            name,
+            members,
+        })
+    }
+
+    /// Room holding every member of a scene, if they share one
+    fn scene_room(&self, res: &Resources, scene: &HassScene) -> Option<ResourceLink> {
+        let mut room_link = None;
+        for (member, _) in &scene.members {
+            let binding = self.entity_map.get(member)?;
+            let room = self.room_map.values().find(|room| {
+                res.get::<Room>(&room.room_link)
+                    .is_ok_and(|hue_room| hue_room.children.contains(&binding.device_link))
+            })?;
+            if room_link.is_some_and(|link| link != room.room_link) {
+                return None;
+            }
+            room_link = Some(room.room_link);
+        }
+        room_link
+    }
+
+    /// Add or update the Hue scene for a Home Assistant scene. Returns the
+    /// scene id, or `None` if the scene does not fit into a single room.
+    pub(super) fn sync_scene(
+        &self,
+        res: &mut Resources,
+        scene: &HassScene,
+    ) -> ApiResult<Option<Uuid>> {
+        let link = self.scene_link(&scene.entity_id);
+
+        let Some(room_link) = self.scene_room(res, scene) else {
+            self.remove_scene(res, &scene.entity_id)?;
+            return Ok(None);
+        };
+
+        let actions = scene
+            .members
+            .iter()
+            .filter_map(|(member, stored)| {
+                let binding = self.entity_map.get(member)?;
+                let is_light = match binding.kind {
+                    HassEntityKind::Light => true,
+                    HassEntityKind::Switch => {
+                        binding.switch_mode.unwrap_or(HassSwitchMode::Plug) == HassSwitchMode::Light
+                    }
+                    HassEntityKind::BinarySensor
+                    | HassEntityKind::Sensor
+                    | HassEntityKind::Event => false,
+                };
+                is_light.then(|| SceneActionElement {
+                    action: scene_action(stored.as_ref()),
+                    target: binding.service_link,
+                })
+            })
+            .collect::<Vec<_>>();
+
+        if let Ok(existing) = res.get::<Scene>(&link) {
+            if existing.group == room_link {
+                res.update::<Scene>(&link.rid, |hue_scene| {
+                    hue_scene.actions = actions;
+                    hue_scene.metadata.name.clone_from(&scene.name);
+                })?;
+                return Ok(Some(link.rid));
+            }
+            // Moved to another room, so it needs a new index there
+            res.delete(&link)?;
+        }
+
+        let index = res.get_next_scene_id(&room_link)?;
+        res.aux_set(
+            &link,
+            AuxData::new()
+                .with_topic(&scene.entity_id)
+                .with_index(index),
+        );
+        res.add(
+            &link,
+            Resource::Scene(Scene {
+                actions,
+                auto_dynamic: false,
+                group: room_link,
+                metadata: SceneMetadata {
+                    appdata: None,
+                    image: None,
+                    name: scene.name.clone(),
+                },
+                palette: json!({
+                    "color": [],
+                    "dimming": [],
+                    "color_temperature": [],
+                    "effects": [],
+                }),
+                speed: 0.5,
+                status: Some(SceneStatus {
+                    active: SceneActive::Inactive,
+                    last_recall: None,
+                }),
+                recall: SceneRecall::default(),
+            }),
+        )?;
+
+        Ok(Some(link.rid))
+    }
+
+    pub(super) fn remove_scene(&self, res: &mut Resources, entity_id: &str) -> ApiResult<()> {
+        let link = self.scene_link(entity_id);
+        if res.get::<Scene>(&link).is_ok() {
+            res.delete(&link)?;
+        }
+        Ok(())
+    }
+
+    /// Replace all imported scenes. Scenes imported earlier (possibly before a
+    /// restart) that are not in `scenes` anymore are deleted.
+    pub(super) fn sync_scenes(
+        &self,
+        res: &mut Resources,
+        scenes: &[HassScene],
+    ) -> ApiResult<usize> {
+        let mut keep = HashSet::new();
+        for scene in scenes {
+            if let Some(rid) = self.sync_scene(res, scene)? {
+                keep.insert(rid);
+            }
+        }
+
+        let imported = res
+            .get_resource_ids_by_type(RType::Scene)
+            .into_iter()
+            .filter(|rid| !keep.contains(rid))
+            .filter_map(|rid| {
+                let topic = res
+                    .aux_get(&RType::Scene.link_to(rid))
+                    .ok()?
+                    .topic
+                    .clone()?;
+                (is_scene(&topic) && self.scene_link(&topic).rid == rid).then_some(topic)
+            })
+            .collect::<Vec<_>>();
+        for entity_id in imported {
+            self.remove_scene(res, &entity_id)?;
+        }
+
+        Ok(keep.len())
+    }
+
+    fn owns_scene(&self, res: &Resources, link: &ResourceLink) -> bool {
+        res.get::<Scene>(link).is_ok_and(|scene| {
+            self.room_map
+                .values()
+                .any(|room| room.room_link == scene.group)
+        })
+    }
+
+    /// The Home Assistant scene behind a Hue scene in one of our rooms. The
+    /// mapping is kept in the (persisted) aux data of the Hue scene.
+    pub(super) fn hass_scene_for(&self, res: &Resources, link: &ResourceLink) -> Option<String> {
+        if !self.owns_scene(res, link) {
+            return None;
+        }
+        let topic = res.aux_get(link).ok()?.topic.clone()?;
+        is_scene(&topic).then_some(topic)
+    }
+
+    /// Id of the stored config of a scene made in the Home Assistant UI
+    async fn scene_config_id(&self, entity_id: &str) -> Option<String> {
+        let state = self.client.get_state(entity_id).await.ok()?;
+        let id = state.attributes.get("id")?.as_str()?;
+        Some(id.to_string())
+    }
+
+    async fn delete_hass_scene(&self, entity_id: &str) -> ApiResult<()> {
+        if let Some(id) = self.scene_config_id(entity_id).await {
+            self.client.delete_scene_config(&id).await
+        } else if entity_id.starts_with(WRITEBACK_PREFIX) {
+            self.client.delete_scene(entity_id).await
+        } else {
+            Err(ApiError::service_error(format!(
+                "{entity_id} is not editable in Home Assistant"
+            )))
+        }
+    }
+
+    async fn rename_hass_scene(
+        &self,
+        entity_id: &str,
+        name: &str,
+        actions: &[SceneActionElement],
+    ) -> ApiResult<()> {
+        if let Some(id) = self.scene_config_id(entity_id).await {
+            let Some(mut config) = self.client.get_scene_config(&id).await? else {
+                return Err(ApiError::service_error(format!(
+                    "Scene config {id} not found in Home Assistant"
+                )));
+            };
+            config["name"] = json!(name);
+            return self.client.set_scene_config(&id, &config).await;
+        }
+
+        if entity_id.starts_with(WRITEBACK_PREFIX) {
+            // Scenes from `scene.create` can only be replaced as a whole
+            let entities = actions
+                .iter()
+                .filter_map(|act| {
+                    let entity_id = self.light_map.get(&act.target.rid)?;
+                    Some((entity_id.clone(), hass_entity_state(&act.action)))
+                })
+                .collect();
+            let scene_id = entity_id.trim_start_matches("scene.");
+            return self.client.create_scene(scene_id, name, entities).await;
+        }
+
+        Err(ApiError::service_error(format!(
+            "{entity_id} is not editable in Home Assistant"
+        )))
+    }
+
+    /// Delete a Hue scene of ours, along with the Home Assistant scene behind it
+    pub(super) async fn backend_scene_delete(&self, link: &ResourceLink) -> ApiResult<()> {
+        let entity_id = {
+            let res = self.state.lock().await;
+            if !self.owns_scene(&res, link) {
+                return Ok(());
+            }
+            self.hass_scene_for(&res, link)
+        };
+
+        if let Some(entity_id) = entity_id {
+            if let Err(err) = self.delete_hass_scene(&entity_id).await {
+                self.ui_log(format!(
+                    "Failed to delete {entity_id} from Home Assistant: {err}"
+                ))
+                .await;
+            }
+        }
+
+        self.state.lock().await.delete(link)
+    }
+
+    /// Propagate a scene renamed in the Hue app to Home Assistant
+    pub(super) async fn backend_scene_rename(
+        &self,
+        link: &ResourceLink,
+        name: &str,
+    ) -> ApiResult<()> {
+        let (entity_id, actions) = {
+            let res = self.state.lock().await;
+            let Some(entity_id) = self.hass_scene_for(&res, link) else {
+                return Ok(());
+            };
+            (entity_id, res.get::<Scene>(link)?.actions.clone())
+        };
+
+        if let Err(err) = self.rename_hass_scene(&entity_id, name, &actions).await {
+            self.ui_log(format!(
+                "Failed to rename {entity_id} in Home Assistant: {err}"
+            ))
+            .await;
+        }
+
+        Ok(())
+    }
+
+    /// Realtime update of a single Home Assistant scene
+    pub(super) async fn handle_scene_update(
+        &self,
+        state: &HassState,
+        old_state: Option<&HassState>,
+    ) -> ApiResult<()> {
+        // The state of a scene is the time it was last activated, which
+        // changes nothing about the scene itself
+        if old_state.is_some_and(|old| old.attributes == state.attributes) {
+            return Ok(());
+        }
+
+        let scene = self.fetch_scene(state).await;
+
+        let mut res = self.state.lock().await;
+        match scene {
+            Some(scene) => {
+                self.sync_scene(&mut res, &scene)?;
+            }
+            None => self.remove_scene(&mut res, &state.entity_id)?,
+        }
+        drop(res);
+
+        Ok(())
+    }
+
+    /// An entity disappeared from Home Assistant
+    pub(super) async fn handle_state_removed(&self, entity_id: &str) -> ApiResult<()> {
+        if !is_scene(entity_id) {
+            return Ok(());
+        }
+
+        let mut res = self.state.lock().await;
+        self.remove_scene(&mut res, entity_id)
+    }
+
+    /// Import all scenes found in a full list of states
+    pub(super) async fn import_scenes(&self, states: &[HassState]) -> ApiResult<usize> {
+        let mut scenes = vec![];
+        for state in states {
+            if let Some(scene) = self.fetch_scene(state).await {
+                scenes.push(scene);
+            }
+        }
+
+        let mut res = self.state.lock().await;
+        self.sync_scenes(&mut res, &scenes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{Map, Value, json};
+
+    use crate::backend::hass::client::HassState;
+
+    use super::{hass_entity_state, is_scene, scene_action, scene_members};
+
+    fn action(stored: Option<&Value>) -> Value {
+        let stored = stored.and_then(Value::as_object);
+        serde_json::to_value(scene_action(stored)).unwrap()
+    }
+
+    #[test]
+    fn scene_members_from_attributes() {
+        let state = HassState {
+            entity_id: "scene.evening".to_string(),
+            state: "scening".to_string(),
+            attributes: json!({"entity_id": ["light.a", "switch.b", 3]})
+                .as_object()
+                .cloned()
+                .unwrap_or_else(Map::new),
+        };
+        assert!(is_scene(&state.entity_id));
+        assert!(!is_scene("light.a"));
+        assert_eq!(scene_members(&state), ["light.a", "switch.b"]);
+    }
+
+    #[test]
+    fn scene_actions_from_stored_state() {
+        assert_eq!(action(None), json!({"on": {"on": true}}));
+        assert_eq!(
+            action(Some(&json!({"state": "off", "brightness": 255}))),
+            json!({"on": {"on": false}})
+        );
+        assert_eq!(
+            action(Some(&json!({
+                "state": "on",
+                "brightness": 255,
+                "color_mode": "color_temp",
+                "color_temp_kelvin": 2500,
+                "xy_color": [0.5, 0.4],
+            }))),
+            json!({
+                "on": {"on": true},
+                "dimming": {"brightness": 100.0},
+                "color_temperature": {"mirek": 400},
+            })
+        );
+        assert_eq!(
+            action(Some(
+                &json!({"state": "on", "color_mode": "xy", "xy_color": [0.5, 0.4]})
+            )),
+            json!({"on": {"on": true}, "color": {"xy": {"x": 0.5, "y": 0.4}}})
+        );
+        // Mireds, for scenes stored by older Home Assistant versions
+        assert_eq!(
+            action(Some(&json!({"state": "on", "color_temp": 300.4}))),
+            json!({"on": {"on": true}, "color_temperature": {"mirek": 300}})
+        );
+    }
+
+    #[test]
+    fn scene_actions_written_back() {
+        let write_back = |stored: Value| hass_entity_state(&scene_action(stored.as_object()));
+
+        assert_eq!(write_back(json!({"state": "off"})), json!({"state": "off"}));
+        assert_eq!(
+            write_back(
+                json!({"state": "on", "brightness": 128, "color_mode": "xy", "xy_color": [0.5, 0.4]})
+            ),
+            json!({"state": "on", "brightness": 128.0, "xy_color": [0.5, 0.4]})
+        );
+        assert_eq!(
+            write_back(
+                json!({"state": "on", "color_mode": "color_temp", "color_temp_kelvin": 2500})
+            ),
+            json!({"state": "on", "color_temp_kelvin": 2500})
+        );
+    }
+}
@@ -0,0 +1,307 @@
+use std::time::Duration;
+
+use serde_json::{Map, json};
+use tokio::sync::oneshot;
+use tokio::task::JoinHandle;
+use tokio::time::Instant;
+
+use hue::api::{ColorUpdate, LightSignal, LightSignalingUpdate, LightUpdate};
+
+use crate::backend::hass::client::HassClient;
+use crate::backend::hass::color;
+use crate::backend::hass::command::HassServiceCall;
+use crate::backend::hass::entertainment::HassEntStream;
+use crate::backend::hass::{HassBackend, HassEntityBinding, HassEntityKind};
+use crate::error::ApiResult;
+
+/// How long a light stays in each state of a software blink
+const BLINK_STEP: Duration = Duration::from_millis(500);
+
+/// Software identify: a few blinks, like a Hue bulb
+const IDENTIFY_DURATION: Duration = Duration::from_secs(4);
+
+/// Software alert: a single breathe cycle, approximated by blinking
+const ALERT_DURATION: Duration = Duration::from_secs(2);
+
+/// Longest signal the Hue API accepts
+const SIGNAL_MAX_DURATION: Duration = Duration::from_secs(65_534);
+
+/// A software signal running on one entity
+pub struct HassSignal {
+    stop: oneshot::Sender<()>,
+    task: JoinHandle<()>,
+}
+
+/// Hue signals run in steps of whole seconds
+fn signal_duration(duration_ms: u32) -> Duration {
+    Duration::from_secs(u64::from(duration_ms.saturating_add(500) / 1000)).min(SIGNAL_MAX_DURATION)
+}
+
+/// One cycle of service calls that make up a signal. Colors are only used
+/// on lights that support them; otherwise the light blinks on and off.
+fn signal_steps(
+    binding: &HassEntityBinding,
+    signal: LightSignal,
+    colors: &[ColorUpdate],
+) -> Vec<HassServiceCall> {
+    let domain = match binding.kind {
+        HassEntityKind::Light => "light",
+        HassEntityKind::Switch => "switch",
+        HassEntityKind::BinarySensor | HassEntityKind::Sensor | HassEntityKind::Event => {
+            return Vec::new();
+        }
+    };
+
+    let caps = &binding.capabilities;
+    let on = |color: Option<&ColorUpdate>| {
+        let mut data = Map::new();
+        if binding.kind == HassEntityKind::Light && caps.supports_brightness {
+            data.insert("brightness".to_string(), json!(255));
+        }
+        if let Some(color) = color.filter(|_| caps.supports_color) {
+            let (key, value) = color::color_service_data(caps.color_mode, color.xy);
+            data.insert(key.to_string(), value);
+        }
+        HassServiceCall::new(domain, "turn_on", data)
+    };
+    let off = HassServiceCall::new(domain, "turn_off", Map::new());
+
+    match (signal, colors) {
+        (LightSignal::Alternating, [first, second, ..]) if caps.supports_color => {
+            vec![on(Some(first)), on(Some(second))]
+        }
+        (LightSignal::OnOffColor | LightSignal::Alternating, [color, ..]) => {
+            vec![on(Some(color)), off]
+        }
+        _ => vec![on(None), off],
+    }
+}
+
+/// Cycle through the steps until the duration is over or the signal is
+/// stopped, then bring the entity back to the state it had before.
+async fn run_signal(
+    client: HassClient,
+    name: String,
+    entity_id: String,
+    steps: Vec<HassServiceCall>,
+    duration: Duration,
+    restore: HassServiceCall,
+    stopped: oneshot::Receiver<()>,
+) {
+    let blink = async {
+        let deadline = Instant::now() + duration;
+        for step in steps.iter().cycle() {
+            let now = Instant::now();
+            if now >= deadline {
+                break;
+            }
+            if let Err(err) = client
+                .call_service(step.domain, step.service, &entity_id, step.data.clone())
+                .await
+            {
+                log::warn!("[{name}] Signal step for {entity_id} failed: {err}");
+                break;
+            }
+            tokio::time::sleep_until((now + BLINK_STEP).min(deadline)).await;
+        }
+    };
+
+    tokio::select! {
+        () = blink => {}
+        _ = stopped => {}
+    }
+
+    if let Err(err) = client
+        .call_service(restore.domain, restore.service, &entity_id, restore.data)
+        .await
+    {
+        log::warn!("[{name}] Failed to restore {entity_id} after signal: {err}");
+    }
+}
+
+impl HassBackend {
+    /// Act on the identify, alert and signaling parts of a light update.
+    ///
+    /// Identify and alert use the Home Assistant `flash` option where the
+    /// light supports it. Everything else is a software blink, after which
+    /// the light returns to its previous state.
+    pub(super) async fn light_signal(
+        &mut self,
+        binding: &HassEntityBinding,
+        upd: &LightUpdate,
+    ) -> ApiResult<()> {
+        if let Some(signaling) = &upd.signaling {
+            return self.signaling_update(binding, signaling).await;
+        }
+
+        let (flash, duration) = if upd.identify.is_some() {
+            ("short", IDENTIFY_DURATION)
+        } else if upd.alert.is_some() {
+            ("long", ALERT_DURATION)
+        } else {
+            return Ok(());
+        };
+
+        if binding.kind == HassEntityKind::Light && binding.capabilities.supports_flash {
+            self.stop_signal(&binding.entity_id).await;
+            let mut data = Map::new();
+            data.insert("flash".to_string(), json!(flash));
+            return self
+                .call_service("light", "turn_on", &binding.entity_id, data)
+                .await;
+        }
+
+        let steps = signal_steps(binding, LightSignal::OnOff, &[]);
+        self.start_signal(binding, steps, duration).await
+    }
+
+    async fn signaling_update(
+        &mut self,
+        binding: &HassEntityBinding,
+        signaling: &LightSignalingUpdate,
+    ) -> ApiResult<()> {
+        let duration = signal_duration(signaling.duration);
+        if signaling.signal == LightSignal::NoSignal || duration.is_zero() {
+            self.stop_signal(&binding.entity_id).await;
+            return Ok(());
+        }
+
+        let steps = signal_steps(binding, signaling.signal, &signaling.colors);
+        self.start_signal(binding, steps, duration).await
+    }
+
+    async fn start_signal(
+        &mut self,
+        binding: &HassEntityBinding,
+        steps: Vec<HassServiceCall>,
+        duration: Duration,
+    ) -> ApiResult<()> {
+        let Some(domain) = steps.first().map(|step| step.domain) else {
+            return Ok(());
+        };
+
+        // A running signal restores the light first, so the state captured
+        // here is the one from before any signal.
+        self.stop_signal(&binding.entity_id).await;
+        self.signals.retain(|_, signal| !signal.task.is_finished());
+
+        let state = self.client.get_state(&binding.entity_id).await?;
+        let (service, data) = HassEntStream::restore_from_state(&state);
+        let restore = HassServiceCall::new(domain, service, data);
+
+        log::debug!(
+            "[{}] Signaling on {} for {}s",
+            self.name,
+            binding.entity_id,
+            duration.as_secs()
+        );
+
+        let (stop, stopped) = oneshot::channel();
+        let task = tokio::spawn(run_signal(
+            self.client.clone(),
+            self.name.clone(),
+            binding.entity_id.clone(),
+            steps,
+            duration,
+            restore,
+            stopped,
+        ));
+        self.signals
+            .insert(binding.entity_id.clone(), HassSignal { stop, task });
+
+        Ok(())
+    }
+
+    /// Stop a running signal, and wait until the entity has been restored
+    pub(super) async fn stop_signal(&mut self, entity_id: &str) {
+        if let Some(signal) = self.signals.remove(entity_id) {
+            let _ = signal.stop.send(());
+            let _ = signal.task.await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use hue::api::{ColorUpdate, LightSignal, RType};
+    use hue::xy::XY;
+
+    use crate::backend::hass::effects::HassEffectMap;
+    use crate::backend::hass::{
+        HassColorMode, HassEntityBinding, HassEntityKind, HassLightCapabilities, HassServiceKind,
+    };
+
+    use super::{signal_duration, signal_steps};
+
+    fn binding(kind: HassEntityKind, supports_color: bool) -> HassEntityBinding {
+        HassEntityBinding {
+            entity_id: "light.a".to_string(),
+            name: "A".to_string(),
+            kind,
+            service_kind: HassServiceKind::Light,
+            service_link: RType::Light.deterministic("light.a"),
+            device_link: RType::Device.deterministic("light.a"),
+            capabilities: HassLightCapabilities {
+                supports_brightness: true,
+                supports_color,
+                color_mode: HassColorMode::Xy,
+                ..HassLightCapabilities::default()
+            },
+            effects: HassEffectMap::default(),
+            switch_mode: None,
+        }
+    }
+
+    fn services(kind: HassEntityKind, color: bool, signal: LightSignal) -> Vec<String> {
+        let colors = [
+            ColorUpdate::new(XY::new(0.6, 0.3)),
+            ColorUpdate::new(XY::new(0.2, 0.6)),
+        ];
+        signal_steps(&binding(kind, color), signal, &colors)
+            .into_iter()
+            .map(|step| {
+                let color = step
+                    .data
+                    .get("xy_color")
+                    .map_or(String::new(), |xy| format!(" {xy}"));
+                format!("{}.{}{color}", step.domain, step.service)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn durations_in_whole_seconds() {
+        assert_eq!(signal_duration(0), Duration::ZERO);
+        assert_eq!(signal_duration(499), Duration::ZERO);
+        assert_eq!(signal_duration(1500), Duration::from_secs(2));
+        assert_eq!(signal_duration(u32::MAX), Duration::from_secs(65_534));
+    }
+
+    #[test]
+    fn blink_steps() {
+        assert_eq!(
+            services(HassEntityKind::Light, false, LightSignal::OnOff),
+            ["light.turn_on", "light.turn_off"]
+        );
+        assert_eq!(
+            services(HassEntityKind::Light, true, LightSignal::OnOffColor),
+            ["light.turn_on [0.6,0.3]", "light.turn_off"]
+        );
+        assert_eq!(
+            services(HassEntityKind::Light, true, LightSignal::Alternating),
+            ["light.turn_on [0.6,0.3]", "light.turn_on [0.2,0.6]"]
+        );
+        // Without color support, alternating is a plain blink
+        assert_eq!(
+            services(HassEntityKind::Light, false, LightSignal::Alternating),
+            ["light.turn_on", "light.turn_off"]
+        );
+        assert_eq!(
+            services(HassEntityKind::Switch, false, LightSignal::OnOff),
+            ["switch.turn_on", "switch.turn_off"]
+        );
+        assert!(services(HassEntityKind::Sensor, false, LightSignal::OnOff).is_empty());
+    }
+}
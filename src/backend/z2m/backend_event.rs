@@ -12,7 +12,7 @@ use hue::api::{
     Entertainment, EntertainmentConfiguration, GroupedLight, GroupedLightUpdate, Light,
     LightEffectsV2Update, LightGradientMode, LightUpdate, RType, Resource, ResourceLink, Room,
     RoomUpdate, Scene, SceneActive, SceneStatus, SceneStatusEnum, SceneUpdate,
-    ZigbeeDeviceDiscoveryUpdate,
+    ZigbeeDeviceDiscoveryUpdate, Zone,
 };
 use hue::error::HueError;
 use hue::stream::HueStreamLightsV2;
@@ -224,10 +224,14 @@ impl Z2mBackend {
                     })?;
                 }
 
-                let room = lock.get::<Scene>(link)?.group;
+                let scene = lock.get::<Scene>(link)?.clone();
+                let room = scene.group;
                 drop(lock);
 
-                if let Some(topic) = self.rmap.get(&room).cloned() {
+                if room.rtype == RType::Zone {
+                    log::info!("[{}] Recall zone scene: {link:?}", self.name);
+                    self.zone_scene_recall(z2mws, &scene).await?;
+                } else if let Some(topic) = self.rmap.get(&room).cloned() {
                     log::info!("[{}] Recall scene: {link:?}", self.name);
 
                     let mut lock = self.state.lock().await;
@@ -269,15 +273,55 @@ impl Z2mBackend {
         link: &ResourceLink,
         upd: &GroupedLightUpdate,
     ) -> ApiResult<()> {
-        let room = self.state.lock().await.get::<GroupedLight>(link)?.owner;
+        let lock = self.state.lock().await;
+        let owner = lock.get::<GroupedLight>(link)?.owner;
+
+        if owner.rtype == RType::Zone {
+            // Zones have no z2m group, so address our lights in it directly
+            let topics = lock
+                .get::<Zone>(&owner)?
+                .children
+                .iter()
+                .filter_map(|light| self.rmap.get(light))
+                .collect::<Vec<_>>();
+            drop(lock);
 
-        if let Some(topic) = self.rmap.get(&room) {
+            let payload = upd.into();
+            for topic in topics {
+                z2mws.send_update(topic, &payload).await?;
+            }
+        } else if let Some(topic) = self.rmap.get(&owner) {
+            drop(lock);
             z2mws.send_update(topic, &upd.into()).await?;
         }
 
         Ok(())
     }
 
+    /// Zone scenes are not stored in z2m, so set each of our lights in the
+    /// scene to its stored state.
+    async fn zone_scene_recall(&self, z2mws: &mut Z2mWebSocket, scene: &Scene) -> ApiResult<()> {
+        for action in &scene.actions {
+            if !self.rmap.contains_key(&action.target) {
+                continue;
+            }
+
+            let upd = LightUpdate {
+                on: action.action.on,
+                dimming: action.action.dimming,
+                color: action.action.color,
+                color_temperature: action.action.color_temperature,
+                gradient: action.action.gradient.clone(),
+                ..LightUpdate::default()
+            };
+
+            self.backend_light_update(z2mws, &action.target, &upd)
+                .await?;
+        }
+
+        Ok(())
+    }
+
     async fn backend_room_update(
         &self,
         z2mws: &mut Z2mWebSocket,
@@ -317,7 +361,66 @@ impl Z2mBackend {
         Ok(())
     }
 
-    async fn backend_delete(&self, z2mws: &mut Z2mWebSocket, link: &ResourceLink) -> ApiResult<()> {
+    /// Add a room made in the Hue app under its requested link, if all of
+    /// its devices are ours. Returns the topic of the z2m group to create for
+    /// it, which [`Self::add_group`] matches back to this room.
+    pub(super) async fn claim_room(
+        &self,
+        link: &ResourceLink,
+        room: &Room,
+    ) -> ApiResult<Option<String>> {
+        if room.children.is_empty() || !room.children.iter().all(|dev| self.rmap.contains_key(dev))
+        {
+            return Ok(None);
+        }
+
+        let prefix = self.server.group_prefix.as_deref().unwrap_or_default();
+        let topic = format!("{prefix}{}", room.metadata.name);
+
+        let mut res = self.state.lock().await;
+        res.aux_set(link, AuxData::new().with_topic(&topic));
+        res.add(
+            link,
+            Resource::Room(Room {
+                children: room.children.clone(),
+                metadata: room.metadata.clone(),
+                services: BTreeSet::new(),
+            }),
+        )?;
+        drop(res);
+
+        Ok(Some(topic))
+    }
+
+    /// Create a z2m group for a room made in the Hue app, if all of its
+    /// devices are ours. The grouped light is added once z2m reports the group.
+    async fn backend_room_create(
+        &self,
+        z2mws: &mut Z2mWebSocket,
+        link: &ResourceLink,
+        room: &Room,
+    ) -> ApiResult<()> {
+        let Some(topic) = self.claim_room(link, room).await? else {
+            return Ok(());
+        };
+
+        log::info!("[{}] Creating group {topic} for {link:?}", self.name);
+        z2mws.send_group_add(&topic).await?;
+
+        for device in &room.children {
+            z2mws
+                .send_group_member_add(&topic, &self.rmap[device])
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    async fn backend_delete(
+        &mut self,
+        z2mws: &mut Z2mWebSocket,
+        link: &ResourceLink,
+    ) -> ApiResult<()> {
         match link.rtype {
             RType::Scene => {
                 let lock = self.state.lock().await;
@@ -351,6 +454,29 @@ impl Z2mBackend {
                 }
             }
 
+            RType::Room => {
+                let Some(topic) = self.rmap.get(link).cloned() else {
+                    return Ok(());
+                };
+
+                log::info!("[{}] Removing group {topic} for {link:?}", self.name);
+                z2mws.send_group_remove(&topic).await?;
+
+                let mut lock = self.state.lock().await;
+                let room = lock.get::<Room>(link)?.clone();
+                for scene in lock.get_scenes_for_room(&link.rid) {
+                    lock.delete(&RType::Scene.link_to(scene))?;
+                }
+                lock.delete(link)?;
+                drop(lock);
+
+                self.map.remove(&topic);
+                self.rmap.remove(link);
+                if let Some(glight) = room.grouped_light_service() {
+                    self.rmap.remove(glight);
+                }
+            }
+
             rtype => {
                 log::warn!(
                     "[{}] Deleting objects of type {rtype:?} is not supported",
@@ -488,6 +614,8 @@ impl Z2mBackend {
             BackendRequest::LightUpdate(link, upd) => {
                 self.backend_light_update(z2mws, link, upd).await
             }
+            // The route has already updated the sensor, and disabled sensors
+            // are skipped when handling device updates.
             BackendRequest::SensorEnabledUpdate(_, _) => Ok(()),
             BackendRequest::HassSync => Ok(()),
             BackendRequest::HassUpsertEntity(_) => Ok(()),
@@ -508,10 +636,20 @@ impl Z2mBackend {
                 self.backend_grouped_light_update(z2mws, link, upd).await
             }
 
+            BackendRequest::RoomCreate(link, room) => {
+                self.backend_room_create(z2mws, link, room).await
+            }
+
             BackendRequest::RoomUpdate(link, upd) => {
                 self.backend_room_update(z2mws, link, upd).await
             }
 
+            BackendRequest::DeviceUpdate(_, _) => Ok(()),
+
+            BackendRequest::SoftwareUpdateInstall(link) => {
+                self.backend_software_update_install(z2mws, link).await
+            }
+
             BackendRequest::Delete(link) => self.backend_delete(z2mws, link).await,
 
             BackendRequest::EntertainmentStart(ent_id) => {
@@ -3,13 +3,18 @@ use serde_json::Value;
 use tokio_tungstenite::tungstenite;
 use uuid::Uuid;
 
-use hue::api::{DimmingUpdate, GroupedLight, Light, LightUpdate, RType, Resource, Room};
+use hue::api::{
+    Device, DimmingUpdate, GroupedLight, Light, LightUpdate, RType, Resource, Room,
+    ZigbeeConnectivity, ZigbeeConnectivityStatus,
+};
 use z2m::api::{
-    BridgeDevices, DeviceRemoveResponse, GroupMemberChange, Message, RawMessage, Response,
+    Availability, AvailabilityMessage, BridgeDevices, DeviceRemoveResponse, GroupMemberChange,
+    Message, RawMessage, Response,
 };
 use z2m::update::DeviceUpdate;
 
 use crate::backend::z2m::Z2mBackend;
+use crate::backend::z2m::bridge_import::sensor_services;
 use crate::error::{ApiError, ApiResult};
 
 impl Z2mBackend {
@@ -52,6 +57,18 @@ impl Z2mBackend {
         let upd = DeviceUpdate::deserialize(payload)?;
 
         let obj = self.state.lock().await.get_resource_by_id(rid)?.obj;
+
+        let device = match &obj {
+            Resource::Light(light) => Some(light.owner.rid),
+            Resource::Device(_) => Some(*rid),
+            _ => None,
+        };
+        if let (Some(device), Some(ota)) = (device, &upd.update) {
+            if let Err(e) = self.handle_update_software(&device, ota).await {
+                log::error!("FAIL: {e:?} in {upd:?}");
+            }
+        }
+
         match obj {
             Resource::Light(_) => {
                 if let Err(e) = self.handle_update_light(rid, &upd).await {
@@ -63,16 +80,73 @@ impl Z2mBackend {
                     log::error!("FAIL: {e:?} in {upd:?}");
                 }
             }
+            Resource::Device(_) => {
+                if let Some(action) = &upd.action {
+                    if let Err(e) = self.handle_update_remote(rid, action).await {
+                        log::error!("FAIL: {e:?} in {upd:?}");
+                    }
+                }
+                if let Err(e) = self.handle_update_sensors(rid, &upd).await {
+                    log::error!("FAIL: {e:?} in {upd:?}");
+                }
+            }
             _ => {}
         }
 
         Ok(())
     }
 
+    /// Mark a device as connected or not, like a real bridge does when a
+    /// device stops responding.
+    async fn handle_availability(&self, name: &str, payload: &Value) -> ApiResult<()> {
+        let Some(link) = self.map.get(name) else {
+            return Ok(());
+        };
+
+        let status = match AvailabilityMessage::deserialize(payload)?.state() {
+            Availability::Online => ZigbeeConnectivityStatus::Connected,
+            Availability::Offline => ZigbeeConnectivityStatus::ConnectivityIssue,
+        };
+
+        let mut lock = self.state.lock().await;
+        let device = match link.rtype {
+            RType::Light => lock.get::<Light>(link)?.owner,
+            RType::Device => *link,
+            _ => return Ok(()),
+        };
+
+        let Some(zigcon) = lock
+            .get::<Device>(&device)?
+            .service(RType::ZigbeeConnectivity)
+            .copied()
+        else {
+            return Ok(());
+        };
+
+        log::debug!("[{}] Device {name} is now {status:?}", self.name);
+        lock.update::<ZigbeeConnectivity>(&zigcon.rid, |zc| zc.status = status)?;
+        drop(lock);
+
+        Ok(())
+    }
+
     async fn handle_device_message(&mut self, msg: RawMessage) -> ApiResult<()> {
-        if msg.topic.ends_with("/availability") || msg.topic.ends_with("/action") {
-            // availability: https://www.zigbee2mqtt.io/guide/usage/mqtt_topics_and_messages.html#zigbee2mqtt-friendly-name-availability
+        // https://www.zigbee2mqtt.io/guide/usage/mqtt_topics_and_messages.html#zigbee2mqtt-friendly-name-availability
+        if let Some(name) = msg.topic.strip_suffix("/availability") {
+            if let Err(err) = self.handle_availability(name, &msg.payload).await {
+                log::error!(
+                    "[{}] Cannot handle availability of {name}: {err}",
+                    self.name
+                );
+            }
+            return Ok(());
+        }
+
+        if msg.topic.ends_with("/action") {
             // action: https://www.home-assistant.io/integrations/device_trigger.mqtt/
+            //
+            // Actions are also reported in the device state, which is where
+            // they are handled, so each one only results in a single event.
             return Ok(());
         }
 
@@ -111,6 +185,15 @@ impl Z2mBackend {
                     dev.model_id.as_deref().unwrap_or("<unknown model>")
                 );
                 self.add_light(dev, exp).await?;
+            } else if dev.expose_action().is_some() || !sensor_services(dev).is_empty() {
+                log::info!(
+                    "[{}] Adding device {:?}: [{}] ({})",
+                    self.name,
+                    dev.ieee_address,
+                    dev.friendly_name,
+                    dev.model_id.as_deref().unwrap_or("<unknown model>")
+                );
+                self.add_device(dev).await?;
             } else {
                 log::debug!(
                     "[{}] Ignoring unsupported device {}",
@@ -119,18 +202,6 @@ impl Z2mBackend {
                 );
                 self.ignore.insert(dev.friendly_name.to_string());
             }
-            /*
-            if dev.expose_action() {
-                log::info!(
-                    "[{}] Adding switch {:?}: [{}] ({})",
-                    self.name,
-                    dev.ieee_address,
-                    dev.friendly_name,
-                    dev.model_id.as_deref().unwrap_or("<unknown model>")
-                );
-                self.add_switch(dev).await?;
-            }
-            */
         }
 
         Ok(())
@@ -145,6 +216,10 @@ impl Z2mBackend {
                     log::info!("Removing device: {owner:?}");
                     lock.delete(&owner)?;
                 }
+                RType::Device => {
+                    log::info!("Removing device: {rlink:?}");
+                    self.state.lock().await.delete(rlink)?;
+                }
                 rtype => {
                     log::warn!("Cannot handle removing resource of type {rtype:?}");
                 }
@@ -1,29 +1,30 @@
 use std::collections::HashSet;
 
-use chrono::Utc;
 use maplit::btreeset;
 use serde_json::json;
 use uuid::Uuid;
 
 use hue::api::{
-    BridgeHome, Button, ButtonData, ButtonMetadata, ButtonReport, DeviceArchetype,
-    DeviceProductData, Entertainment, EntertainmentSegment, EntertainmentSegments, GroupedLight,
-    Light, LightEffects, LightEffectsV2, LightMetadata, Metadata, RType, Resource, ResourceLink,
-    Room, RoomArchetype, RoomMetadata, Scene, SceneActive, SceneMetadata, SceneRecall, SceneStatus,
-    Stub, Taurus, ZigbeeConnectivity, ZigbeeConnectivityStatus,
+    BridgeHome, DevicePower, DeviceProductData, DeviceSoftwareUpdate, Entertainment,
+    EntertainmentSegment, EntertainmentSegments, GroupedLight, Light, LightEffects, LightEffectsV2,
+    LightMetadata, Metadata, RType, RelativeRotary, Resource, ResourceLink, Room, RoomArchetype,
+    RoomMetadata, Scene, SceneActive, SceneMetadata, SceneRecall, SceneStatus, Stub, Taurus,
+    ZigbeeConnectivity, ZigbeeConnectivityStatus,
 };
 use hue::scene_icons;
-use z2m::api::ExposeLight;
+use z2m::action;
+use z2m::api::{Expose, ExposeLight};
 use z2m::convert::{
     ExtractColorTemperature, ExtractDeviceProductData, ExtractDimming, ExtractLightColor,
     ExtractLightGradient,
 };
 
-use crate::backend::z2m::Z2mBackend;
+use crate::backend::z2m::{Z2mBackend, remote, sensor};
 use crate::error::ApiResult;
 use crate::model::state::AuxData;
 
 impl Z2mBackend {
+    #[allow(clippy::too_many_lines)]
     pub async fn add_light(
         &mut self,
         apidev: &z2m::api::Device,
@@ -44,10 +45,17 @@ impl Z2mBackend {
             apidev.manufacturer.as_deref() == Some(DeviceProductData::SIGNIFY_MANUFACTURER_NAME);
         let gradient = apidev.expose_gradient();
 
+        let link_swu = RType::DeviceSoftwareUpdate.deterministic(link_device.rid);
+
+        let mut services = btreeset![link_zigcon, link_light, link_enttm, link_taurus];
+        if apidev.supports_ota() {
+            services.insert(link_swu);
+        }
+
         let dev = hue::api::Device {
             product_data,
             metadata: metadata.clone().into(),
-            services: btreeset![link_zigcon, link_light, link_enttm, link_taurus],
+            services,
             identify: Some(Stub),
             usertest: None,
         };
@@ -130,7 +138,7 @@ impl Z2mBackend {
         let zigcon = ZigbeeConnectivity {
             channel: None,
             extended_pan_id: None,
-            mac_address: apidev.ieee_address.to_string(),
+            mac_address: apidev.ieee_address.mac_address(),
             owner: link_device,
             status: ZigbeeConnectivityStatus::Connected,
         };
@@ -142,58 +150,116 @@ impl Z2mBackend {
         res.add(&link_enttm, Resource::Entertainment(enttm))?;
         res.add(&link_taurus, Resource::Taurus(taurus))?;
         res.add(&link_zigcon, Resource::ZigbeeConnectivity(zigcon))?;
+        res.update::<ZigbeeConnectivity>(&link_zigcon.rid, |zigcon| {
+            zigcon.mac_address = apidev.ieee_address.mac_address();
+        })?;
+        if apidev.supports_ota() {
+            let swu = DeviceSoftwareUpdate::new(link_device);
+            res.add(&link_swu, Resource::DeviceSoftwareUpdate(swu))?;
+            // Lights from before update support need the new service linked
+            res.update::<hue::api::Device>(&link_device.rid, |dev| {
+                dev.services.insert(link_swu);
+            })?;
+        }
         drop(res);
 
         Ok(())
     }
 
-    pub async fn add_switch(&mut self, dev: &z2m::api::Device) -> ApiResult<()> {
-        let name = &dev.friendly_name;
+    /// Add a device that is not a light. Remotes get a button for each
+    /// button found in their `action` values, and a relative rotary if they
+    /// have a dial. Sensors get a service for each reading they report.
+    pub async fn add_device(&mut self, apidev: &z2m::api::Device) -> ApiResult<()> {
+        let name = &apidev.friendly_name;
+
+        let link_device = RType::Device.deterministic(&apidev.ieee_address);
+        let link_zigcon = RType::ZigbeeConnectivity.deterministic(&apidev.ieee_address);
+
+        let mut services = vec![];
+
+        if let Some(expose) = apidev.expose_action() {
+            for (button, control_id) in action::buttons(expose.string_values()).into_iter().zip(1..)
+            {
+                let link = RType::Button.deterministic((link_device.rid, button));
+                let obj = remote::make_button(link_device, control_id);
+                services.push((link, Resource::Button(obj)));
+            }
+
+            if action::has_rotary(expose.string_values()) {
+                let link = RType::RelativeRotary.deterministic(link_device.rid);
+                let obj = RelativeRotary {
+                    owner: link_device,
+                    relative_rotary: None,
+                    rotary_report: None,
+                };
+                services.push((link, Resource::RelativeRotary(obj)));
+            }
+        }
+
+        for rtype in sensor_services(apidev) {
+            if let Some(obj) = sensor::make_sensor(rtype, link_device) {
+                services.push((rtype.deterministic(link_device.rid), obj));
+            }
+        }
+
+        let link_power = RType::DevicePower.deterministic(link_device.rid);
+        let has_battery = apidev.expose_property("battery").is_some();
+        if has_battery {
+            services.push((
+                link_power,
+                Resource::DevicePower(DevicePower::new(link_device)),
+            ));
+        }
 
-        let link_device = RType::Device.deterministic(&dev.ieee_address);
-        let link_button = RType::Button.deterministic(&dev.ieee_address);
-        let link_zbc = RType::ZigbeeConnectivity.deterministic(&dev.ieee_address);
+        let link_swu = RType::DeviceSoftwareUpdate.deterministic(link_device.rid);
+        let has_ota = apidev.supports_ota();
+        if has_ota {
+            services.push((
+                link_swu,
+                Resource::DeviceSoftwareUpdate(DeviceSoftwareUpdate::new(link_device)),
+            ));
+        }
 
+        services.push((
+            link_zigcon,
+            Resource::ZigbeeConnectivity(ZigbeeConnectivity {
+                channel: None,
+                extended_pan_id: None,
+                mac_address: apidev.ieee_address.mac_address(),
+                owner: link_device,
+                status: ZigbeeConnectivityStatus::Connected,
+            }),
+        ));
+
+        let product_data = DeviceProductData::guess_from_device(apidev);
         let dev = hue::api::Device {
-            product_data: DeviceProductData::guess_from_device(dev),
-            metadata: Metadata::new(DeviceArchetype::UnknownArchetype, "foo"),
-            services: btreeset![link_button, link_zbc],
+            metadata: Metadata::new(product_data.product_archetype.clone(), name),
+            product_data,
+            services: services.iter().map(|(link, _)| *link).collect(),
             identify: None,
             usertest: None,
         };
 
-        self.map.insert(name.to_string(), link_button);
-        self.rmap.insert(link_button, name.to_string());
+        self.map.insert(name.to_string(), link_device);
+        self.rmap.insert(link_device, name.to_string());
 
+        // Known services are kept as they are, so sensors stay disabled
+        // across restarts, if disabled in the Hue app. Only the mac address
+        // is refreshed, since older versions stored the raw IEEE address.
         let mut res = self.state.lock().await;
-        let button = Button {
-            owner: link_device,
-            metadata: ButtonMetadata { control_id: 0 },
-            button: ButtonData {
-                last_event: None,
-                button_report: Some(ButtonReport {
-                    updated: Utc::now(),
-                    event: String::from("initial_press"),
-                }),
-                repeat_interval: Some(100),
-                event_values: Some(json!(["initial_press", "repeat"])),
-            },
-        };
-
-        let zbc = ZigbeeConnectivity {
-            owner: link_device,
-            mac_address: String::from("11:22:33:44:55:66:77:89"),
-            status: ZigbeeConnectivityStatus::ConnectivityIssue,
-            channel: Some(json!({
-                "status": "set",
-                "value": "channel_25",
-            })),
-            extended_pan_id: None,
-        };
-
         res.add(&link_device, Resource::Device(dev))?;
-        res.add(&link_button, Resource::Button(button))?;
-        res.add(&link_zbc, Resource::ZigbeeConnectivity(zbc))?;
+        for (link, obj) in services {
+            res.add(&link, obj)?;
+        }
+        res.update::<ZigbeeConnectivity>(&link_zigcon.rid, |zigcon| {
+            zigcon.mac_address = apidev.ieee_address.mac_address();
+        })?;
+        // Devices from before battery and update support need the new
+        // services linked
+        let linked = [(link_power, has_battery), (link_swu, has_ota)]
+            .into_iter()
+            .filter_map(|(link, found)| found.then_some(link));
+        res.update::<hue::api::Device>(&link_device.rid, |dev| dev.services.extend(linked))?;
         drop(res);
 
         Ok(())
@@ -218,7 +284,23 @@ impl Z2mBackend {
             room_name = &grp.friendly_name;
         }
 
-        let link_room = RType::Room.deterministic(&grp.friendly_name);
+        let topic = grp.friendly_name.to_string();
+
+        let mut res = self.state.lock().await;
+
+        // Groups created for a room made in the Hue app keep the link of
+        // that room, which has the group topic in its aux data.
+        let link_room = res
+            .get_resource_ids_by_type(RType::Room)
+            .into_iter()
+            .find(|id| {
+                res.aux_get(&RType::Room.link_to(*id))
+                    .is_ok_and(|aux| aux.topic.as_ref() == Some(&topic))
+            })
+            .map_or_else(
+                || RType::Room.deterministic(&grp.friendly_name),
+                |id| RType::Room.link_to(id),
+            );
         let link_glight = RType::GroupedLight.deterministic((link_room.rid, grp.id));
 
         let children = grp
@@ -227,10 +309,6 @@ impl Z2mBackend {
             .map(|f| RType::Device.deterministic(&f.ieee_address))
             .collect();
 
-        let topic = grp.friendly_name.to_string();
-
-        let mut res = self.state.lock().await;
-
         let mut scenes_new = HashSet::new();
 
         for scn in &grp.scenes {
@@ -327,7 +405,14 @@ impl Z2mBackend {
             })?;
         }
 
-        res.add(&link_room, Resource::Room(room))?;
+        if res.get::<Room>(&link_room).is_ok() {
+            res.update(&link_room.rid, |known: &mut Room| {
+                known.children = room.children;
+                known.services = room.services;
+            })?;
+        } else {
+            res.add(&link_room, Resource::Room(room))?;
+        }
 
         let glight = GroupedLight::new(link_room);
 
@@ -338,6 +423,27 @@ impl Z2mBackend {
     }
 }
 
+/// The sensor services for the readings a device reports
+pub fn sensor_services(apidev: &z2m::api::Device) -> Vec<RType> {
+    let has = |property| apidev.expose_property(property).is_some();
+
+    // Before zigbee2mqtt 2.0, "illuminance" was the raw measurement
+    let has_lux = has("illuminance_lux")
+        || apidev.expose_property("illuminance").is_some_and(
+            |exp| matches!(exp, Expose::Numeric(num) if num.unit.as_deref() == Some("lx")),
+        );
+
+    [
+        (RType::Motion, has("occupancy")),
+        (RType::Contact, has("contact")),
+        (RType::Temperature, has("temperature")),
+        (RType::LightLevel, has_lux),
+    ]
+    .into_iter()
+    .filter_map(|(rtype, found)| found.then_some(rtype))
+    .collect()
+}
+
 #[allow(clippy::match_same_arms)]
 fn guess_scene_icon(name: &str) -> Option<ResourceLink> {
     let icon = match name {
@@ -365,3 +471,148 @@ fn guess_scene_icon(name: &str) -> Option<ResourceLink> {
         rtype: RType::PublicImage,
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use maplit::btreeset;
+    use serde_json::{Value, json};
+
+    use bifrost_api::backend::BackendRequest;
+    use bifrost_api::config::Z2mServer;
+    use hue::api::{
+        Device, DeviceArchetype, DeviceProductData, GroupedLight, Metadata, RType, Resource, Room,
+        RoomArchetype, RoomMetadata,
+    };
+    use hue::version::SwVersion;
+
+    use crate::backend::z2m::Z2mBackend;
+    use crate::backend::z2m::bridge_import::sensor_services;
+    use crate::routes::clip::room::post_room;
+    use crate::server::appstate::AppState;
+
+    #[tokio::test]
+    async fn room_from_hue_app_keeps_its_link() {
+        let state = AppState::for_tests().unwrap();
+        let server = Z2mServer {
+            url: "ws://localhost:8080".parse().unwrap(),
+            group_prefix: None,
+            disable_tls_verify: None,
+            streaming_fps: None,
+        };
+        let mut z2m =
+            Z2mBackend::new("z2m".into(), server, state.config(), state.res.clone()).unwrap();
+
+        let grp: z2m::api::Group = serde_json::from_str(
+            r#"{
+                "friendly_name": "Office",
+                "id": 7,
+                "members": [{"endpoint": 11, "ieee_address": "0x0017880100000001"}],
+                "scenes": []
+            }"#,
+        )
+        .unwrap();
+        let link_device = RType::Device.deterministic(&grp.members[0].ieee_address);
+
+        let mut requests = {
+            let mut res = state.res.lock().await;
+            let device = Device {
+                product_data: DeviceProductData::hue_bridge_v2(&SwVersion::default()),
+                metadata: Metadata::new(DeviceArchetype::ClassicBulb, "Lamp"),
+                services: btreeset![],
+                usertest: None,
+                identify: None,
+            };
+            res.add(&link_device, Resource::Device(device)).unwrap();
+            res.backend_event_stream()
+        };
+        z2m.rmap.insert(link_device, "Lamp".into());
+
+        let room = Room {
+            children: btreeset![link_device],
+            metadata: RoomMetadata::new(RoomArchetype::Office, "Office"),
+            services: btreeset![],
+        };
+        post_room(&state, serde_json::to_value(room).unwrap())
+            .await
+            .unwrap();
+
+        let BackendRequest::RoomCreate(link, room) = &*requests.recv().await.unwrap() else {
+            panic!("expected a room create request");
+        };
+        assert_eq!(
+            z2m.claim_room(link, room).await.unwrap().as_deref(),
+            Some("Office")
+        );
+        assert!(state.res.lock().await.get::<Room>(link).is_ok());
+
+        z2m.add_group(&grp).await.unwrap();
+
+        let res = state.res.lock().await;
+        let room = res.get::<Room>(link).unwrap();
+        assert_eq!(room.children, btreeset![link_device]);
+        let link_glight = room.grouped_light_service().unwrap();
+        assert_eq!(res.get::<GroupedLight>(link_glight).unwrap().owner, *link);
+        assert_eq!(res.get_resource_ids_by_type(RType::Room), vec![link.rid]);
+        drop(res);
+    }
+
+    fn sensor_device(exposes: &[Value]) -> z2m::api::Device {
+        let device = json!({
+            "definition": {
+                "model": "SNZB-03",
+                "vendor": "SONOFF",
+                "description": "Sensor",
+                "exposes": exposes,
+                "supports_ota": false,
+                "options": [],
+            },
+            "disabled": false,
+            "endpoints": {},
+            "friendly_name": "Sensor",
+            "ieee_address": "0x0017880100000002",
+            "interview_completed": true,
+            "interviewing": false,
+            "network_address": 1234,
+            "type": "EndDevice",
+        });
+        serde_json::from_str(&device.to_string()).unwrap()
+    }
+
+    fn numeric(property: &str, unit: &str) -> Value {
+        json!({"type": "numeric", "property": property, "unit": unit})
+    }
+
+    fn binary(property: &str) -> Value {
+        json!({"type": "binary", "property": property, "value_on": true, "value_off": false})
+    }
+
+    #[test]
+    fn sensor_services_from_exposes() {
+        let dev = sensor_device(&[
+            binary("occupancy"),
+            binary("contact"),
+            numeric("temperature", "°C"),
+            numeric("illuminance_lux", "lx"),
+        ]);
+        assert_eq!(
+            sensor_services(&dev),
+            vec![
+                RType::Motion,
+                RType::Contact,
+                RType::Temperature,
+                RType::LightLevel
+            ]
+        );
+        assert!(sensor_services(&sensor_device(&[])).is_empty());
+    }
+
+    #[test]
+    fn sensor_services_illuminance_needs_lux() {
+        let lux = sensor_device(&[numeric("illuminance", "lx")]);
+        assert_eq!(sensor_services(&lux), vec![RType::LightLevel]);
+
+        // Before zigbee2mqtt 2.0, "illuminance" was the raw measurement
+        let raw = sensor_device(&[numeric("illuminance", "")]);
+        assert!(sensor_services(&raw).is_empty());
+    }
+}
@@ -3,6 +3,9 @@ mod bridge_event;
 mod bridge_import;
 pub mod entertainment;
 pub mod learn;
+mod remote;
+mod sensor;
+mod software_update;
 pub mod websocket;
 pub mod zclcommand;
 
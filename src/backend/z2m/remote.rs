@@ -0,0 +1,157 @@
+use chrono::{DateTime, Duration, Utc};
+use serde_json::json;
+use uuid::Uuid;
+
+use hue::api::{
+    Button, ButtonData, ButtonMetadata, ButtonReport, RType, RelativeRotary, ResourceLink,
+};
+use z2m::action::{Action, ButtonAction, RotaryDirection, RotarySpeed};
+
+use crate::backend::z2m::Z2mBackend;
+use crate::error::ApiResult;
+
+/// Hue button events, in the order a real Hue remote reports them
+const BUTTON_EVENT_VALUES: [&str; 6] = [
+    "initial_press",
+    "repeat",
+    "short_release",
+    "long_press",
+    "long_release",
+    "double_short_release",
+];
+
+/// Rotary events closer together than this continue a turn ("repeat")
+const ROTARY_REPEAT_WINDOW: Duration = Duration::milliseconds(1000);
+
+#[must_use]
+pub fn make_button(owner: ResourceLink, control_id: u32) -> Button {
+    Button {
+        owner,
+        metadata: ButtonMetadata { control_id },
+        button: ButtonData {
+            button_report: None,
+            last_event: None,
+            repeat_interval: Some(800),
+            event_values: Some(json!(BUTTON_EVENT_VALUES)),
+        },
+    }
+}
+
+/// Translate a zigbee2mqtt button action into Hue button events.
+///
+/// Holds and plain releases depend on what the button did before: a hold
+/// that continues is a `repeat`, and a release after a hold is a
+/// `long_release`.
+fn button_events(action: ButtonAction, last: Option<&str>) -> &'static [&'static str] {
+    let held = matches!(last, Some("long_press" | "repeat"));
+
+    match action {
+        ButtonAction::Press => &["initial_press"],
+        ButtonAction::Hold if held => &["repeat"],
+        ButtonAction::Hold => &["long_press"],
+        ButtonAction::Release if held => &["long_release"],
+        ButtonAction::Release | ButtonAction::PressRelease => &["short_release"],
+        ButtonAction::HoldRelease => &["long_release"],
+        ButtonAction::Click | ButtonAction::Activate => &["initial_press", "short_release"],
+        ButtonAction::Double => &["double_short_release"],
+    }
+}
+
+/// Rough number of steps for each speed of dial rotation z2m reports
+const fn rotary_steps(speed: RotarySpeed) -> u32 {
+    match speed {
+        RotarySpeed::Step => 30,
+        RotarySpeed::Slow => 60,
+        RotarySpeed::Fast => 120,
+    }
+}
+
+impl Z2mBackend {
+    /// Push an `action` reported by a remote to the Hue event stream, as
+    /// button or rotary activity.
+    pub(super) async fn handle_update_remote(&self, device: &Uuid, action: &str) -> ApiResult<()> {
+        let Some(action) = Action::parse(action) else {
+            return Ok(());
+        };
+
+        let now = Utc::now();
+        let mut res = self.state.lock().await;
+
+        match action {
+            Action::Button { button, action } => {
+                let link = RType::Button.deterministic((*device, button));
+
+                // Plain actions of single-button remotes have no button
+                let Ok(current) = res.get::<Button>(&link) else {
+                    log::debug!(
+                        "[{}] Ignoring action for unknown button {link:?}",
+                        self.name
+                    );
+                    return Ok(());
+                };
+
+                let last = current
+                    .button
+                    .button_report
+                    .as_ref()
+                    .map(|report| report.event.clone());
+
+                for event in button_events(action, last.as_deref()) {
+                    res.update::<Button>(&link.rid, |button| {
+                        button.button.button_report = Some(ButtonReport {
+                            updated: now,
+                            event: (*event).to_string(),
+                        });
+                        button.button.last_event = Some(json!(event));
+                    })?;
+                }
+            }
+            Action::Rotary { direction, speed } => {
+                let link = RType::RelativeRotary.deterministic(*device);
+
+                let previous = res
+                    .get::<RelativeRotary>(&link)?
+                    .relative_rotary
+                    .as_ref()
+                    .and_then(|rr| {
+                        rr.pointer("/rotary_report/updated")?
+                            .as_str()?
+                            .parse::<DateTime<Utc>>()
+                            .ok()
+                    });
+
+                let action = if previous.is_some_and(|prev| now - prev < ROTARY_REPEAT_WINDOW) {
+                    "repeat"
+                } else {
+                    "start"
+                };
+
+                let rotation = json!({
+                    "direction": match direction {
+                        RotaryDirection::Left => "counter_clock_wise",
+                        RotaryDirection::Right => "clock_wise",
+                    },
+                    "steps": rotary_steps(speed),
+                    "duration": 400,
+                });
+
+                res.update::<RelativeRotary>(&link.rid, |rotary| {
+                    rotary.relative_rotary = Some(json!({
+                        "last_event": {
+                            "action": action,
+                            "rotation": rotation,
+                        },
+                        "rotary_report": {
+                            "updated": now.to_rfc3339(),
+                            "action": action,
+                            "rotation": rotation,
+                        },
+                    }));
+                })?;
+            }
+        }
+        drop(res);
+
+        Ok(())
+    }
+}
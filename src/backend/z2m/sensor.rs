@@ -0,0 +1,159 @@
+use chrono::Utc;
+use serde_json::json;
+use uuid::Uuid;
+
+use hue::api::{
+    Contact, DevicePower, LightLevel, Motion, RType, Resource, ResourceLink, Temperature,
+};
+use z2m::update::DeviceUpdate;
+
+use crate::backend::z2m::Z2mBackend;
+use crate::error::ApiResult;
+
+/// A sensor service without readings, until the device reports them
+#[must_use]
+pub fn make_sensor(rtype: RType, owner: ResourceLink) -> Option<Resource> {
+    let obj = match rtype {
+        RType::Motion => Resource::Motion(Motion {
+            enabled: true,
+            owner,
+            motion: json!({
+                "motion": false,
+                "motion_valid": false,
+            }),
+            sensitivity: json!({}),
+        }),
+        RType::Temperature => Resource::Temperature(Temperature {
+            enabled: true,
+            owner,
+            temperature: json!({
+                "temperature": 0.0,
+                "temperature_valid": false,
+            }),
+        }),
+        RType::LightLevel => Resource::LightLevel(LightLevel {
+            enabled: true,
+            light: json!({
+                "light_level": 0,
+                "light_level_valid": false,
+            }),
+            owner,
+        }),
+        RType::Contact => Resource::Contact(Contact::new(owner)),
+        _ => return None,
+    };
+    Some(obj)
+}
+
+impl Z2mBackend {
+    /// Update the sensor services of a device with the readings in a state
+    /// update, including its battery. Disabled sensors keep their last
+    /// reading.
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    pub(super) async fn handle_update_sensors(
+        &self,
+        device: &Uuid,
+        upd: &DeviceUpdate,
+    ) -> ApiResult<()> {
+        let changed = Utc::now().to_rfc3339();
+        let mut res = self.state.lock().await;
+
+        if let Some(occupancy) = upd.occupancy {
+            let link = RType::Motion.deterministic(*device);
+            if res.get::<Motion>(&link).is_ok_and(|obj| obj.enabled) {
+                res.update::<Motion>(&link.rid, |obj| {
+                    obj.motion = json!({
+                        "motion": occupancy,
+                        "motion_valid": true,
+                        "motion_report": {
+                            "changed": changed,
+                            "motion": occupancy,
+                        },
+                    });
+                })?;
+            }
+        }
+
+        // zigbee2mqtt reports contact when closed
+        if let Some(contact) = upd.contact {
+            let link = RType::Contact.deterministic(*device);
+            if res.get::<Contact>(&link).is_ok_and(|obj| obj.enabled) {
+                res.update::<Contact>(&link.rid, |obj| obj.report(!contact))?;
+            }
+        }
+
+        if let Some(temp) = upd.temperature {
+            let link = RType::Temperature.deterministic(*device);
+            if res.get::<Temperature>(&link).is_ok_and(|obj| obj.enabled) {
+                res.update::<Temperature>(&link.rid, |obj| {
+                    obj.temperature = json!({
+                        "temperature": temp,
+                        "temperature_valid": true,
+                        "temperature_report": {
+                            "changed": changed,
+                            "temperature": temp,
+                        },
+                    });
+                })?;
+            }
+        }
+
+        if let Some(lux) = upd.illuminance_lux.or(upd.illuminance) {
+            let link = RType::LightLevel.deterministic(*device);
+            if res.get::<LightLevel>(&link).is_ok_and(|obj| obj.enabled) {
+                let level = LightLevel::light_level_from_lux(lux) as u32;
+                res.update::<LightLevel>(&link.rid, |obj| {
+                    obj.light = json!({
+                        "light_level": level,
+                        "light_level_valid": true,
+                        "light_level_report": {
+                            "changed": changed,
+                            "light_level": level,
+                        },
+                    });
+                })?;
+            }
+        }
+
+        if let Some(battery) = upd.battery {
+            let link = RType::DevicePower.deterministic(*device);
+            if res.get::<DevicePower>(&link).is_ok() {
+                res.update::<DevicePower>(&link.rid, |obj| obj.report(battery))?;
+            }
+        }
+
+        drop(res);
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use hue::api::{RType, Resource};
+
+    use crate::backend::z2m::sensor::make_sensor;
+
+    #[test]
+    fn sensors_start_without_readings() {
+        let owner = RType::Device.deterministic("0x0017880100000002");
+
+        let Some(Resource::Motion(motion)) = make_sensor(RType::Motion, owner) else {
+            panic!("expected a motion sensor");
+        };
+        assert_eq!(motion.motion["motion_valid"], false);
+
+        let Some(Resource::LightLevel(level)) = make_sensor(RType::LightLevel, owner) else {
+            panic!("expected a light level sensor");
+        };
+        assert_eq!(level.light["light_level_valid"], false);
+
+        let Some(Resource::Contact(contact)) = make_sensor(RType::Contact, owner) else {
+            panic!("expected a contact sensor");
+        };
+        assert!(contact.contact_report.is_none());
+
+        assert!(make_sensor(RType::Temperature, owner).is_some());
+        assert!(make_sensor(RType::Button, owner).is_none());
+    }
+}
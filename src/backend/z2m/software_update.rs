@@ -0,0 +1,75 @@
+use uuid::Uuid;
+
+use hue::api::{DeviceSoftwareUpdate, RType, ResourceLink, SoftwareUpdateState};
+use z2m::update::{OtaUpdate, OtaUpdateState};
+
+use crate::backend::z2m::Z2mBackend;
+use crate::backend::z2m::websocket::Z2mWebSocket;
+use crate::error::ApiResult;
+
+const fn software_update_state(state: OtaUpdateState) -> Option<SoftwareUpdateState> {
+    match state {
+        OtaUpdateState::Idle => Some(SoftwareUpdateState::NoUpdate),
+        OtaUpdateState::Available => Some(SoftwareUpdateState::ReadyToInstall),
+        OtaUpdateState::Scheduled => Some(SoftwareUpdateState::UpdatePending),
+        OtaUpdateState::Updating => Some(SoftwareUpdateState::Installing),
+        OtaUpdateState::Unknown => None,
+    }
+}
+
+impl Z2mBackend {
+    /// Update the software update service of a device from its reported
+    /// `update` state. While updating, every progress report is an event.
+    pub(super) async fn handle_update_software(
+        &self,
+        device: &Uuid,
+        ota: &OtaUpdate,
+    ) -> ApiResult<()> {
+        let Some(state) = ota.state.and_then(software_update_state) else {
+            return Ok(());
+        };
+
+        let link = RType::DeviceSoftwareUpdate.deterministic(*device);
+
+        let mut res = self.state.lock().await;
+        if res.get::<DeviceSoftwareUpdate>(&link).is_ok() {
+            res.update::<DeviceSoftwareUpdate>(&link.rid, |swu| swu.report(state, ota.progress))?;
+        }
+        drop(res);
+
+        Ok(())
+    }
+
+    /// Ask zigbee2mqtt to install the firmware update for a device
+    pub(super) async fn backend_software_update_install(
+        &self,
+        z2mws: &mut Z2mWebSocket,
+        link: &ResourceLink,
+    ) -> ApiResult<()> {
+        let device = self
+            .state
+            .lock()
+            .await
+            .get::<DeviceSoftwareUpdate>(link)?
+            .owner;
+
+        let Some(dev) = self
+            .rmap
+            .get(&device)
+            .and_then(|topic| self.network.get(topic))
+        else {
+            return Ok(());
+        };
+
+        log::info!(
+            "[{}] Requesting firmware update of {} ({})",
+            self.name,
+            dev.ieee_address,
+            dev.friendly_name
+        );
+
+        z2mws
+            .send_device_ota_update(dev.ieee_address.to_string())
+            .await
+    }
+}
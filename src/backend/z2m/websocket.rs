@@ -6,7 +6,9 @@ use hue::zigbee::{HueZigbeeUpdate, ZigbeeMessage};
 use tokio::net::TcpStream;
 use tokio_tungstenite::tungstenite::{self, Message};
 use tokio_tungstenite::{MaybeTlsStream, WebSocketStream};
-use z2m::api::{DeviceRemove, GroupMemberChange, PermitJoin};
+use z2m::api::{
+    DeviceOtaUpdate, DeviceRemove, GroupAdd, GroupMemberChange, GroupRemove, PermitJoin,
+};
 use z2m::request::Z2mPayload;
 use z2m::update::DeviceUpdate;
 use z2m::{api::RawMessage, request::Z2mRequest};
@@ -39,6 +41,14 @@ impl Z2mWebSocket {
         /* ); */
 
         let api_req = match &payload {
+            Z2mRequest::GroupAdd(value) => RawMessage {
+                topic: "bridge/request/group/add".into(),
+                payload: serde_json::to_value(value)?,
+            },
+            Z2mRequest::GroupRemove(value) => RawMessage {
+                topic: "bridge/request/group/remove".into(),
+                payload: serde_json::to_value(value)?,
+            },
             Z2mRequest::GroupMemberAdd(value) => RawMessage {
                 topic: "bridge/request/group/members/add".into(),
                 payload: serde_json::to_value(value)?,
@@ -55,6 +65,10 @@ impl Z2mWebSocket {
                 topic: "bridge/request/device/remove".into(),
                 payload: serde_json::to_value(dev)?,
             },
+            Z2mRequest::DeviceOtaUpdate(dev) => RawMessage {
+                topic: "bridge/request/device/ota_update/update".into(),
+                payload: serde_json::to_value(dev)?,
+            },
             _ => RawMessage {
                 topic: format!("{topic}/set"),
                 payload: serde_json::to_value(payload)?,
@@ -96,6 +110,24 @@ impl Z2mWebSocket {
         self.send(topic, &z2mreq).await
     }
 
+    pub async fn send_group_add(&mut self, friendly_name: &str) -> ApiResult<()> {
+        let z2mreq = Z2mRequest::GroupAdd(GroupAdd {
+            id: None,
+            friendly_name: friendly_name.to_string(),
+        });
+
+        self.send("", &z2mreq).await
+    }
+
+    pub async fn send_group_remove(&mut self, friendly_name: &str) -> ApiResult<()> {
+        let z2mreq = Z2mRequest::GroupRemove(GroupRemove {
+            id: friendly_name.to_string(),
+            force: false,
+        });
+
+        self.send("", &z2mreq).await
+    }
+
     pub async fn send_group_member_add(
         &mut self,
         topic: &str,
@@ -166,6 +198,12 @@ impl Z2mWebSocket {
 
         self.send("", &z2mreq).await
     }
+
+    pub async fn send_device_ota_update(&mut self, id: String) -> ApiResult<()> {
+        let z2mreq = Z2mRequest::DeviceOtaUpdate(DeviceOtaUpdate { id });
+
+        self.send("", &z2mreq).await
+    }
 }
 
 impl Stream for Z2mWebSocket
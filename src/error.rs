@@ -123,6 +123,9 @@ pub enum ApiError {
     #[error("Failed to get firmware version reply from update server")]
     NoUpdateInformation,
 
+    #[error("unauthorized user")]
+    Unauthorized,
+
     /* bifrost errors: routes */
     #[error("Creating object of type {0:?} is not yet supported by Bifrost")]
     CreateNotYetSupported(RType),
@@ -142,6 +145,9 @@ pub enum ApiError {
     #[error("Deleting object of type {0:?} is not allowed by hue protocol")]
     DeleteNotAllowed(RType),
 
+    #[error("Object of type {0:?} needs a name")]
+    EmptyName(RType),
+
     /* bifrost errors */
     #[error("Missing auxiliary data resource {0:?}")]
     AuxNotFound(uuid::Uuid),
@@ -144,6 +144,8 @@ async fn build_tasks(appstate: &AppState) -> ApiResult<()> {
             url: fallback_url,
             token_env: Some("HASS_TOKEN".to_string()),
             poll_interval_secs: None,
+            poll_only: None,
+            streaming_fps: None,
         };
         let svc = backend::hass::HassBackend::new(
             "runtime".to_string(),
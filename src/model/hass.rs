@@ -1,4 +1,4 @@
-use std::collections::{BTreeSet, HashMap};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 use std::fs;
 use std::fs::File;
 
@@ -6,6 +6,9 @@ use camino::Utf8PathBuf;
 use chrono::{DateTime, Local, Utc};
 use serde::{Deserialize, Serialize};
 use url::Url;
+use uuid::Uuid;
+
+use hue::api::RoomArchetype;
 
 use crate::error::{ApiError, ApiResult};
 
@@ -14,6 +17,8 @@ use crate::error::{ApiError, ApiResult};
 pub enum HassSensorKind {
     Motion,
     Contact,
+    Temperature,
+    LightLevel,
     Ignore,
 }
 
@@ -134,6 +139,12 @@ pub struct HassRoomConfig {
     pub source_area: Option<String>,
     #[serde(default)]
     pub auto_created: bool,
+    /// Room type chosen in the Hue app
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub archetype: Option<RoomArchetype>,
+    /// Id of the Hue room, for rooms created in the Hue app
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hue_room_id: Option<Uuid>,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize, Eq, PartialEq, Default)]
@@ -152,6 +163,9 @@ pub struct HassEntityPreference {
     pub switch_mode: Option<HassSwitchMode>,
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub light_archetype: Option<HassLightArchetype>,
+    /// Hue effect name (e.g. `candle`) to Home Assistant effect name
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub effect_map: BTreeMap<String, String>,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize, Eq, PartialEq)]
@@ -255,6 +269,8 @@ impl HassUiConfig {
                     name: Self::DEFAULT_ROOM_NAME.to_string(),
                     source_area: None,
                     auto_created: false,
+                    archetype: None,
+                    hue_room_id: None,
                 },
             );
         }
@@ -321,6 +337,8 @@ impl HassUiConfig {
                     .map(|x| x.trim().to_string())
                     .filter(|x| !x.is_empty()),
                 auto_created: room.auto_created,
+                archetype: room.archetype,
+                hue_room_id: room.hue_room_id,
             });
         }
         self.rooms = normalized;
@@ -358,6 +376,12 @@ impl HassUiConfig {
                 .as_ref()
                 .map(|x| x.trim().to_string())
                 .filter(|x| !x.is_empty());
+            pref.effect_map = pref
+                .effect_map
+                .iter()
+                .map(|(hue, hass)| (hue.trim().to_string(), hass.trim().to_string()))
+                .filter(|(hue, _)| !hue.is_empty())
+                .collect();
             pref.visible.is_some()
                 || pref.room_id.is_some()
                 || pref.alias.is_some()
@@ -365,6 +389,7 @@ impl HassUiConfig {
                 || pref.sensor_enabled.is_some()
                 || pref.switch_mode.is_some()
                 || pref.light_archetype.is_some()
+                || !pref.effect_map.is_empty()
         });
     }
 
@@ -462,6 +487,15 @@ impl HassUiConfig {
         self.normalize();
     }
 
+    pub fn set_entity_effect_map(&mut self, entity_id: &str, effect_map: BTreeMap<String, String>) {
+        let pref = self
+            .entity_preferences
+            .entry(entity_id.to_string())
+            .or_default();
+        pref.effect_map = effect_map;
+        self.normalize();
+    }
+
     #[must_use]
     pub fn entity_alias(&self, entity_id: &str) -> Option<String> {
         self.entity_preferences
@@ -502,6 +536,14 @@ impl HassUiConfig {
             .unwrap_or(HassLightArchetype::ClassicBulb)
     }
 
+    #[must_use]
+    pub fn effect_map(&self, entity_id: &str) -> BTreeMap<String, String> {
+        self.entity_preferences
+            .get(entity_id)
+            .map(|x| x.effect_map.clone())
+            .unwrap_or_default()
+    }
+
     pub fn room_for_area(&self, area_name: &str) -> Option<String> {
         self.rooms
             .iter()
@@ -547,6 +589,8 @@ impl HassUiConfig {
             name: area_name.to_string(),
             source_area: Some(area_name.to_string()),
             auto_created: true,
+            archetype: None,
+            hue_room_id: None,
         });
         self.normalize();
         room_id
@@ -669,6 +713,9 @@ pub struct HassEntitySummary {
     pub sensor_kind: Option<HassSensorKind>,
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub light_archetype: Option<HassLightArchetype>,
+    /// Effects offered by Home Assistant for this light
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub effect_list: Vec<String>,
     #[serde(default)]
     pub enabled: bool,
 }
@@ -1094,12 +1141,28 @@ impl HassUiState {
             name: name.to_string(),
             source_area: None,
             auto_created: false,
+            archetype: None,
+            hue_room_id: None,
         };
         self.config.rooms.push(room.clone());
         self.config.normalize();
         Some(room)
     }
 
+    /// Add a room created in the Hue app, keeping the id the app knows it by
+    pub fn add_hue_room(
+        &mut self,
+        room_name: &str,
+        hue_room_id: Uuid,
+        archetype: RoomArchetype,
+    ) -> Option<HassRoomConfig> {
+        let id = self.add_room(room_name)?.id;
+        let room = self.config.rooms.iter_mut().find(|room| room.id == id)?;
+        room.hue_room_id = Some(hue_room_id);
+        room.archetype = Some(archetype);
+        Some(room.clone())
+    }
+
     pub fn remove_room(&mut self, room_id: &str) {
         if room_id == HassUiConfig::DEFAULT_ROOM_ID {
             return;
@@ -1133,6 +1196,12 @@ impl HassUiState {
         self.config.normalize();
     }
 
+    pub fn set_room_archetype(&mut self, room_id: &str, archetype: RoomArchetype) {
+        if let Some(room) = self.config.rooms.iter_mut().find(|room| room.id == room_id) {
+            room.archetype = Some(archetype);
+        }
+    }
+
     pub fn set_entity_visibility(&mut self, entity_id: &str, hidden: bool) {
         self.config.set_entity_hidden(entity_id, hidden);
     }
@@ -1167,6 +1236,10 @@ impl HassUiState {
             .set_entity_light_archetype(entity_id, light_archetype);
     }
 
+    pub fn set_entity_effect_map(&mut self, entity_id: &str, effect_map: BTreeMap<String, String>) {
+        self.config.set_entity_effect_map(entity_id, effect_map);
+    }
+
     pub fn visible_logs(&self) -> Vec<String> {
         self.logs.iter().rev().cloned().collect()
     }
@@ -1250,6 +1323,8 @@ pub struct HassEntityPatchRequest {
     pub switch_mode: Option<HassSwitchMode>,
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub light_archetype: Option<HassLightArchetype>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub effect_map: Option<BTreeMap<String, String>>,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize, Eq, PartialEq)]
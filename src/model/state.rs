@@ -1,11 +1,12 @@
 use std::collections::BTreeMap;
 use std::io::Read;
 
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use serde_yml::Value;
 use uuid::Uuid;
 
-use hue::api::{DeviceArchetype, Resource};
+use hue::api::{DeviceArchetype, HueStreamKey, Resource};
 use hue::error::{HueError, HueResult};
 use hue::version::SwVersion;
 
@@ -40,6 +41,34 @@ impl AuxData {
     }
 }
 
+/// An application paired with the bridge. Its username (the v1 username,
+/// and v2 `hue-application-key`) is kept as the key in [`State`].
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct AppKey {
+    /// Reported as `hue-application-id`
+    pub application_id: Uuid,
+    pub devicetype: String,
+    pub create_date: DateTime<Utc>,
+    pub last_use_date: DateTime<Utc>,
+    /// Entertainment stream key, if the application asked for one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub clientkey: Option<HueStreamKey>,
+}
+
+impl AppKey {
+    #[must_use]
+    pub fn new(application_id: Uuid, devicetype: &str, clientkey: Option<HueStreamKey>) -> Self {
+        let now = Utc::now();
+        Self {
+            application_id,
+            devicetype: devicetype.to_string(),
+            create_date: now,
+            last_use_date: now,
+            clientkey,
+        }
+    }
+}
+
 #[derive(Clone, Debug, Default, Serialize, Deserialize)]
 pub struct IdMap {
     forward: BTreeMap<Uuid, u32>,
@@ -74,6 +103,21 @@ impl IdMap {
         id
     }
 
+    /// Like [`Self::add`], but use `preferred` if it is still free
+    pub fn reserve(&mut self, uuid: Uuid, preferred: u32) -> u32 {
+        if let Some(id) = self.forward.get(&uuid).copied() {
+            return id;
+        }
+        if self.reverse.contains_key(&preferred) {
+            return self.add(uuid);
+        }
+
+        self.forward.insert(uuid, preferred);
+        self.reverse.insert(preferred, uuid);
+
+        preferred
+    }
+
     #[must_use]
     pub fn id(&self, uuid: &Uuid) -> Option<u32> {
         self.forward.get(uuid).copied()
@@ -107,6 +151,8 @@ pub struct State {
     aux: BTreeMap<Uuid, AuxData>,
     id_v1: IdMap,
     pub res: BTreeMap<Uuid, Resource>,
+    #[serde(default)]
+    apps: BTreeMap<String, AppKey>,
 }
 
 impl State {
@@ -189,6 +235,7 @@ impl State {
             aux,
             id_v1,
             res,
+            apps: BTreeMap::new(),
         })
     }
 
@@ -212,6 +259,42 @@ impl State {
         self.aux.insert(id, aux);
     }
 
+    /// Clear all resources, keeping the paired applications
+    pub fn reset(&mut self) {
+        *self = Self {
+            apps: std::mem::take(&mut self.apps),
+            ..Self::new()
+        };
+    }
+
+    #[must_use]
+    pub fn app_get(&self, username: &str) -> Option<&AppKey> {
+        self.apps.get(username)
+    }
+
+    pub fn app_get_mut(&mut self, username: &str) -> Option<&mut AppKey> {
+        self.apps.get_mut(username)
+    }
+
+    pub fn app_insert(&mut self, username: String, app: AppKey) {
+        self.apps.insert(username, app);
+    }
+
+    /// Remove an application by its application id
+    pub fn app_remove(&mut self, application_id: &Uuid) -> Option<AppKey> {
+        let username = self
+            .apps
+            .iter()
+            .find(|(_, app)| app.application_id == *application_id)
+            .map(|(username, _)| username.clone())?;
+
+        self.apps.remove(&username)
+    }
+
+    pub fn apps(&self) -> impl Iterator<Item = (&String, &AppKey)> {
+        self.apps.iter()
+    }
+
     #[must_use]
     pub fn try_get(&self, id: &Uuid) -> Option<&Resource> {
         self.res.get(id)
@@ -230,6 +313,12 @@ impl State {
         self.id_v1.add(key);
     }
 
+    /// Give a resource that only exists in the v1 api (like the daylight
+    /// sensor) a lasting id
+    pub fn reserve_id_v1(&mut self, uuid: Uuid, preferred: u32) -> u32 {
+        self.id_v1.reserve(uuid, preferred)
+    }
+
     pub fn remove(&mut self, id: &Uuid) -> ApiResult<()> {
         self.aux.remove(id);
         self.id_v1.remove(id);
@@ -247,3 +336,34 @@ impl State {
         self.id_v1.uuid(id)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use uuid::Uuid;
+
+    use super::IdMap;
+
+    #[test]
+    fn reserved_ids_stay() {
+        let (daylight, light) = (Uuid::new_v4(), Uuid::new_v4());
+
+        let mut ids = IdMap::new();
+        assert_eq!(ids.reserve(daylight, 1), 1);
+        assert_eq!(ids.add(light), 0);
+        assert_eq!(ids.reserve(daylight, 5), 1);
+
+        let ids: IdMap = serde_yml::from_str(&serde_yml::to_string(&ids).unwrap()).unwrap();
+        assert_eq!(ids.id(&daylight), Some(1));
+        assert_eq!(ids.uuid(&1), Some(daylight));
+    }
+
+    #[test]
+    fn reserve_taken_id() {
+        let (daylight, light) = (Uuid::new_v4(), Uuid::new_v4());
+
+        let mut ids = IdMap::new();
+        ids.add(Uuid::new_v4());
+        assert_eq!(ids.reserve(light, 1), 1);
+        assert_eq!(ids.reserve(daylight, 1), 2);
+    }
+}
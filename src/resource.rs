@@ -1,21 +1,25 @@
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::io::{Read, Write};
 use std::sync::Arc;
 
+use chrono::{TimeDelta, Utc};
 use itertools::Itertools;
 use maplit::btreeset;
+use rand::Rng;
+use rand::distr::Alphanumeric;
 use serde::Serialize;
 use serde_json::json;
 use tokio::sync::Notify;
 use tokio::sync::broadcast::{Receiver, Sender};
-use uuid::Uuid;
+use uuid::{Uuid, uuid};
 
 use bifrost_api::backend::BackendRequest;
 use hue::api::{
     Bridge, BridgeHome, Device, DeviceArchetype, DeviceProductData, DimmingUpdate, Entertainment,
-    EntertainmentConfiguration, GroupedLight, Light, Metadata, On, RType, Resource, ResourceLink,
-    ResourceRecord, Room, Stub, TimeZone, ZigbeeConnectivity, ZigbeeConnectivityStatus,
-    ZigbeeDeviceDiscovery, ZigbeeDeviceDiscoveryAction, ZigbeeDeviceDiscoveryStatus, Zone,
+    EntertainmentConfiguration, GroupedLight, HueStreamKey, Light, Metadata, On, RType, Resource,
+    ResourceLink, ResourceRecord, Room, Stub, TimeZone, ZigbeeConnectivity,
+    ZigbeeConnectivityStatus, ZigbeeDeviceDiscovery, ZigbeeDeviceDiscoveryAction,
+    ZigbeeDeviceDiscoveryStatus, Zone,
 };
 use hue::api::{InternetConnectivity, InternetConnectivityStatus};
 use hue::error::{HueError, HueResult};
@@ -23,7 +27,7 @@ use hue::event::EventBlock;
 use hue::version::SwVersion;
 
 use crate::error::ApiResult;
-use crate::model::state::{AuxData, State};
+use crate::model::state::{AppKey, AuxData, State};
 use crate::server::hueevents::HueEventStream;
 
 #[derive(Clone, Debug)]
@@ -38,6 +42,17 @@ pub struct Resources {
 impl Resources {
     const MAX_SCENE_ID: u32 = 100;
     const HUE_EVENTS_BUFFER_SIZE: usize = 128;
+    const APP_USERNAME_LENGTH: usize = 40;
+
+    /// The built-in v1 daylight sensor. It has no v2 resource, only an id.
+    pub const DAYLIGHT_SENSOR: Uuid = uuid!("5d8e9c1a-3f0b-4c7e-9a2d-6b1f0e4a7c35");
+
+    /// Id of the daylight sensor on real bridges
+    const DAYLIGHT_SENSOR_ID_V1: u32 = 1;
+
+    /// The last use of an application is only saved this often, so requests
+    /// do not rewrite the state file every time.
+    const APP_LAST_USE_RESOLUTION: TimeDelta = TimeDelta::minutes(10);
 
     #[allow(clippy::new_without_default)]
     #[must_use]
@@ -96,9 +111,10 @@ impl Resources {
 
     /// Wipe the Hue resource database and re-initialize the bridge core resources.
     ///
-    /// This is intended for "start over" onboarding in the Hue app.
+    /// This is intended for "start over" onboarding in the Hue app. Paired
+    /// applications are kept; they can be revoked separately.
     pub fn factory_reset(&mut self, bridge_id: &str) -> ApiResult<()> {
-        self.state = State::new();
+        self.state.reset();
         self.add_bridge(bridge_id.to_owned())?;
         self.state_updates.notify_one();
         Ok(())
@@ -112,6 +128,9 @@ impl Resources {
         let link_bridge_dev = RType::Device.deterministic(link_bridge.rid);
         let link_ic = RType::InternetConnectivity.deterministic(link_bridge.rid);
 
+        // Older state files did not keep an id for the daylight sensor
+        self.reserve_daylight_sensor();
+
         // If the bridge device doesn't exist yet, there's nothing sensible to patch.
         if self.state.try_get(&link_bridge_dev.rid).is_none() {
             return Ok(());
@@ -135,6 +154,70 @@ impl Resources {
         Ok(())
     }
 
+    /// Pair a new application, and return its username. Applications that
+    /// want to stream get their own entertainment key.
+    pub fn add_app(&mut self, devicetype: &str, generate_clientkey: bool) -> (String, AppKey) {
+        let username: String = rand::rng()
+            .sample_iter(Alphanumeric)
+            .take(Self::APP_USERNAME_LENGTH)
+            .map(char::from)
+            .collect();
+
+        let clientkey = generate_clientkey.then(|| HueStreamKey::new(rand::random()));
+        let app = AppKey::new(Uuid::new_v4(), devicetype, clientkey);
+
+        self.state.app_insert(username.clone(), app.clone());
+        self.state_updates.notify_one();
+
+        (username, app)
+    }
+
+    /// Entertainment stream keys, by PSK identity. Clients use their
+    /// application id, but older (v1) clients use their username.
+    #[must_use]
+    pub fn get_stream_keys(&self) -> HashMap<String, HueStreamKey> {
+        let mut keys = HashMap::new();
+        for (username, app) in self.state.apps() {
+            if let Some(key) = app.clientkey {
+                keys.insert(app.application_id.to_string(), key);
+                keys.insert(username.clone(), key);
+            }
+        }
+        keys
+    }
+
+    #[must_use]
+    pub fn get_app(&self, username: &str) -> Option<&AppKey> {
+        self.state.app_get(username)
+    }
+
+    /// Look up the application making a request, and note its use
+    pub fn use_app(&mut self, username: &str) -> Option<AppKey> {
+        let app = self.state.app_get_mut(username)?;
+
+        let now = Utc::now();
+        if now - app.last_use_date >= Self::APP_LAST_USE_RESOLUTION {
+            app.last_use_date = now;
+            self.state_updates.notify_one();
+        }
+
+        Some(app.clone())
+    }
+
+    pub fn apps(&self) -> impl Iterator<Item = (&String, &AppKey)> {
+        self.state.apps()
+    }
+
+    /// Revoke a paired application
+    pub fn remove_app(&mut self, application_id: &Uuid) -> HueResult<AppKey> {
+        let app = self
+            .state
+            .app_remove(application_id)
+            .ok_or(HueError::NotFound(*application_id))?;
+        self.state_updates.notify_one();
+        Ok(app)
+    }
+
     pub fn aux_get(&self, link: &ResourceLink) -> ApiResult<&AuxData> {
         self.state.aux_get(&link.rid)
     }
@@ -153,6 +236,7 @@ impl Resources {
     {
         let id_v1 = self.id_v1_scope(id, self.state.get(id)?);
         let resource = self.state.get_mut(id)?;
+        let rtype = resource.rtype();
 
         let obj: &mut T = resource.try_into()?;
 
@@ -164,14 +248,17 @@ impl Resources {
         // if the function affected a meaningful difference, send an update event
         if let Some(delta) = hue::diff::event_update_diff(before, after)? {
             log::trace!("Hue event: {id_v1:?} {delta:#?}");
-            self.hue_event_stream.hue_event(EventBlock::update(
-                id,
-                id_v1,
-                resource.rtype(),
-                delta,
-            )?);
+            self.hue_event_stream
+                .hue_event(EventBlock::update(id, id_v1, rtype, delta)?);
 
             self.state_updates.notify_one();
+
+            // Zones span backends, so their grouped lights follow the lights here
+            if rtype == RType::Light {
+                for zone in self.get_zones_for_light(id) {
+                    self.update_zone_grouped_light(&zone)?;
+                }
+            }
         }
 
         Ok(())
@@ -221,6 +308,64 @@ impl Resources {
             .collect()
     }
 
+    #[must_use]
+    pub fn get_zones_for_light(&self, id: &Uuid) -> Vec<Uuid> {
+        let light = RType::Light.link_to(*id);
+        self.state
+            .res
+            .iter()
+            .filter_map(|(k, v)| match v {
+                Resource::Zone(zone) if zone.children.contains(&light) => Some(*k),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Add a zone, together with the grouped light that controls it
+    pub fn add_zone(&mut self, link: &ResourceLink, mut zone: Zone) -> ApiResult<()> {
+        let link_glight = RType::GroupedLight.deterministic(link.rid);
+
+        zone.services.insert(link_glight);
+        self.add(link, Resource::Zone(zone))?;
+        self.add(
+            &link_glight,
+            Resource::GroupedLight(GroupedLight::new(*link)),
+        )?;
+
+        self.update_zone_grouped_light(&link.rid)
+    }
+
+    /// Update the grouped light of a zone from the lights in it: on if any
+    /// light is on, at the average brightness of the lights that are on.
+    pub fn update_zone_grouped_light(&mut self, id: &Uuid) -> ApiResult<()> {
+        let zone: &Zone = self.get_id(*id)?;
+        let Some(glight) = zone.grouped_light_service().copied() else {
+            return Ok(());
+        };
+
+        let lights_on = zone
+            .children
+            .iter()
+            .filter_map(|link| self.get::<Light>(link).ok())
+            .filter(|light| light.on.on)
+            .collect_vec();
+
+        let on = On::new(!lights_on.is_empty());
+        let levels = lights_on
+            .iter()
+            .filter_map(|light| light.dimming.map(|dim| dim.brightness))
+            .collect_vec();
+        let dimming = (!levels.is_empty()).then(|| {
+            let count = u32::try_from(levels.len()).map_or(1.0, f64::from);
+            DimmingUpdate::new(levels.iter().sum::<f64>() / count)
+        });
+
+        self.update::<GroupedLight>(&glight.rid, |glight| {
+            glight.on = Some(on);
+            glight.dimming = dimming;
+        })
+    }
+
     pub fn add(&mut self, link: &ResourceLink, obj: Resource) -> ApiResult<()> {
         assert!(
             link.rtype == obj.rtype(),
@@ -313,7 +458,18 @@ impl Resources {
         Ok(())
     }
 
+    /// Keep an id for the daylight sensor, before other resources take it
+    fn reserve_daylight_sensor(&mut self) {
+        if self.state.id_v1(&Self::DAYLIGHT_SENSOR).is_none() {
+            self.state
+                .reserve_id_v1(Self::DAYLIGHT_SENSOR, Self::DAYLIGHT_SENSOR_ID_V1);
+            self.state_updates.notify_one();
+        }
+    }
+
     pub fn add_bridge(&mut self, bridge_id: String) -> ApiResult<()> {
+        self.reserve_daylight_sensor();
+
         let link_bridge = RType::Bridge.deterministic(&bridge_id);
         let link_bridge_home = RType::BridgeHome.deterministic(format!("{bridge_id}HOME"));
         let link_bridge_dev = RType::Device.deterministic(link_bridge.rid);
@@ -485,6 +641,7 @@ impl Resources {
     zigbee_connectivity       /lights/{id}
     zigbee_connectivity       null
     zigbee_device_discovery   null
+    zone                      /groups/{id}
      */
 
     #[must_use]
@@ -494,14 +651,14 @@ impl Resources {
             Resource::Light(_) => Some(format!("/lights/{id}")),
             Resource::Scene(_) => Some(format!("/scenes/{id}")),
 
-            /* GroupedLights are mapped to their (room) owner's id_v1 */
+            /* GroupedLights are mapped to their (room or zone) owner's id_v1 */
             Resource::GroupedLight(grp) => {
                 let id = self.state.id_v1(&grp.owner.rid)?;
                 Some(format!("/groups/{id}"))
             }
 
-            /* Rooms are mapped directly */
-            Resource::Room(_) => Some(format!("/groups/{id}")),
+            /* Rooms and zones are mapped directly */
+            Resource::Room(_) | Resource::Zone(_) => Some(format!("/groups/{id}")),
 
             /* Devices (that are lights) map to the light service's id_v1 */
             Resource::Device(dev) => dev
@@ -551,8 +708,7 @@ impl Resources {
             | Resource::Temperature(_)
             | Resource::ZgpConnectivity(_)
             | Resource::ZigbeeConnectivity(_)
-            | Resource::ZigbeeDeviceDiscovery(_)
-            | Resource::Zone(_) => None,
+            | Resource::ZigbeeDeviceDiscovery(_) => None,
         }
     }
 
@@ -9,56 +9,104 @@ use log::{info, warn};
 use serde::Serialize;
 use serde_json::{Value, json};
 use tokio::sync::MutexGuard;
+use uuid::Uuid;
 
 use bifrost_api::backend::BackendRequest;
 use hue::api::{
-    Device, DeviceArchetype, Entertainment, EntertainmentConfiguration,
+    Button, Contact, Device, DeviceArchetype, Entertainment, EntertainmentConfiguration,
     EntertainmentConfigurationAction, EntertainmentConfigurationLocationsNew,
     EntertainmentConfigurationMetadata, EntertainmentConfigurationNew,
     EntertainmentConfigurationServiceLocationsNew, EntertainmentConfigurationType,
-    EntertainmentConfigurationUpdate, GroupedLight, GroupedLightUpdate, Light, LightUpdate, RType,
-    ResourceLink, Room, Scene, SceneActive, SceneStatus, SceneUpdate, V1Reply,
+    EntertainmentConfigurationUpdate, GroupedLight, GroupedLightUpdate, Light, LightLevel,
+    LightUpdate, Motion, RType, Resource, ResourceLink, Room, Scene, SceneActive, SceneStatus,
+    SceneUpdate, Temperature, V1Reply, ZigbeeConnectivity, ZigbeeConnectivityStatus, Zone,
 };
 use hue::error::{HueApiV1Error, HueError, HueResult};
 use hue::legacy_api::{
     ApiGroup, ApiGroupAction, ApiGroupActionUpdate, ApiGroupClass, ApiGroupNew, ApiGroupState,
     ApiGroupType, ApiGroupUpdate2, ApiLight, ApiLightStateUpdate, ApiResourceType, ApiScene,
-    ApiSceneAppData, ApiSceneType, ApiSceneVersion, ApiSensor, ApiUserConfig, Capabilities,
-    HueApiResult, NewUser, NewUserReply,
+    ApiSceneAppData, ApiSceneType, ApiSceneVersion, ApiSensor, ApiSensorConfigUpdate,
+    ApiUserConfig, Capabilities, HueApiResult, NewUser, NewUserReply, Whitelist,
 };
 
 use crate::error::{ApiError, ApiResult};
 use crate::resource::Resources;
-use crate::routes::auth::{STANDARD_APPLICATION_ID, STANDARD_CLIENT_KEY};
+use crate::routes::auth::STANDARD_APPLICATION_ID;
 use crate::routes::clip::entertainment_configuration::{self, POSITIONS};
+use crate::routes::clip::sensor;
 use crate::routes::extractor::Json;
 use crate::routes::{ApiV1Error, ApiV1Result};
 use crate::server::appstate::AppState;
 
-async fn get_api_config(State(state): State<AppState>) -> Json<impl Serialize> {
-    match state.api_config("testuser".to_string()).await {
-        Ok(cfg) => Json(serde_json::to_value(cfg).unwrap_or_else(|_| json!({}))),
+/// Config for clients that are not (yet) paired. Paired applications are
+/// left out.
+async fn public_api_config(state: &AppState) -> Value {
+    match state.api_config(HashMap::new()).await {
+        Ok(cfg) => serde_json::to_value(cfg).unwrap_or_else(|_| json!({})),
         Err(_) => {
-            Json(serde_json::to_value(state.api_short_config().await).unwrap_or_else(|_| json!({})))
+            serde_json::to_value(state.api_short_config().await).unwrap_or_else(|_| json!({}))
         }
     }
 }
 
-async fn post_api(bytes: Bytes) -> ApiV1Result<Json<impl Serialize>> {
+async fn get_api_config(State(state): State<AppState>) -> Json<impl Serialize> {
+    Json(public_api_config(&state).await)
+}
+
+async fn post_api(
+    State(state): State<AppState>,
+    bytes: Bytes,
+) -> ApiV1Result<Json<impl Serialize>> {
     info!("post: {bytes:?}");
     let json: NewUser = serde_json::from_slice(&bytes)?;
 
+    if !state.linkbutton_active().await {
+        warn!(
+            "Refusing to pair {:?}: link button not pressed",
+            json.devicetype
+        );
+        return Err(HueApiV1Error::LinkButtonNotPressed)?;
+    }
+
+    let (username, app) = state
+        .res
+        .lock()
+        .await
+        .add_app(&json.devicetype, json.generateclientkey);
+
+    state
+        .hass_ui()
+        .lock()
+        .await
+        .push_log(format!("Paired new app: {}", json.devicetype));
+
     let res = NewUserReply {
-        clientkey: if json.generateclientkey {
-            Some(hex::encode_upper(STANDARD_CLIENT_KEY))
-        } else {
-            None
-        },
-        username: STANDARD_APPLICATION_ID.to_string(),
+        clientkey: app.clientkey.map(hex::encode_upper),
+        username,
     };
     Ok(Json(vec![HueApiResult::Success(res)]))
 }
 
+fn get_whitelist(res: &Resources) -> HashMap<String, Whitelist> {
+    res.apps()
+        .map(|(username, app)| {
+            let entry = Whitelist {
+                create_date: app.create_date,
+                last_use_date: app.last_use_date,
+                name: app.devicetype.clone(),
+            };
+            (username.clone(), entry)
+        })
+        .collect()
+}
+
+/// Devices are unreachable while their zigbee connection has issues
+fn is_reachable(res: &Resources, dev: &Device) -> bool {
+    dev.service(RType::ZigbeeConnectivity)
+        .and_then(|zc| res.get::<ZigbeeConnectivity>(zc).ok())
+        .is_none_or(|zc| zc.status == ZigbeeConnectivityStatus::Connected)
+}
+
 fn get_lights(res: &MutexGuard<Resources>) -> ApiResult<HashMap<String, ApiLight>> {
     let mut lights = HashMap::new();
 
@@ -67,13 +115,102 @@ fn get_lights(res: &MutexGuard<Resources>) -> ApiResult<HashMap<String, ApiLight
         let dev = res.get::<Device>(&light.owner)?;
         lights.insert(
             res.get_id_v1(rr.id)?,
-            ApiLight::from_dev_and_light(&rr.id, dev, &light),
+            ApiLight::from_dev_and_light(&rr.id, dev, &light)
+                .with_reachable(is_reachable(res, dev)),
         );
     }
 
     Ok(lights)
 }
 
+fn get_sensors(res: &MutexGuard<Resources>) -> ApiResult<HashMap<String, ApiSensor>> {
+    let mut sensors = HashMap::new();
+
+    for rr in res.get_resources_by_type(RType::Motion) {
+        let motion: Motion = rr.obj.try_into()?;
+        let Some(dev) = sensor_owner(res, &motion.owner) else {
+            continue;
+        };
+        sensors.insert(
+            res.get_id_v1(rr.id)?,
+            ApiSensor::from_dev_and_motion(&rr.id, dev, &motion),
+        );
+    }
+
+    for rr in res.get_resources_by_type(RType::Temperature) {
+        let temp: Temperature = rr.obj.try_into()?;
+        let Some(dev) = sensor_owner(res, &temp.owner) else {
+            continue;
+        };
+        sensors.insert(
+            res.get_id_v1(rr.id)?,
+            ApiSensor::from_dev_and_temperature(&rr.id, dev, &temp),
+        );
+    }
+
+    for rr in res.get_resources_by_type(RType::LightLevel) {
+        let light_level: LightLevel = rr.obj.try_into()?;
+        let Some(dev) = sensor_owner(res, &light_level.owner) else {
+            continue;
+        };
+        sensors.insert(
+            res.get_id_v1(rr.id)?,
+            ApiSensor::from_dev_and_light_level(&rr.id, dev, &light_level),
+        );
+    }
+
+    for rr in res.get_resources_by_type(RType::Contact) {
+        let contact: Contact = rr.obj.try_into()?;
+        let Some(dev) = sensor_owner(res, &contact.owner) else {
+            continue;
+        };
+        sensors.insert(
+            res.get_id_v1(rr.id)?,
+            ApiSensor::from_dev_and_contact(&rr.id, dev, &contact),
+        );
+    }
+
+    /* v1 has a single switch sensor per device, named after its first button */
+    let mut switches = BTreeMap::<ResourceLink, Vec<(Uuid, Button)>>::new();
+    for rr in res.get_resources_by_type(RType::Button) {
+        let button: Button = rr.obj.try_into()?;
+        switches
+            .entry(button.owner)
+            .or_default()
+            .push((rr.id, button));
+    }
+
+    for (owner, mut buttons) in switches {
+        buttons.sort_by_key(|(_, button)| button.metadata.control_id);
+        let Some(uuid) = buttons.first().map(|(uuid, _)| *uuid) else {
+            continue;
+        };
+        let Some(dev) = sensor_owner(res, &owner) else {
+            continue;
+        };
+        let buttons: Vec<Button> = buttons.into_iter().map(|(_, button)| button).collect();
+        sensors.insert(
+            res.get_id_v1(uuid)?,
+            ApiSensor::from_dev_and_buttons(&uuid, dev, &buttons),
+        );
+    }
+
+    sensors.insert(
+        res.get_id_v1(Resources::DAYLIGHT_SENSOR)?,
+        ApiSensor::builtin_daylight_sensor(),
+    );
+
+    Ok(sensors)
+}
+
+/// Sensors without an owning device are left out, instead of failing the
+/// whole sensor list.
+fn sensor_owner<'a>(res: &'a Resources, owner: &ResourceLink) -> Option<&'a Device> {
+    res.get::<Device>(owner)
+        .inspect_err(|err| warn!("Skipping sensor of missing device {owner:?}: {err}"))
+        .ok()
+}
+
 fn get_groups(res: &MutexGuard<Resources>, group_0: bool) -> ApiResult<HashMap<String, ApiGroup>> {
     let mut rooms = HashMap::new();
 
@@ -109,6 +246,25 @@ fn get_groups(res: &MutexGuard<Resources>, group_0: bool) -> ApiResult<HashMap<S
         );
     }
 
+    for rr in res.get_resources_by_type(RType::Zone) {
+        let zone: Zone = rr.obj.try_into()?;
+        let glight_link = zone
+            .grouped_light_service()
+            .ok_or(HueError::NotFound(rr.id))?;
+
+        let glight = res.get::<GroupedLight>(glight_link)?;
+        let lights: Vec<String> = zone
+            .children
+            .iter()
+            .filter_map(|rl| res.get_id_v1(rl.rid).ok())
+            .collect();
+
+        rooms.insert(
+            res.get_id_v1(rr.id)?,
+            ApiGroup::from_lights_and_zone(glight, lights, zone),
+        );
+    }
+
     for rr in res.get_resources_by_type(RType::EntertainmentConfiguration) {
         let entconf: EntertainmentConfiguration = rr.obj.try_into()?;
 
@@ -227,31 +383,36 @@ async fn get_api_user(
     let lock = state.res.lock().await;
 
     Ok(Json(ApiUserConfig {
-        config: state.api_config(username.clone()).await?,
+        config: state.api_config(get_whitelist(&lock)).await?,
         groups: get_groups(&lock, false)?,
         lights: get_lights(&lock)?,
         resourcelinks: HashMap::new(),
         rules: HashMap::new(),
         scenes: get_scenes(&username, &lock)?,
         schedules: HashMap::new(),
-        sensors: HashMap::from([(1, ApiSensor::builtin_daylight_sensor())]),
+        sensors: get_sensors(&lock)?,
     }))
 }
 
+#[allow(clippy::significant_drop_tightening)]
 async fn get_api_user_resource(
     State(state): State<AppState>,
     Path((username, artype)): Path<(String, ApiResourceType)>,
 ) -> ApiV1Result<Json<Value>> {
     let lock = &state.res.lock().await;
     match artype {
-        ApiResourceType::Config => Ok(Json(json!(state.api_config(username).await?))),
+        // Unknown users get here too, and only see the public config
+        ApiResourceType::Config if lock.get_app(&username).is_none() => {
+            Ok(Json(public_api_config(&state).await))
+        }
+        ApiResourceType::Config => Ok(Json(json!(state.api_config(get_whitelist(lock)).await?))),
         ApiResourceType::Lights => Ok(Json(json!(get_lights(lock)?))),
         ApiResourceType::Groups => Ok(Json(json!(get_groups(lock, false)?))),
         ApiResourceType::Scenes => Ok(Json(json!(get_scenes(&username, lock)?))),
-        ApiResourceType::Resourcelinks
-        | ApiResourceType::Rules
-        | ApiResourceType::Schedules
-        | ApiResourceType::Sensors => Ok(Json(json!({}))),
+        ApiResourceType::Sensors => Ok(Json(json!(get_sensors(lock)?))),
+        ApiResourceType::Resourcelinks | ApiResourceType::Rules | ApiResourceType::Schedules => {
+            Ok(Json(json!({})))
+        }
         ApiResourceType::Capabilities => Ok(Json(json!(Capabilities::new()))),
     }
 }
@@ -363,7 +524,10 @@ async fn get_api_user_resource_id(
             let light = lock.get::<Light>(&link)?;
             let dev = lock.get::<Device>(&light.owner)?;
 
-            json!(ApiLight::from_dev_and_light(&uuid, dev, light))
+            json!(
+                ApiLight::from_dev_and_light(&uuid, dev, light)
+                    .with_reachable(is_reachable(&lock, dev))
+            )
         }
         ApiResourceType::Scenes => {
             let lock = state.res.lock().await;
@@ -382,6 +546,15 @@ async fn get_api_user_resource_id(
 
             json!(group)
         }
+        ApiResourceType::Sensors => {
+            let lock = state.res.lock().await;
+            let mut sensors = get_sensors(&lock)?;
+            let sensor = sensors
+                .remove(&id.to_string())
+                .ok_or(HueError::V1NotFound(id))?;
+
+            json!(sensor)
+        }
         _ => Err(HueError::V1NotFound(id))?,
     };
 
@@ -488,10 +661,13 @@ async fn put_api_user_resource_id_path(
             let lock = state.res.lock().await;
 
             let uuid = lock.from_id_v1(id)?;
-            let link = ResourceLink::new(uuid, RType::Room);
-
-            let room: &Room = lock.get(&link)?;
-            let glight = room.grouped_light_service().unwrap();
+            let group = lock.get_resource_by_id(&uuid)?.obj;
+            let glight = match &group {
+                Resource::Room(room) => room.grouped_light_service(),
+                Resource::Zone(zone) => zone.grouped_light_service(),
+                _ => None,
+            }
+            .ok_or(HueError::V1NotFound(id))?;
 
             let updv1: ApiGroupActionUpdate = serde_json::from_value(req)?;
 
@@ -554,12 +730,33 @@ async fn put_api_user_resource_id_path(
             Ok(Json(reply.json()))
         }
 
+        /* only the "on" flag of sensor configs can be changed */
+        ApiResourceType::Sensors => {
+            if path != "config" {
+                return Err(HueError::V1NotFound(id))?;
+            }
+
+            let upd: ApiSensorConfigUpdate = serde_json::from_value(req)?;
+            let mut reply = V1Reply::for_sensor_path(id, &path);
+
+            if let Some(on) = upd.on {
+                let mut lock = state.res.lock().await;
+                let uuid = lock.from_id_v1(id)?;
+                let rtype = lock.get_resource_by_id(&uuid)?.obj.rtype();
+                sensor::set_sensor_enabled(&mut lock, rtype.link_to(uuid), on)?;
+                drop(lock);
+
+                reply = reply.add("on", on)?;
+            }
+
+            Ok(Json(reply.json()))
+        }
+
         ApiResourceType::Config
         | ApiResourceType::Resourcelinks
         | ApiResourceType::Rules
         | ApiResourceType::Scenes
         | ApiResourceType::Schedules
-        | ApiResourceType::Sensors
         | ApiResourceType::Capabilities => Err(ApiV1Error::V1CreateUnsupported(artype)),
     }
 }
@@ -591,3 +788,72 @@ pub fn router() -> Router<AppState> {
             put(put_api_user_resource_id_path),
         )
 }
+
+#[cfg(test)]
+mod tests {
+    use axum::body::{Body, to_bytes};
+    use axum::http::Request;
+    use maplit::btreeset;
+    use serde_json::Value;
+    use tower::ServiceExt;
+
+    use hue::api::{
+        Device, DeviceArchetype, DeviceProductData, Metadata, RType, Resource, ZigbeeConnectivity,
+        ZigbeeConnectivityStatus,
+    };
+    use hue::version::SwVersion;
+
+    use crate::routes::api::is_reachable;
+    use crate::routes::router;
+    use crate::server::appstate::AppState;
+
+    #[tokio::test]
+    async fn daylight_sensor_keeps_id_1() {
+        let state = AppState::for_tests().unwrap();
+        let username = state.res.lock().await.add_app("test#bifrost", false).0;
+
+        let req = Request::get(format!("/api/{username}/sensors"))
+            .body(Body::empty())
+            .unwrap();
+        let resp = router(state.clone()).oneshot(req).await.unwrap();
+        let body = to_bytes(resp.into_body(), usize::MAX).await.unwrap();
+        let sensors: Value = serde_json::from_slice(&body).unwrap();
+
+        assert_eq!(sensors["1"]["type"], "Daylight");
+    }
+
+    #[tokio::test]
+    async fn reachable_follows_zigbee_connectivity() {
+        let state = AppState::for_tests().unwrap();
+        let mut res = state.res.lock().await;
+
+        let link_device = RType::Device.deterministic("0x0017880100000001");
+        let link_zc = RType::ZigbeeConnectivity.deterministic("0x0017880100000001");
+        let mut dev = Device {
+            product_data: DeviceProductData::hue_bridge_v2(&SwVersion::default()),
+            metadata: Metadata::new(DeviceArchetype::ClassicBulb, "Lamp"),
+            services: btreeset![],
+            usertest: None,
+            identify: None,
+        };
+        assert!(is_reachable(&res, &dev));
+
+        dev.services.insert(link_zc);
+        let zc = ZigbeeConnectivity {
+            channel: None,
+            extended_pan_id: None,
+            mac_address: "00:17:88:01:00:00:00:01".into(),
+            owner: link_device,
+            status: ZigbeeConnectivityStatus::Connected,
+        };
+        res.add(&link_zc, Resource::ZigbeeConnectivity(zc)).unwrap();
+        assert!(is_reachable(&res, &dev));
+
+        res.update::<ZigbeeConnectivity>(&link_zc.rid, |zc| {
+            zc.status = ZigbeeConnectivityStatus::ConnectivityIssue;
+        })
+        .unwrap();
+        assert!(!is_reachable(&res, &dev));
+        drop(res);
+    }
+}
@@ -1,29 +1,231 @@
-use axum::Router;
-use axum::http::HeaderValue;
-use axum::response::IntoResponse;
+use axum::extract::{RawPathParams, Request, State};
+use axum::http::Method;
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
 use axum::routing::get;
-use hyper::HeaderMap;
+use axum::{Extension, Router};
 use serde_json::json;
 
-use hue::api::HueStreamKey;
+use hue::error::HueApiV1Error;
 
+use crate::error::ApiError;
+use crate::model::state::AppKey;
+use crate::routes::ApiV1Error;
 use crate::routes::extractor::Json;
 use crate::server::appstate::AppState;
 
 pub const STANDARD_APPLICATION_ID: &str = "01010101-0202-0303-0404-050505050505";
 
-/// This 16-byte key is used for all DTLS entertainment streams
-pub const STANDARD_CLIENT_KEY: HueStreamKey = HueStreamKey::new(*b"BifrostHueTlsKey");
+pub async fn auth_v1(Extension(app): Extension<AppKey>) -> impl IntoResponse {
+    (
+        [("hue-application-id", app.application_id.to_string())],
+        Json(json!({})),
+    )
+}
 
-pub async fn auth_v1() -> impl IntoResponse {
-    let value = HeaderValue::from_static(STANDARD_APPLICATION_ID);
+/// Reject v2 requests without a known `hue-application-key`. The paired
+/// application is passed on to the handlers as an [`Extension`].
+pub async fn require_app_key(
+    State(state): State<AppState>,
+    mut req: Request,
+    next: Next,
+) -> Response {
+    let app = match req
+        .headers()
+        .get("hue-application-key")
+        .and_then(|value| value.to_str().ok())
+    {
+        Some(key) => state.res.lock().await.use_app(key),
+        None => None,
+    };
 
-    let mut headers = HeaderMap::new();
-    headers.append("hue-application-id", value);
+    let Some(app) = app else {
+        return ApiError::Unauthorized.into_response();
+    };
 
-    (headers, Json(json!({})))
+    req.extensions_mut().insert(app);
+    next.run(req).await
+}
+
+/// Reject v1 requests for unknown usernames.
+///
+/// Like on a real bridge, `GET /api/<username>/config` is still answered for
+/// unknown usernames, with the public part of the config only.
+pub async fn require_username(
+    State(state): State<AppState>,
+    params: RawPathParams,
+    req: Request,
+    next: Next,
+) -> Response {
+    let param = |name: &str| {
+        params
+            .iter()
+            .find(|(key, _)| *key == name)
+            .map(|(_, value)| value)
+    };
+
+    let Some(username) = param("user") else {
+        return next.run(req).await;
+    };
+
+    let public =
+        req.method() == Method::GET && param("rtype") == Some("config") && param("id").is_none();
+
+    if public || state.res.lock().await.use_app(username).is_some() {
+        return next.run(req).await;
+    }
+
+    ApiV1Error::from(HueApiV1Error::UnauthorizedUser).into_response()
 }
 
 pub fn router() -> Router<AppState> {
     Router::new().route("/v1", get(auth_v1))
 }
+
+#[cfg(test)]
+mod tests {
+    use axum::body::{Body, to_bytes};
+    use axum::http::{Request, StatusCode};
+    use serde_json::{Value, json};
+    use tower::ServiceExt;
+
+    use hue::error::HueApiV1Error;
+
+    use crate::routes::router;
+    use crate::server::appstate::AppState;
+
+    async fn send(state: &AppState, req: Request<Body>) -> (StatusCode, Value) {
+        let resp = router(state.clone()).oneshot(req).await.unwrap();
+        let status = resp.status();
+        let body = to_bytes(resp.into_body(), usize::MAX).await.unwrap();
+        (status, serde_json::from_slice(&body).unwrap())
+    }
+
+    fn get(uri: &str) -> Request<Body> {
+        Request::get(uri).body(Body::empty()).unwrap()
+    }
+
+    fn get_with_key(uri: &str, key: &str) -> Request<Body> {
+        Request::get(uri)
+            .header("hue-application-key", key)
+            .body(Body::empty())
+            .unwrap()
+    }
+
+    fn v1_error_type(body: &Value) -> Option<u64> {
+        body[0]["error"]["type"].as_u64()
+    }
+
+    async fn pair(state: &AppState) -> String {
+        state.res.lock().await.add_app("test#bifrost", false).0
+    }
+
+    #[tokio::test]
+    async fn v1_unknown_username() {
+        let state = AppState::for_tests().unwrap();
+
+        let (status, body) = send(&state, get("/api/nobody/lights")).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(
+            v1_error_type(&body),
+            Some(HueApiV1Error::UnauthorizedUser.error_code().into())
+        );
+
+        let username = pair(&state).await;
+        let (status, body) = send(&state, get(&format!("/api/{username}/lights"))).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(v1_error_type(&body), None);
+    }
+
+    #[tokio::test]
+    async fn v1_public_config_for_unknown_username() {
+        let state = AppState::for_tests().unwrap();
+        pair(&state).await;
+
+        let (status, body) = send(&state, get("/api/nobody/config")).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(v1_error_type(&body), None);
+        assert!(body["bridgeid"].is_string());
+        assert!(
+            body.get("whitelist")
+                .is_none_or(|whitelist| whitelist == &json!({}))
+        );
+
+        let (_, body) = send(&state, get("/api/nobody/config/whitelist")).await;
+        assert_eq!(
+            v1_error_type(&body),
+            Some(HueApiV1Error::UnauthorizedUser.error_code().into())
+        );
+    }
+
+    #[tokio::test]
+    async fn v1_pairing_needs_link_button() {
+        let state = AppState::for_tests().unwrap();
+        let new_user = || {
+            Request::post("/api")
+                .header("content-type", "application/json")
+                .body(Body::from(r#"{"devicetype": "test#bifrost"}"#))
+                .unwrap()
+        };
+
+        let (status, body) = send(&state, new_user()).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(
+            v1_error_type(&body),
+            Some(HueApiV1Error::LinkButtonNotPressed.error_code().into())
+        );
+
+        state
+            .press_linkbutton(std::time::Duration::from_secs(30))
+            .await;
+        let (_, body) = send(&state, new_user()).await;
+        assert!(body[0]["success"]["username"].is_string());
+    }
+
+    #[tokio::test]
+    async fn v1_pairing_mints_stream_keys() {
+        let state = AppState::for_tests().unwrap();
+        state
+            .press_linkbutton(std::time::Duration::from_secs(30))
+            .await;
+        let new_user = |generateclientkey: bool| {
+            let body =
+                json!({"devicetype": "test#bifrost", "generateclientkey": generateclientkey});
+            Request::post("/api")
+                .header("content-type", "application/json")
+                .body(Body::from(body.to_string()))
+                .unwrap()
+        };
+
+        let (_, first) = send(&state, new_user(true)).await;
+        let (_, second) = send(&state, new_user(true)).await;
+        let (_, plain) = send(&state, new_user(false)).await;
+
+        let first = first[0]["success"]["clientkey"].as_str().unwrap();
+        let second = second[0]["success"]["clientkey"].as_str().unwrap();
+        assert_eq!(first.len(), 32);
+        assert_ne!(first, second);
+        assert!(plain[0]["success"].get("clientkey").is_none());
+
+        let keys = state.res.lock().await.get_stream_keys();
+        let username = plain[0]["success"]["username"].as_str().unwrap();
+        assert!(!keys.contains_key(username));
+    }
+
+    #[tokio::test]
+    async fn v2_requires_app_key() {
+        let state = AppState::for_tests().unwrap();
+        let uri = "/clip/v2/resource/light";
+
+        let (status, _) = send(&state, get(uri)).await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+
+        let (status, _) = send(&state, get_with_key(uri, "not-a-paired-key")).await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+
+        let key = pair(&state).await;
+        let (status, body) = send(&state, get_with_key(uri, &key)).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["errors"], json!([]));
+    }
+}
@@ -0,0 +1,73 @@
+use std::cmp::Reverse;
+
+use axum::Router;
+use axum::extract::{Path, State};
+use axum::routing::{delete, get};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use uuid::Uuid;
+
+use crate::model::state::AppKey;
+use crate::routes::bifrost::BifrostApiResult;
+use crate::routes::extractor::Json;
+use crate::server::appstate::AppState;
+
+/// A paired application, as shown in the UI. Usernames and client keys are
+/// secret, so applications are listed (and revoked) by application id.
+#[derive(Debug, Serialize)]
+struct AppInfo {
+    application_id: Uuid,
+    devicetype: String,
+    create_date: DateTime<Utc>,
+    last_use_date: DateTime<Utc>,
+    entertainment: bool,
+}
+
+impl From<&AppKey> for AppInfo {
+    fn from(app: &AppKey) -> Self {
+        Self {
+            application_id: app.application_id,
+            devicetype: app.devicetype.clone(),
+            create_date: app.create_date,
+            last_use_date: app.last_use_date,
+            entertainment: app.clientkey.is_some(),
+        }
+    }
+}
+
+/// Paired applications, most recently used first
+async fn get_apps(State(state): State<AppState>) -> BifrostApiResult<Json<Vec<AppInfo>>> {
+    let mut apps: Vec<AppInfo> = state
+        .res
+        .lock()
+        .await
+        .apps()
+        .map(|(_, app)| app.into())
+        .collect();
+
+    apps.sort_by_key(|app| Reverse(app.last_use_date));
+
+    Ok(Json(apps))
+}
+
+async fn delete_app(
+    State(state): State<AppState>,
+    Path(application_id): Path<Uuid>,
+) -> BifrostApiResult<Json<AppInfo>> {
+    let app = state.res.lock().await.remove_app(&application_id)?;
+
+    log::info!("Revoked app {} ({application_id})", app.devicetype);
+    state
+        .hass_ui()
+        .lock()
+        .await
+        .push_log(format!("Revoked app: {}", app.devicetype));
+
+    Ok(Json((&app).into()))
+}
+
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route("/", get(get_apps))
+        .route("/{application_id}", delete(delete_app))
+}
@@ -172,6 +172,10 @@ async fn patch_entity(
         lock.set_entity_light_archetype(&req.entity_id, Some(archetype));
         trigger_upsert = true;
     }
+    if let Some(effect_map) = req.effect_map {
+        lock.set_entity_effect_map(&req.entity_id, effect_map);
+        trigger_upsert = true;
+    }
 
     lock.persist_and_log(&format!("Updated entity {}", req.entity_id))?;
     let cfg = lock.config_normalized();
@@ -195,7 +199,7 @@ async fn patch_entity(
         }
         summary.hidden = cfg.is_manually_hidden(&summary.entity_id);
         let mut included = cfg.should_include(&summary.entity_id, &summary.name, summary.available);
-        if summary.domain == "binary_sensor" {
+        if matches!(summary.domain.as_str(), "binary_sensor" | "sensor") {
             let detected = summary.sensor_kind.unwrap_or(HassSensorKind::Ignore);
             let selected = cfg.sensor_kind(&summary.entity_id, detected);
             summary.sensor_kind = Some(selected);
@@ -348,7 +352,7 @@ async fn get_bridge_info(State(state): State<AppState>) -> BifrostApiResult<Json
             .iter()
             .filter(|ent| {
                 let mut include = cfg.should_include(&ent.entity_id, &ent.name, ent.available);
-                if ent.domain == "binary_sensor" {
+                if matches!(ent.domain.as_str(), "binary_sensor" | "sensor") {
                     let detected = ent.sensor_kind.unwrap_or(HassSensorKind::Ignore);
                     if matches!(
                         cfg.sensor_kind(&ent.entity_id, detected),
@@ -450,7 +454,7 @@ async fn post_apply(State(state): State<AppState>) -> BifrostApiResult<Json<Hass
     let mut keep_device_rids = HashSet::new();
     for ent in &entities {
         let mut include = cfg.should_include(&ent.entity_id, &ent.name, ent.available);
-        if ent.domain == "binary_sensor" {
+        if matches!(ent.domain.as_str(), "binary_sensor" | "sensor") {
             let detected = ent.sensor_kind.unwrap_or(HassSensorKind::Ignore);
             if matches!(
                 cfg.sensor_kind(&ent.entity_id, detected),
@@ -1,3 +1,4 @@
+pub mod apps;
 pub mod backend;
 pub mod hass;
 pub mod service;
@@ -54,6 +55,7 @@ pub fn router() -> Router<AppState> {
     Router::new()
         .nest("/service", service::router())
         .nest("/backend", backend::router())
+        .nest("/apps", apps::router())
         .merge(hass::router())
         .route("/config", get(get_config))
         .route("/ws", any(websocket))
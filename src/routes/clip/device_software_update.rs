@@ -0,0 +1,28 @@
+use bifrost_api::backend::BackendRequest;
+use serde_json::Value;
+
+use hue::api::{DeviceSoftwareUpdate, DeviceSoftwareUpdateUpdate, ResourceLink};
+
+use crate::routes::V2Reply;
+use crate::routes::clip::ApiV2Result;
+use crate::server::appstate::AppState;
+
+pub async fn put_device_software_update(
+    state: &AppState,
+    rlink: ResourceLink,
+    put: Value,
+) -> ApiV2Result {
+    let upd: DeviceSoftwareUpdateUpdate = serde_json::from_value(put)?;
+
+    let lock = state.res.lock().await;
+
+    /* check that the resource exists, otherwise we should return 404 */
+    lock.get::<DeviceSoftwareUpdate>(&rlink)?;
+
+    if upd.install == Some(true) {
+        lock.backend_request(BackendRequest::SoftwareUpdateInstall(rlink))?;
+    }
+    drop(lock);
+
+    V2Reply::ok(rlink)
+}
@@ -1,4 +1,5 @@
 pub mod device;
+pub mod device_software_update;
 pub mod entertainment_configuration;
 pub mod grouped_light;
 pub mod light;
@@ -6,6 +7,7 @@ pub mod room;
 pub mod scene;
 pub mod sensor;
 pub mod zigbee_device_discovery;
+pub mod zone;
 
 use bifrost_api::backend::BackendRequest;
 use entertainment_configuration as ent_conf;
@@ -83,15 +85,15 @@ async fn post_resource(
 
     match rtype {
         RType::EntertainmentConfiguration => ent_conf::post_resource(&state, req).await,
+        RType::Room => room::post_room(&state, req).await,
         RType::Scene => scene::post_scene(&state, req).await,
+        RType::Zone => zone::post_zone(&state, req).await,
 
         /* Not supported yet by Bifrost */
         RType::BehaviorInstance
         | RType::GeofenceClient
-        | RType::Room
         | RType::ServiceGroup
-        | RType::SmartScene
-        | RType::Zone => {
+        | RType::SmartScene => {
             let err = ApiError::CreateNotYetSupported(rtype);
             log::warn!("{err}");
             Err(err)
@@ -154,15 +156,21 @@ async fn put_resource_id(
     match rlink.rtype {
         /* Allowed + supported */
         RType::Device => device::put_device(&state, rlink, put).await,
+        RType::DeviceSoftwareUpdate => {
+            device_software_update::put_device_software_update(&state, rlink, put).await
+        }
         RType::EntertainmentConfiguration => ent_conf::put_resource_id(&state, rlink, put).await,
         RType::GroupedLight => grouped_light::put_grouped_light(&state, rlink, put).await,
         RType::Light => light::put_light(&state, rlink, put).await,
-        RType::Motion | RType::Contact => sensor::put_sensor(&state, rlink, put).await,
+        RType::Motion | RType::Contact | RType::Temperature | RType::LightLevel => {
+            sensor::put_sensor(&state, rlink, put).await
+        }
         RType::Scene => scene::put_scene(&state, rlink, put).await,
         RType::Room => room::put_room(&state, rlink, put).await,
         RType::ZigbeeDeviceDiscovery => {
             zigbee_device_discovery::put_zigbee_device_discovery(&state, rlink, put).await
         }
+        RType::Zone => zone::put_zone(&state, rlink, put).await,
 
         /* Allowed, but support is missing in Bifrost */
         RType::BehaviorInstance
@@ -170,7 +178,6 @@ async fn put_resource_id(
         | RType::Button
         | RType::CameraMotion
         | RType::DevicePower
-        | RType::DeviceSoftwareUpdate
         | RType::Entertainment
         | RType::GeofenceClient
         | RType::Geolocation
@@ -178,15 +185,12 @@ async fn put_resource_id(
         | RType::GroupedMotion
         | RType::Homekit
         | RType::InternetConnectivity
-        | RType::LightLevel
         | RType::Matter
         | RType::RelativeRotary
         | RType::ServiceGroup
         | RType::SmartScene
-        | RType::Temperature
         | RType::ZgpConnectivity
-        | RType::ZigbeeConnectivity
-        | RType::Zone => {
+        | RType::ZigbeeConnectivity => {
             /* check that the resource exists, otherwise we should return 404 */
             state.res.lock().await.get_resource(&rlink)?;
 
@@ -218,6 +222,10 @@ async fn delete_resource_id(
     log::info!("DELETE {rlink:?}");
 
     match rlink.rtype {
+        /* Allowed (handled by Bifrost) */
+        RType::Scene => scene::delete_scene(&state, rlink).await,
+        RType::Zone => zone::delete_zone(&state, rlink).await,
+
         /* Allowed (send request to backend) */
         RType::BehaviorInstance
         | RType::Device
@@ -225,10 +233,8 @@ async fn delete_resource_id(
         | RType::GeofenceClient
         | RType::MatterFabric
         | RType::Room
-        | RType::Scene
         | RType::ServiceGroup
-        | RType::SmartScene
-        | RType::Zone => {
+        | RType::SmartScene => {
             let lock = state.res.lock().await;
 
             /* check that the resource exists, otherwise we should return 404 */
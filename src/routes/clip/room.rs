@@ -1,20 +1,44 @@
 use serde_json::Value;
+use uuid::Uuid;
 
 use bifrost_api::backend::BackendRequest;
-use hue::api::{ResourceLink, Room, RoomUpdate};
+use hue::api::{Device, RType, ResourceLink, Room, RoomUpdate};
 
+use crate::error::ApiError;
 use crate::routes::clip::{ApiV2Result, V2Reply};
 use crate::server::appstate::AppState;
 
+/// Rooms hold devices, so the backend owning those devices creates the room
+/// (and its grouped light) under the link returned here.
+pub async fn post_room(state: &AppState, req: Value) -> ApiV2Result {
+    let room: Room = serde_json::from_value(req)?;
+
+    if room.metadata.name.trim().is_empty() {
+        return Err(ApiError::EmptyName(RType::Room));
+    }
+
+    let lock = state.res.lock().await;
+    for child in &room.children {
+        lock.get::<Device>(child)?;
+    }
+
+    let link = ResourceLink::new(Uuid::new_v4(), RType::Room);
+    lock.backend_request(BackendRequest::RoomCreate(link, room))?;
+
+    drop(lock);
+
+    V2Reply::ok(link)
+}
+
 pub async fn put_room(state: &AppState, rlink: ResourceLink, put: Value) -> ApiV2Result {
     let mut lock = state.res.lock().await;
     lock.get::<Room>(&rlink)?;
 
-    let mut upd: RoomUpdate = serde_json::from_value(put)?;
+    let upd: RoomUpdate = serde_json::from_value(put)?;
 
-    if let Some(metadata) = upd.metadata.take() {
+    if let Some(metadata) = &upd.metadata {
         lock.update(&rlink.rid, |room: &mut Room| {
-            room.metadata += &metadata;
+            room.metadata += metadata;
         })?;
     }
 
@@ -24,3 +48,31 @@ pub async fn put_room(state: &AppState, rlink: ResourceLink, put: Value) -> ApiV
 
     V2Reply::ok(rlink)
 }
+
+#[cfg(test)]
+mod tests {
+    use hue::api::RType;
+    use serde_json::json;
+
+    use crate::error::ApiError;
+    use crate::routes::clip::room::post_room;
+    use crate::server::appstate::AppState;
+
+    #[tokio::test]
+    async fn blank_room_name_is_rejected() {
+        let state = AppState::for_tests().unwrap();
+        let _requests = state.res.lock().await.backend_event_stream();
+
+        for name in ["", "   "] {
+            let room = json!({
+                "children": [],
+                "metadata": {"name": name, "archetype": "office"},
+                "services": [],
+            });
+            assert!(matches!(
+                post_room(&state, room).await,
+                Err(ApiError::EmptyName(RType::Room))
+            ));
+        }
+    }
+}
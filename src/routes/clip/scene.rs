@@ -1,21 +1,30 @@
 use serde_json::Value;
 
 use bifrost_api::backend::BackendRequest;
-use hue::api::{RType, ResourceLink, Scene, SceneUpdate};
+use hue::api::{RType, Resource, ResourceLink, Scene, SceneUpdate, Zone};
 
+use crate::model::state::AuxData;
 use crate::routes::clip::{ApiV2Result, V2Reply};
 use crate::server::appstate::AppState;
 
 pub async fn post_scene(state: &AppState, req: Value) -> ApiV2Result {
     let scene: Scene = serde_json::from_value(req)?;
 
-    let lock = state.res.lock().await;
+    let mut lock = state.res.lock().await;
 
     let sid = lock.get_next_scene_id(&scene.group)?;
 
     let link_scene = RType::Scene.deterministic((scene.group.rid, sid));
 
-    lock.backend_request(BackendRequest::SceneCreate(link_scene, sid, scene))?;
+    if scene.group.rtype == RType::Zone {
+        // Zone scenes can span backends, so Bifrost keeps them itself. The
+        // backends only recall them, each for their own lights.
+        lock.get::<Zone>(&scene.group)?;
+        lock.aux_set(&link_scene, AuxData::new().with_index(sid));
+        lock.add(&link_scene, Resource::Scene(scene))?;
+    } else {
+        lock.backend_request(BackendRequest::SceneCreate(link_scene, sid, scene))?;
+    }
 
     drop(lock);
 
@@ -31,10 +40,32 @@ pub async fn put_scene(state: &AppState, rlink: ResourceLink, put: Value) -> Api
         lock.update::<Scene>(&rlink.rid, |scn| scn.metadata += md)?;
     }
 
-    let _scene = lock.get::<Scene>(&rlink)?;
+    let scene = lock.get::<Scene>(&rlink)?;
+
+    if scene.group.rtype == RType::Zone && upd.recall.is_none() {
+        lock.update::<Scene>(&rlink.rid, |scn| *scn += &upd)?;
+    }
 
     lock.backend_request(BackendRequest::SceneUpdate(rlink, upd))?;
     drop(lock);
 
     V2Reply::ok(rlink)
 }
+
+pub async fn delete_scene(state: &AppState, rlink: ResourceLink) -> ApiV2Result {
+    let mut lock = state.res.lock().await;
+
+    /* check that the resource exists, otherwise we should return 404 */
+    let scene = lock.get::<Scene>(&rlink)?;
+
+    if scene.group.rtype == RType::Zone {
+        lock.delete(&rlink)?;
+    } else {
+        /* request deletion from backend */
+        lock.backend_request(BackendRequest::Delete(rlink))?;
+    }
+
+    drop(lock);
+
+    V2Reply::ok(rlink)
+}
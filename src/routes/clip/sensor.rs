@@ -1,9 +1,10 @@
 use serde_json::Value;
 
 use bifrost_api::backend::BackendRequest;
-use hue::api::{Motion, RType, ResourceLink};
+use hue::api::{Contact, LightLevel, Motion, RType, ResourceLink, Temperature};
 
-use crate::error::ApiError;
+use crate::error::{ApiError, ApiResult};
+use crate::resource::Resources;
 use crate::routes::V2Reply;
 use crate::routes::clip::ApiV2Result;
 use crate::server::appstate::AppState;
@@ -24,10 +25,12 @@ fn parse_enabled(put: &Value) -> Result<bool, ApiError> {
     Err(ApiError::UpdateNotYetSupported(RType::Motion))
 }
 
-pub async fn put_sensor(state: &AppState, rlink: ResourceLink, put: Value) -> ApiV2Result {
-    let enabled = parse_enabled(&put)?;
-
-    let mut lock = state.res.lock().await;
+/// Enable or disable a sensor service, and pass the change on to its backend
+pub fn set_sensor_enabled(
+    lock: &mut Resources,
+    rlink: ResourceLink,
+    enabled: bool,
+) -> ApiResult<()> {
     match rlink.rtype {
         RType::Motion => {
             let _ = lock.get::<Motion>(&rlink)?;
@@ -36,21 +39,34 @@ pub async fn put_sensor(state: &AppState, rlink: ResourceLink, put: Value) -> Ap
             })?;
         }
         RType::Contact => {
-            let record = lock.get_resource(&rlink)?;
-            let mut raw = match record.obj {
-                hue::api::Resource::Contact(value) => value,
-                _ => return Err(ApiError::UpdateNotYetSupported(RType::Contact)),
-            };
-            if let Some(map) = raw.as_object_mut() {
-                map.insert("enabled".to_string(), Value::Bool(enabled));
-            }
-            let _ = lock.delete(&rlink);
-            lock.add(&rlink, hue::api::Resource::Contact(raw))?;
+            let _ = lock.get::<Contact>(&rlink)?;
+            lock.update::<Contact>(&rlink.rid, |contact| {
+                contact.enabled = enabled;
+            })?;
+        }
+        RType::Temperature => {
+            let _ = lock.get::<Temperature>(&rlink)?;
+            lock.update::<Temperature>(&rlink.rid, |temp| {
+                temp.enabled = enabled;
+            })?;
+        }
+        RType::LightLevel => {
+            let _ = lock.get::<LightLevel>(&rlink)?;
+            lock.update::<LightLevel>(&rlink.rid, |light_level| {
+                light_level.enabled = enabled;
+            })?;
         }
         _ => return Err(ApiError::UpdateNotYetSupported(rlink.rtype)),
     }
 
-    lock.backend_request(BackendRequest::SensorEnabledUpdate(rlink, enabled))?;
+    lock.backend_request(BackendRequest::SensorEnabledUpdate(rlink, enabled))
+}
+
+pub async fn put_sensor(state: &AppState, rlink: ResourceLink, put: Value) -> ApiV2Result {
+    let enabled = parse_enabled(&put)?;
+
+    let mut lock = state.res.lock().await;
+    set_sensor_enabled(&mut lock, rlink, enabled)?;
     drop(lock);
 
     V2Reply::ok(rlink)
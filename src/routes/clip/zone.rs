@@ -0,0 +1,70 @@
+use std::collections::BTreeSet;
+
+use serde_json::Value;
+use uuid::Uuid;
+
+use hue::api::{Light, RType, ResourceLink, Zone, ZoneUpdate};
+
+use crate::error::ApiResult;
+use crate::resource::Resources;
+use crate::routes::clip::{ApiV2Result, V2Reply};
+use crate::server::appstate::AppState;
+
+/// Zones can only hold lights that exist
+fn check_children(res: &Resources, children: &BTreeSet<ResourceLink>) -> ApiResult<()> {
+    for child in children {
+        res.get::<Light>(child)?;
+    }
+    Ok(())
+}
+
+/// Zones span lights from all backends, so they are managed here, and not
+/// by any single backend.
+pub async fn post_zone(state: &AppState, req: Value) -> ApiV2Result {
+    let new: Zone = serde_json::from_value(req)?;
+
+    let mut lock = state.res.lock().await;
+    check_children(&lock, &new.children)?;
+
+    let link = ResourceLink::new(Uuid::new_v4(), RType::Zone);
+    lock.add_zone(&link, Zone::new(new.metadata, new.children))?;
+
+    drop(lock);
+
+    V2Reply::ok(link)
+}
+
+pub async fn put_zone(state: &AppState, rlink: ResourceLink, put: Value) -> ApiV2Result {
+    let upd: ZoneUpdate = serde_json::from_value(put)?;
+
+    let mut lock = state.res.lock().await;
+    lock.get::<Zone>(&rlink)?;
+
+    if let Some(children) = &upd.children {
+        check_children(&lock, children)?;
+    }
+
+    lock.update(&rlink.rid, |zone: &mut Zone| *zone += &upd)?;
+    lock.update_zone_grouped_light(&rlink.rid)?;
+
+    drop(lock);
+
+    V2Reply::ok(rlink)
+}
+
+/// Delete a zone, and the scenes made for it
+pub async fn delete_zone(state: &AppState, rlink: ResourceLink) -> ApiV2Result {
+    let mut lock = state.res.lock().await;
+    lock.get::<Zone>(&rlink)?;
+
+    for scene in lock.get_scenes_for_room(&rlink.rid) {
+        lock.delete(&RType::Scene.link_to(scene))?;
+    }
+
+    // The grouped light is owned by the zone, and goes with it
+    lock.delete(&rlink)?;
+
+    drop(lock);
+
+    V2Reply::ok(rlink)
+}
@@ -1,6 +1,6 @@
-use axum::Router;
 use axum::extract::DefaultBodyLimit;
 use axum::response::{IntoResponse, Response};
+use axum::{Router, middleware};
 use hue::error::{HueApiV1Error, HueError};
 use hue::legacy_api::ApiResourceType;
 use hyper::StatusCode;
@@ -59,7 +59,8 @@ impl ApiV1Error {
                 | HueApiV1Error::InvalidValueForParameter
                 | HueApiV1Error::ParameterNotModifiable
                 | HueApiV1Error::TooManyItemsInList
-                | HueApiV1Error::PortalConnectionIsRequired,
+                | HueApiV1Error::PortalConnectionIsRequired
+                | HueApiV1Error::LinkButtonNotPressed,
             ) => StatusCode::OK,
 
             Self::HueApiV1(HueApiV1Error::BridgeInternalError) => StatusCode::INTERNAL_SERVER_ERROR,
@@ -144,13 +145,16 @@ impl IntoResponse for ApiError {
 
             Self::AuxNotFound(_) => StatusCode::NOT_FOUND,
 
+            Self::EmptyName(_) => StatusCode::BAD_REQUEST,
+
             Self::CreateNotAllowed(_) | Self::UpdateNotAllowed(_) | Self::DeleteNotAllowed(_) => {
                 StatusCode::METHOD_NOT_ALLOWED
             }
 
             Self::CreateNotYetSupported(_)
             | Self::UpdateNotYetSupported(_)
-            | Self::DeleteNotYetSupported(_) => StatusCode::FORBIDDEN,
+            | Self::DeleteNotYetSupported(_)
+            | Self::Unauthorized => StatusCode::FORBIDDEN,
 
             _ => StatusCode::INTERNAL_SERVER_ERROR,
         };
@@ -160,14 +164,20 @@ impl IntoResponse for ApiError {
 }
 
 pub fn router(appstate: AppState) -> Router<()> {
+    let v1_auth = middleware::from_fn_with_state(appstate.clone(), auth::require_username);
+    let v2_auth = middleware::from_fn_with_state(appstate.clone(), auth::require_app_key);
+
     Router::new()
-        .nest("/api", api::router())
-        .nest("/auth", auth::router())
+        .nest("/api", api::router().route_layer(v1_auth))
+        .nest("/auth", auth::router().route_layer(v2_auth.clone()))
         .nest("/updater", updater::router())
         .nest("/licenses", licenses::router())
         .nest("/description.xml", upnp::router())
-        .nest("/clip/v2/resource", clip::router())
-        .nest("/eventstream", eventstream::router())
+        .nest(
+            "/clip/v2/resource",
+            clip::router().route_layer(v2_auth.clone()),
+        )
+        .nest("/eventstream", eventstream::router().route_layer(v2_auth))
         .nest("/bifrost", bifrost::router())
         .with_state(appstate)
         .layer(DefaultBodyLimit::max(100 * 1024 * 1024))
@@ -53,7 +53,8 @@ impl AppState {
 
         if let Ok(fd) = File::open(&config.bifrost.state_file) {
             log::debug!("Existing state file found, loading..");
-            let yaml = serde_yml::from_reader(fd)?;
+            let yaml: serde_yml::Value = serde_yml::from_reader(fd)?;
+            let has_apps = yaml.get("apps").is_some();
             let state = match State::version(&yaml)? {
                 StateVersion::V0 => {
                     log::info!("Detected state file version 0. Upgrading to new version..");
@@ -67,6 +68,13 @@ impl AppState {
                     State::from_v1(yaml)?
                 }
             };
+            if !has_apps {
+                // Before pairing was enforced, every app got the same public
+                // key. That key is not paired, so these apps must pair again.
+                log::warn!(
+                    "No paired apps found. Apps used with older versions of Bifrost must pair again, using the link button"
+                );
+            }
             res = Resources::new(swversion, state);
         } else {
             log::debug!("No state file found, initializing..");
@@ -153,7 +161,7 @@ impl AppState {
         ApiShortConfig::from_mac_and_version(mac, self.upd.lock().await.get().await)
     }
 
-    pub async fn api_config(&self, username: String) -> ApiResult<ApiConfig> {
+    pub async fn api_config(&self, whitelist: HashMap<String, Whitelist>) -> ApiResult<ApiConfig> {
         let (ui_cfg, cloud) = {
             let ui = self.hass_ui.lock().await;
             let cfg = ui.config_normalized();
@@ -176,14 +184,7 @@ impl AppState {
             timezone,
             lat: ui_cfg.hass_lat.unwrap_or_else(|| "0.0000".to_string()),
             long: ui_cfg.hass_long.unwrap_or_else(|| "0.0000".to_string()),
-            whitelist: HashMap::from([(
-                username,
-                Whitelist {
-                    create_date: Utc::now(),
-                    last_use_date: Utc::now(),
-                    name: "User#foo".to_string(),
-                },
-            )]),
+            whitelist,
             localtime,
             linkbutton,
             internet: cloud.internet,
@@ -254,3 +255,56 @@ fn map_portal_action(value: HassPortalAction) -> PortalAction {
         HassPortalAction::LinkButton => PortalAction::LinkButton,
     }
 }
+
+#[cfg(test)]
+impl AppState {
+    /// App state for tests, without certificates or services. The Home
+    /// Assistant state files go in a fresh temporary directory.
+    pub fn for_tests() -> ApiResult<Self> {
+        let dir = std::env::temp_dir().join(format!("bifrost-test-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir)?;
+        let path = |name: &str| dir.join(name).to_string_lossy().into_owned();
+
+        let config: AppConfig = serde_json::from_value(serde_json::json!({
+            "bridge": {
+                "name": "Bifrost",
+                "mac": "00:11:22:33:44:55",
+                "ipaddress": "10.0.0.2",
+                "http_port": 80,
+                "https_port": 443,
+                "entm_port": 2100,
+                "netmask": "255.255.255.0",
+                "gateway": "10.0.0.1",
+                "timezone": "UTC",
+            },
+            "bifrost": {
+                "state_file": path("state.yaml"),
+                "cert_file": path("cert.pem"),
+                "hass_ui_file": path("hass-ui.yaml"),
+                "hass_runtime_file": path("hass-runtime.yaml"),
+            },
+        }))?;
+
+        let bridge_id = hue::bridge_id(config.bridge.mac);
+        let mut res = Resources::new(hue::version::SwVersion::default(), State::new());
+        res.init(&bridge_id)?;
+        res.ensure_core_bridge_resources(&bridge_id)?;
+
+        let (svm_tx, _) = tokio::sync::mpsc::unbounded_channel();
+
+        Ok(Self {
+            upd: Arc::new(Mutex::new(VersionUpdater::with_default_version())),
+            svm: SvmClient::new(svm_tx),
+            res: Arc::new(Mutex::new(res)),
+            hass_ui: Arc::new(Mutex::new(HassUiState::load(
+                config.bifrost.hass_ui_file.clone(),
+            )?)),
+            hass_runtime: Arc::new(Mutex::new(HassRuntimeState::load(
+                config.bifrost.hass_runtime_file.clone(),
+                None,
+            )?)),
+            linkbutton_until: Arc::new(Mutex::new(None)),
+            conf: Arc::new(config),
+        })
+    }
+}
@@ -1,4 +1,4 @@
-use std::collections::{BTreeMap, BTreeSet};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 use std::io::ErrorKind;
 use std::net::{Ipv4Addr, SocketAddr};
 use std::os::fd::AsFd;
@@ -10,6 +10,7 @@ use async_trait::async_trait;
 use chrono::Utc;
 use nix::sys::socket;
 use nix::sys::socket::sockopt::RcvBuf;
+use openssl::ex_data::Index;
 use openssl::ssl::{Ssl, SslContext, SslMethod};
 use tokio::io::AsyncReadExt;
 use tokio::net::UdpSocket;
@@ -20,7 +21,7 @@ use udp_stream::{UdpListenBuilder, UdpListener, UdpStream};
 use uuid::Uuid;
 
 use bifrost_api::backend::BackendRequest;
-use hue::api::{Device, EntertainmentConfiguration, Light, RType};
+use hue::api::{Device, EntertainmentConfiguration, HueStreamKey, Light, RType};
 use hue::error::HueError;
 use hue::stream::{
     HueStreamLightsV1, HueStreamLightsV2, HueStreamPacket, HueStreamPacketV1, HueStreamPacketV2,
@@ -30,12 +31,15 @@ use svc::traits::Service;
 
 use crate::error::{ApiError, ApiResult};
 use crate::resource::Resources;
-use crate::routes::auth::STANDARD_CLIENT_KEY;
+
+/// Stream keys of the paired applications, by PSK identity
+type StreamKeys = HashMap<String, HueStreamKey>;
 
 pub struct EntertainmentService {
     addr: SocketAddr,
     udp: Option<Arc<UdpListener>>,
     ctx: Option<SslContext>,
+    keys: Index<Ssl, StreamKeys>,
     res: Arc<Mutex<Resources>>,
 }
 
@@ -45,6 +49,7 @@ impl EntertainmentService {
             addr: SocketAddr::new(addr.into(), port),
             udp: None,
             ctx: None,
+            keys: Ssl::new_ex_index()?,
             res,
         };
 
@@ -265,10 +270,23 @@ impl Service for EntertainmentService {
     async fn configure(&mut self) -> Result<(), Self::Error> {
         let mut bldr = SslContext::builder(SslMethod::dtls_server())?;
 
-        bldr.set_psk_server_callback(|_sslref, cid, psk| {
+        // The keys are looked up in a snapshot taken for each session, so
+        // revoked applications cannot start new streams.
+        let keys = self.keys;
+        bldr.set_psk_server_callback(move |sslref, cid, psk| {
             let client_id = String::from_utf8_lossy(cid.unwrap_or_default());
+            let Some(key) = sslref
+                .ex_data(keys)
+                .and_then(|keys| keys.get(client_id.as_ref()))
+            else {
+                log::warn!("Rejecting entertainment stream for unknown identity {client_id}");
+                return Ok(0);
+            };
+
             log::debug!("Setting PSK for {client_id}",);
-            STANDARD_CLIENT_KEY.write_to_slice(psk).unwrap();
+            if key.write_to_slice(psk).is_err() {
+                return Ok(0);
+            }
 
             log::trace!("psk: {}", hex::encode(&psk[..16]));
             Ok(16)
@@ -303,7 +321,8 @@ impl Service for EntertainmentService {
 
         loop {
             let (socket, _addr) = udp.accept().await?;
-            let ssl = Ssl::new(ctx)?;
+            let mut ssl = Ssl::new(ctx)?;
+            ssl.set_ex_data(self.keys, self.res.lock().await.get_stream_keys());
             let stream = SslStream::new(ssl, socket)?;
 
             match self.run_loop(stream).await {
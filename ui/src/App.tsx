@@ -111,7 +111,9 @@ function AppContent(props: { data: ReturnType<typeof useBifrostData> }) {
   const counters = useMemo(() => {
     const lights = entities.filter((e) => e.domain === 'light').length
     const switches = entities.filter((e) => e.domain === 'switch').length
-    const sensors = entities.filter((e) => e.domain === 'binary_sensor').length
+    const sensors = entities.filter(
+      (e) => e.domain === 'binary_sensor' || e.domain === 'sensor' || e.domain === 'event',
+    ).length
     const hidden = entities.filter((e) => !e.included).length
     return { lights, switches, sensors, hidden }
   }, [entities])
@@ -288,10 +290,12 @@ function AppContent(props: { data: ReturnType<typeof useBifrostData> }) {
           {tab === 'sensors' && (
             <EntitiesPage
               title="Sensors"
-              subtitle="Binary sensors mapped as Hue motion/contact sensors."
+              subtitle="Sensors mapped as Hue motion, contact, temperature and light level sensors, and event entities mapped as Hue buttons and dials."
               entities={entities}
               rooms={rooms}
-              predicate={(e) => e.domain === 'binary_sensor'}
+              predicate={(e) =>
+                e.domain === 'binary_sensor' || e.domain === 'sensor' || e.domain === 'event'
+              }
               onSetIncluded={setIncluded}
               onSetRoom={setRoom}
               onSetAlias={setAlias}
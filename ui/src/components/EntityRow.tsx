@@ -156,17 +156,25 @@ export function EntityRow(props: {
         </div>
       )}
 
-      {e.domain === 'binary_sensor' && (
+      {(e.domain === 'binary_sensor' || e.domain === 'sensor') && (
         <div className="mt-2 grid gap-2 md:grid-cols-[minmax(0,1fr)_190px]">
           <SelectField
             label="Sensor type"
             value={(e.sensor_kind || 'ignore') as string}
             onChange={(v) => props.onSetSensorKind(e, v as HassSensorKind)}
-            options={[
-              { value: 'motion', label: 'Motion sensor' },
-              { value: 'contact', label: 'Door/contact sensor' },
-              { value: 'ignore', label: 'Ignore' },
-            ]}
+            options={
+              e.domain === 'sensor'
+                ? [
+                    { value: 'temperature', label: 'Temperature sensor' },
+                    { value: 'light_level', label: 'Light level sensor' },
+                    { value: 'ignore', label: 'Ignore' },
+                  ]
+                : [
+                    { value: 'motion', label: 'Motion sensor' },
+                    { value: 'contact', label: 'Door/contact sensor' },
+                    { value: 'ignore', label: 'Ignore' },
+                  ]
+            }
           />
           <ToggleSwitch
             checked={!!e.enabled}
@@ -3,6 +3,7 @@ import type {
   HassRuntimeConfigPublic,
   HassUiConfig,
   HassUiPayload,
+  HueApp,
 } from './types'
 
 type JsonValue = unknown
@@ -90,6 +91,14 @@ export async function postLinkButton(): Promise<void> {
   await api('/bifrost/hass/linkbutton', { method: 'POST' })
 }
 
+export async function getApps(): Promise<HueApp[]> {
+  return api('/bifrost/apps')
+}
+
+export async function deleteApp(application_id: string): Promise<void> {
+  await api(`/bifrost/apps/${encodeURIComponent(application_id)}`, { method: 'DELETE' })
+}
+
 export async function postResetBridge(): Promise<void> {
   await api('/bifrost/hass/reset-bridge', { method: 'POST' })
 }
@@ -1,4 +1,4 @@
-export type HassSensorKind = 'motion' | 'contact' | 'ignore'
+export type HassSensorKind = 'motion' | 'contact' | 'temperature' | 'light_level' | 'ignore'
 export type HassSwitchMode = 'plug' | 'light'
 export type HassLightArchetype =
   | 'classic_bulb'
@@ -46,6 +46,8 @@ export interface HassRoomConfig {
   name: string
   source_area?: string | null
   auto_created: boolean
+  archetype?: string | null
+  hue_room_id?: string | null
 }
 
 export interface HassEntityPreference {
@@ -56,6 +58,8 @@ export interface HassEntityPreference {
   sensor_enabled?: boolean | null
   switch_mode?: HassSwitchMode | null
   light_archetype?: HassLightArchetype | null
+  // Hue effect name -> Home Assistant effect name
+  effect_map?: Record<string, string>
 }
 
 export interface HassUiConfig {
@@ -77,7 +81,7 @@ export interface HassUiConfig {
 
 export interface HassEntitySummary {
   entity_id: string
-  domain: 'light' | 'switch' | 'binary_sensor' | string
+  domain: 'light' | 'switch' | 'binary_sensor' | 'sensor' | 'event' | string
   name: string
   state: string
   available: boolean
@@ -93,6 +97,7 @@ export interface HassEntitySummary {
   switch_mode?: HassSwitchMode | null
   sensor_kind?: HassSensorKind | null
   light_archetype?: HassLightArchetype | null
+  effect_list?: string[]
   enabled: boolean
 }
 
@@ -124,6 +129,14 @@ export interface HassBridgeInfo {
   sync_status: HassSyncStatus
 }
 
+export interface HueApp {
+  application_id: string
+  devicetype: string
+  create_date: string
+  last_use_date: string
+  entertainment: boolean
+}
+
 export interface HassRuntimeConfigPublic {
   enabled: boolean
   url: string
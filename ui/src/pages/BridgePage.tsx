@@ -1,12 +1,15 @@
-import { useMemo, useState } from 'react'
+import { useEffect, useMemo, useState } from 'react'
 import {
+  deleteApp,
+  getApps,
   postApply,
   postLinkButton,
   postPatinaEvent,
   postResetBridge,
   postSync,
 } from '../lib/api'
-import type { HassBridgeInfo, HassUiPayload } from '../lib/types'
+import type { HassBridgeInfo, HassUiPayload, HueApp } from '../lib/types'
+import { Chip } from '../components/Chip'
 import { ConfirmDialog } from '../components/ConfirmDialog'
 import { Panel } from '../components/Panel'
 import { TactileButton } from '../components/TactileButton'
@@ -18,6 +21,15 @@ export function BridgePage(props: {
 }) {
   const [busy, setBusy] = useState<string | null>(null)
   const [confirmReset, setConfirmReset] = useState(false)
+  const [apps, setApps] = useState<HueApp[]>([])
+  const [revoke, setRevoke] = useState<HueApp | null>(null)
+
+  // follow the bridge info polling, so newly paired apps show up
+  useEffect(() => {
+    getApps()
+      .then(setApps)
+      .catch(() => {})
+  }, [props.bridge])
 
   const kv = useMemo(() => {
     const b = props.bridge
@@ -98,6 +110,42 @@ export function BridgePage(props: {
         </div>
       </Panel>
 
+      <Panel
+        title="Paired Apps"
+        subtitle="Apps paired using the bridge button. Revoke apps you no longer use."
+      >
+        {apps.length === 0 ? (
+          <div className="text-sm text-ink-1/70">No apps paired yet.</div>
+        ) : (
+          <div className="space-y-2">
+            {apps.map((app) => (
+              <div
+                key={app.application_id}
+                className="sub-panel flex flex-col gap-2 p-3 sm:flex-row sm:items-center sm:justify-between"
+              >
+                <div>
+                  <div className="flex items-center gap-2 text-[14px] font-semibold text-ink-0">
+                    {app.devicetype}
+                    {app.entertainment && <Chip>entertainment</Chip>}
+                  </div>
+                  <div className="mt-1 font-mono text-[12px] text-ink-1/70">
+                    paired {app.create_date} / last used {app.last_use_date}
+                  </div>
+                </div>
+                <TactileButton
+                  variant="danger"
+                  disabled={!!busy}
+                  onClick={() => setRevoke(app)}
+                  wearKey={`app:revoke:${app.application_id}`}
+                >
+                  Revoke
+                </TactileButton>
+              </div>
+            ))}
+          </div>
+        )}
+      </Panel>
+
       <Panel title="Sync Status" subtitle="This is about importing HA entities and areas.">
         <div className="text-sm text-ink-0">
           Sync in progress:{' '}
@@ -133,6 +181,28 @@ export function BridgePage(props: {
           })
         }
       />
+
+      <ConfirmDialog
+        open={!!revoke}
+        title="Revoke app?"
+        tone="danger"
+        confirmText="Revoke"
+        body={
+          <div className="space-y-2">
+            <div>
+              <span className="font-semibold">{revoke?.devicetype}</span> will no longer be able to
+              control the bridge.
+            </div>
+            <div>Pair it again using the bridge button to restore access.</div>
+          </div>
+        }
+        onClose={() => setRevoke(null)}
+        onConfirm={() => {
+          const app = revoke
+          setRevoke(null)
+          if (app) void run('revoke', () => deleteApp(app.application_id))
+        }}
+      />
     </div>
   )
 }